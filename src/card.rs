@@ -0,0 +1,314 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Support for importing directly from camera cards following the Design rule
+//! for Camera File system (DCF), alongside a ledger of which files were already
+//! imported from each card, stored in `catalog/.c1a/imports.json`.
+
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{io, manifest};
+
+/// Location of the import ledger, relative to the catalog root.
+const LEDGER_PATH: &str = ".c1a/imports.json";
+
+/// DCF directory names: a number from 100-999 and five free characters (e.g.
+/// `100CANON`).
+const DCF_DIR_PATTERN: &str = r"^[1-9]\d{2}[0-9A-Z_]{5}$";
+
+/// Extensions of camera-generated files which should not be imported (e.g.
+/// video thumbnails).
+const SKIPPED_EXTENSIONS: [&str; 1] = ["thm"];
+
+/// A mounted camera card.
+pub struct Card {
+  /// Directory containing `DCIM/`.
+  root:      PathBuf,
+  volume_id: String,
+}
+
+/// Identifies a single file on a card, as DCF numbers are reused after
+/// formatting.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FileRecord {
+  size:     u64,
+  modified: DateTime<Utc>,
+}
+
+/// Files already imported, by card volume ID and path relative to the card.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Ledger {
+  volumes: BTreeMap<String, BTreeMap<PathBuf, FileRecord>>,
+}
+
+impl Card {
+  /// Detects whether `path` is a camera card (or its `DCIM/` directory).
+  pub fn detect(path: impl AsRef<Path>) -> Option<Self> {
+    let path = path.as_ref();
+
+    let root = if path.join("DCIM").is_dir() {
+      path.to_path_buf()
+    } else if path.file_name().is_some_and(|n| n == "DCIM") {
+      path.parent()?.to_path_buf()
+    } else {
+      return None;
+    };
+
+    let volume_id = get_volume_id(&root);
+
+    Some(Self { root, volume_id })
+  }
+
+  /// Gets the directory containing `DCIM/`, which all card paths are relative
+  /// to.
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// Gets an identifier for the card's filesystem.
+  pub fn volume_id(&self) -> &str {
+    &self.volume_id
+  }
+
+  /// Lists all files in DCF directories which are not yet in `ledger`,
+  /// relative to the card root. Other directories (e.g. `MISC/` or
+  /// manufacturer directories) and thumbnails are skipped.
+  pub fn list_new_files(&self, ledger: &Ledger) -> Result<Vec<(PathBuf, FileRecord)>, String> {
+    let dcf_dir = Regex::new(DCF_DIR_PATTERN).unwrap();
+    let dcim = self.root.join("DCIM");
+
+    let mut dirs = fs::read_dir(&dcim)
+      .map_err(|e| format!("{}: Failed to read directory ({e}).", dcim.display()))?
+      .filter_map(Result::ok)
+      .filter(|e| e.path().is_dir())
+      .map(|e| e.file_name())
+      .collect::<Vec<_>>();
+    dirs.sort();
+
+    let mut files = Vec::new();
+
+    for dir in dirs {
+      if !dcf_dir.is_match(&dir.to_string_lossy()) {
+        log::debug!("{}: Skipping non-DCF directory.", dcim.join(&dir).display());
+        continue;
+      }
+
+      for file in manifest::list_files(dcim.join(&dir))? {
+        let path = Path::new("DCIM").join(&dir).join(file);
+
+        if path.extension().is_some_and(|e| {
+          SKIPPED_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str())
+        }) {
+          log::debug!("{}: Skipping thumbnail.", path.display());
+          continue;
+        }
+
+        let record = FileRecord::new(self.root.join(&path))?;
+
+        if ledger.contains(&self.volume_id, &path, &record) {
+          log::debug!("{}: Already imported.", path.display());
+          continue;
+        }
+
+        files.push((path, record));
+      }
+    }
+
+    Ok(files)
+  }
+}
+
+impl FileRecord {
+  /// Records the file at `path`.
+  pub fn new(path: impl AsRef<Path>) -> Result<Self, String> {
+    let path = path.as_ref();
+
+    let stat = fs::metadata(path)
+      .map_err(|e| format!("{}: Failed to read file metadata ({e}).", path.display()))?;
+    let modified = stat.modified().map_err(|e| {
+      format!(
+        "{}: Failed to read modification time ({e}).",
+        path.display()
+      )
+    })?;
+
+    Ok(Self {
+      size:     stat.len(),
+      modified: modified.into(),
+    })
+  }
+}
+
+impl Ledger {
+  /// Loads the ledger for `catalog`, or an empty one if there is none.
+  pub fn load(catalog: impl AsRef<Path>) -> Result<Self, String> {
+    let path = catalog.as_ref().join(LEDGER_PATH);
+
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    io::read_json(path)
+  }
+
+  /// Writes the ledger for `catalog`.
+  pub fn save(&self, catalog: impl AsRef<Path>) -> Result<(), String> {
+    let path = catalog.as_ref().join(LEDGER_PATH);
+
+    fs::create_dir_all(path.parent().unwrap())
+      .map_err(|e| format!("{}: Failed to create directory ({e}).", path.display()))?;
+
+    io::write_json(path, self)
+  }
+
+  /// Whether `path` on the card `volume_id` was already imported.
+  pub fn contains(&self, volume_id: &str, path: impl AsRef<Path>, record: &FileRecord) -> bool {
+    self
+      .volumes
+      .get(volume_id)
+      .and_then(|v| v.get(path.as_ref()))
+      .is_some_and(|r| r == record)
+  }
+
+  /// Records `path` on the card `volume_id` as imported.
+  pub fn record(&mut self, volume_id: &str, path: impl AsRef<Path>, record: FileRecord) {
+    self
+      .volumes
+      .entry(volume_id.to_string())
+      .or_default()
+      .insert(path.as_ref().to_path_buf(), record);
+  }
+}
+
+/// Gets an identifier for the filesystem containing `path`. This is the
+/// filesystem UUID where available, else its mount point.
+fn get_volume_id(path: impl AsRef<Path>) -> String {
+  let path = path.as_ref();
+  let path = path.canonicalize().unwrap_or(path.to_path_buf());
+
+  let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+  let Some((device, mount_point)) = mounts
+    .lines()
+    .filter_map(|l| {
+      let mut fields = l.split_whitespace();
+      Some((fields.next()?, fields.next()?.replace("\\040", " ")))
+    })
+    .filter(|(_, m)| path.starts_with(m))
+    .max_by_key(|(_, m)| m.len())
+  else {
+    return path.to_string_lossy().to_string();
+  };
+
+  let device = Path::new(device).canonicalize().ok();
+
+  fs::read_dir("/dev/disk/by-uuid")
+    .into_iter()
+    .flatten()
+    .filter_map(Result::ok)
+    .find(|e| device.is_some() && e.path().canonicalize().ok() == device)
+    .map_or(mount_point, |e| e.file_name().to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod test_detect {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn detects_card_root_and_dcim() {
+    let d = test_dir!();
+    fs::create_dir_all(d.get_path("card/DCIM")).unwrap();
+
+    assert_eq!(
+      Card::detect(d.get_path("card")).unwrap().root(),
+      d.get_path("card")
+    );
+    assert_eq!(
+      Card::detect(d.get_path("card/DCIM")).unwrap().root(),
+      d.get_path("card")
+    );
+  }
+
+  #[test]
+  fn ignores_plain_directories() {
+    let d = test_dir!();
+
+    assert!(Card::detect(d.root()).is_none());
+  }
+}
+
+#[cfg(test)]
+mod test_list_new_files {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn lists_only_dcf_media() {
+    let d = test_dir!();
+    for file in [
+      "DCIM/100CANON/IMG_0001.JPG",
+      "DCIM/100CANON/MVI_0002.MP4",
+      "DCIM/100CANON/MVI_0002.THM",
+      "DCIM/CANONMSC/M0001.CTG",
+      "MISC/AUTPRINT.MRK",
+    ] {
+      fs::create_dir_all(d.get_path(file).parent().unwrap()).unwrap();
+      fs::write(d.get_path(file), file).unwrap();
+    }
+
+    let card = Card::detect(d.root()).unwrap();
+    let files = card.list_new_files(&Ledger::default()).unwrap();
+
+    assert_eq!(files.into_iter().map(|(p, _)| p).collect::<Vec<_>>(), vec![
+      PathBuf::from("DCIM/100CANON/IMG_0001.JPG"),
+      PathBuf::from("DCIM/100CANON/MVI_0002.MP4"),
+    ]);
+  }
+
+  #[test]
+  fn skips_imported_files() {
+    let d = test_dir!();
+    fs::create_dir_all(d.get_path("DCIM/100CANON")).unwrap();
+    fs::write(d.get_path("DCIM/100CANON/IMG_0001.JPG"), "1").unwrap();
+    fs::write(d.get_path("DCIM/100CANON/IMG_0002.JPG"), "2").unwrap();
+
+    let card = Card::detect(d.root()).unwrap();
+    let mut ledger = Ledger::default();
+    let (path, record) = card.list_new_files(&ledger).unwrap().remove(0);
+    ledger.record(card.volume_id(), path, record);
+
+    assert_eq!(
+      card
+        .list_new_files(&ledger)
+        .unwrap()
+        .into_iter()
+        .map(|(p, _)| p)
+        .collect::<Vec<_>>(),
+      vec![PathBuf::from("DCIM/100CANON/IMG_0002.JPG")]
+    );
+  }
+
+  #[test]
+  fn reimports_reused_file_numbers() {
+    let d = test_dir!();
+    fs::create_dir_all(d.get_path("DCIM/100CANON")).unwrap();
+    fs::write(d.get_path("DCIM/100CANON/IMG_0001.JPG"), "1").unwrap();
+
+    let card = Card::detect(d.root()).unwrap();
+    let mut ledger = Ledger::default();
+    let (path, record) = card.list_new_files(&ledger).unwrap().remove(0);
+    ledger.record(card.volume_id(), path, record);
+
+    // Card formatted and numbering restarted.
+    fs::write(d.get_path("DCIM/100CANON/IMG_0001.JPG"), "new").unwrap();
+
+    assert_eq!(card.list_new_files(&ledger).unwrap().len(), 1);
+  }
+}
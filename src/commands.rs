@@ -2,28 +2,143 @@
 
 //! Program subcommands for managing photo/video catalog.
 
-use std::path::Path;
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  io::{self as std_io, Write},
+  path::Path,
+  time::{Duration, Instant},
+};
 
-use crate::{io, org::Organizer};
+use chrono::{TimeDelta, Utc};
+use clap::ValueEnum;
+
+use crate::{
+  card::{Card, Ledger},
+  config::Config,
+  gallery,
+  io,
+  manifest::{self, Manifest},
+  org::{CatalogStats, Organizer, Query, Report},
+  prim,
+  runs,
+  staging::Staging,
+  trash,
+  watch::Inbox,
+};
 
 pub fn exiftool_check() -> Result<(), String> {
   io::exiftool_check()
 }
 
+/// How `find` outputs the media files found.
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum FindFormat {
+  /// One absolute path per line, e.g. for `xargs`.
+  #[default]
+  Paths,
+  /// Paths alongside the tags used in the query.
+  Table,
+  /// Paths alongside all tags.
+  Json,
+}
+
+/// How `stats` outputs statistics.
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum StatsFormat {
+  /// A table per breakdown.
+  #[default]
+  Table,
+  Json,
+}
+
+/// How `run` should treat files in stage 6.
+#[derive(Clone, Copy)]
+enum Mode {
+  /// Move only files which passed validation.
+  Import,
+  /// Move only files whose names have drifted from their metadata, after
+  /// confirmation (unless `yes`).
+  Org { yes: bool },
+}
+
 /// Scans all files under `catalog`, performing various cleanup tasks. This will
-/// move files that are to be deleted to `catalog/.trash`.
-pub fn org(catalog: impl AsRef<Path>) -> Result<(), String> {
+/// move files that are to be deleted to `catalog/.trash`, and offer to move
+/// files whose names no longer match their metadata (skipping confirmation if
+/// `yes`). If `report` is `Some`, writes a JSON report of all changes there.
+pub fn org(catalog: impl AsRef<Path>, yes: bool, report: Option<&Path>) -> Result<(), String> {
   log::info!("{}: Organizing.", catalog.as_ref().display());
 
-  let trash = catalog.as_ref().join(".trash");
+  let config = Config::load(&catalog)?;
+  let trash = catalog.as_ref().join(trash::TRASH_PATH);
   let organizer = Organizer::load_catalog(&catalog, Some(trash))?;
 
-  run(organizer, catalog, true)
+  run(organizer, config, catalog, Mode::Org { yes }, report).map(|_| ())
+}
+
+/// Checks `path` (or `catalog` if `None`) without modifying any files, logging
+/// a summary of failures per rule. Errors if there are more than
+/// `max_failures` error-severity failures in total. If `report` is `Some`,
+/// writes a JSON report of all failures there.
+pub fn check(
+  catalog: impl AsRef<Path>,
+  path: Option<&Path>,
+  max_failures: usize,
+  report: Option<&Path>,
+) -> Result<(), String> {
+  let path = path.unwrap_or(catalog.as_ref());
+
+  log::info!("{}: Checking.", path.display());
+
+  let config = Config::load(&catalog)?;
+  let mut organizer = Organizer::load_catalog(path, None::<&Path>)?;
+  organizer.set_copyright_policy(config.copyright);
+  organizer.enable_plausibility_validation(config.plausibility);
+  organizer.add_validation_rules(config.rules);
+  organizer.find_bursts(&config.bursts);
+  organizer.enable_file_name_validation(&catalog);
+  organizer.verify_integrity()?;
+  let summary = organizer.check();
+
+  for (rule, severity, files) in summary.iter_rules() {
+    log::info!("{rule} ({severity:?}): {} file(s).", files.len());
+    for file in files {
+      log::debug!("{rule}: {}", file.display());
+    }
+  }
+
+  if let Some(report) = report {
+    log::info!("{}: Writing report.", report.display());
+    io::write_json(report, &organizer.into_report())?;
+  }
+
+  if summary.errors() > max_failures {
+    return Err(format!(
+      "{} check failure(s), exceeding maximum of {max_failures}.",
+      summary.errors()
+    ));
+  }
+
+  log::info!("{} check failure(s).", summary.errors());
+
+  Ok(())
 }
 
 /// Performs cleanup on `import` and then moves all *good* files to `catalog`.
-/// Other files will remain in place.
-pub fn import(catalog: impl AsRef<Path>, import: impl AsRef<Path>) -> Result<(), String> {
+/// Other files will remain in place. If `copy`, `import` is left untouched, as
+/// all work is done on copies in a staging area within `catalog`. Files which
+/// were imported are then deleted from `import` if `delete_source`. If
+/// `report` is `Some`, writes a JSON report of all changes there.
+///
+/// Camera cards (i.e. with a `DCIM/` directory) are always imported by copy,
+/// skipping any files already imported from the same card.
+pub fn import(
+  catalog: impl AsRef<Path>,
+  import: impl AsRef<Path>,
+  copy: bool,
+  delete_source: bool,
+  report: Option<&Path>,
+) -> Result<(), String> {
   let catalog = catalog.as_ref();
   let import = import.as_ref();
 
@@ -37,31 +152,205 @@ pub fn import(catalog: impl AsRef<Path>, import: impl AsRef<Path>) -> Result<(),
     catalog.display()
   );
 
-  let organizer = Organizer::import(import)?;
+  let config = Config::load(catalog)?;
+
+  if let Some(card) = Card::detect(import) {
+    return import_card(catalog, &card, config, delete_source, report);
+  }
+
+  if !copy {
+    let organizer = import_linked(import, catalog)?;
+    return run(organizer, config, catalog, Mode::Import, report).map(|_| ());
+  }
+
+  let staging = Staging::create(catalog, import, manifest::list_files(import)?)?;
+  let organizer = import_linked(staging.dir(), catalog)?;
+  let run_report = run(organizer, config, catalog, Mode::Import, report)?;
+
+  staging.finish(&run_report, delete_source).map(|_| ())
+}
+
+/// Imports all files from `card` not yet imported, via a staging area, and
+/// records those imported in the catalog's import ledger.
+fn import_card(
+  catalog: &Path,
+  card: &Card,
+  config: Config,
+  delete_source: bool,
+  report: Option<&Path>,
+) -> Result<(), String> {
+  log::info!(
+    "{}: Camera card detected (volume {}).",
+    card.root().display(),
+    card.volume_id()
+  );
+
+  let mut ledger = Ledger::load(catalog)?;
+  let files = card.list_new_files(&ledger)?;
+
+  if files.is_empty() {
+    log::info!("All files on card already imported.");
+    return Ok(());
+  }
+
+  log::info!("{} new file(s) on card.", files.len());
+
+  let staging = Staging::create(catalog, card.root(), files.iter().map(|(p, _)| p.clone()))?;
+  let organizer = import_linked(staging.dir(), catalog)?;
+  let run_report = run(organizer, config, catalog, Mode::Import, report)?;
+  let imported = staging.finish(&run_report, delete_source)?;
+
+  for (path, record) in files {
+    if imported.contains(&path) {
+      ledger.record(card.volume_id(), path, record);
+    }
+  }
 
-  run(organizer, catalog, false)
+  ledger.save(catalog)
 }
 
-/// Runs `organizer` with output to `catalog`.
-fn run(mut organizer: Organizer, catalog: impl AsRef<Path>, force_move: bool) -> Result<(), String> {
+/// Watches `inbox`, importing files into `catalog` once none have changed for
+/// `settle`. Imports wait for Live Photos to have both halves present, for up
+/// to `pair_timeout`. Files which are not imported stay in `inbox`, with the
+/// latest run's report in `inbox/.c1a_report.json`.
+pub fn watch(
+  catalog: impl AsRef<Path>,
+  inbox: impl AsRef<Path>,
+  settle: Duration,
+  pair_timeout: Duration,
+) -> Result<(), String> {
+  let catalog = catalog.as_ref();
+  let inbox = inbox.as_ref();
+
+  if inbox.starts_with(catalog) {
+    return Err("Cannot import into self.".to_string());
+  }
+
+  let watcher = Inbox::new(inbox)?;
+  let mut ready = !manifest::list_files(inbox)?.is_empty();
+  let mut waiting_since: Option<Instant> = None;
+
+  log::info!("{}: Watching for new files.", inbox.display());
+
+  loop {
+    if !ready {
+      watcher.wait_for_change()?;
+    }
+    watcher.wait_until_quiet(settle)?;
+
+    let force = waiting_since.is_some_and(|t| t.elapsed() >= pair_timeout);
+    let scanned = manifest::list_files(inbox)?
+      .into_iter()
+      .collect::<HashSet<_>>();
+
+    match import_batch(catalog, inbox, force) {
+      Ok(true) => waiting_since = None,
+      Ok(false) => {
+        waiting_since.get_or_insert_with(Instant::now);
+      }
+      Err(e) => {
+        log::error!("{e}");
+        waiting_since = None;
+      }
+    }
+
+    // Events from importing are discarded, so any files that arrived during
+    // the import are found by re-scanning instead. Files left in the inbox by
+    // the import were already scanned, so don't trigger another.
+    watcher.discard_events();
+    ready = waiting_since.is_some()
+      || manifest::list_files(inbox)?
+        .iter()
+        .any(|p| !scanned.contains(p));
+  }
+}
+
+/// Imports all files in `inbox` into `catalog`, unless any Live Photos or
+/// RAW+JPEG pairs are incomplete and not `force`. Returns whether the import
+/// ran.
+fn import_batch(catalog: &Path, inbox: &Path, force: bool) -> Result<bool, String> {
+  let config = Config::load(catalog)?;
+  let organizer = import_linked(inbox, catalog)?;
+
+  let incomplete = organizer.count_incomplete_live_photos();
+  if incomplete > 0 && !force {
+    log::info!("Waiting for {incomplete} incomplete Live Photo(s) to finish syncing.");
+    return Ok(false);
+  }
+
+  let incomplete = organizer.count_incomplete_raw_pairs();
+  if incomplete > 0 && !force {
+    log::info!("Waiting for the JPEGs of {incomplete} RAW file(s) to finish syncing.");
+    return Ok(false);
+  }
+
+  log::info!("{}: Importing into {}.", inbox.display(), catalog.display());
+
+  let report = inbox.join(".c1a_report.json");
+  run(organizer, config, catalog, Mode::Import, Some(&report))?;
+
+  Ok(true)
+}
+
+/// Loads the files in `dir` for import, linked to their partners already in
+/// `catalog` (see `Organizer::link_catalog`).
+fn import_linked(dir: &Path, catalog: &Path) -> Result<Organizer, String> {
+  let mut organizer = Organizer::import(dir)?;
+  organizer.link_catalog(catalog)?;
+  Ok(organizer)
+}
+
+/// Runs `organizer` with output to `catalog`, using settings from `config`, and
+/// optionally writing a report of the run to `report`. Returns the report.
+fn run(
+  mut organizer: Organizer,
+  config: Config,
+  catalog: impl AsRef<Path>,
+  mode: Mode,
+  report: Option<&Path>,
+) -> Result<Report, String> {
   // 1. Remove duplicates and leftovers.
 
+  organizer.apply_edit_policy(config.apple_edits)?;
   organizer.remove_live_photo_leftovers()?;
   organizer.remove_live_photo_duplicates()?;
   organizer.remove_sidecar_leftovers()?;
+  organizer.remove_companion_leftovers()?;
 
   // 2. Create sidecars for files without.
 
+  organizer.set_sidecar_naming(config.sidecar_naming);
   organizer.create_missing_sidecars()?;
+  organizer.merge_takeout_metadata()?;
+  organizer.set_video_dates(&config.video)?;
 
   // 3. Automatic metadata adjustments.
 
+  organizer.set_copyright_policy(config.copyright);
   organizer.enable_align_mwg_tags();
+  organizer.enable_set_creator_from_camera(config.cameras);
   organizer.enable_set_copyrights_from_creator();
   organizer.enable_set_location_from_gps();
   organizer.enable_set_time_zone_from_gps();
   organizer.apply_metadata_updates()?;
 
+  if let Mode::Org { yes } = mode {
+    let nonconforming = organizer.count_nonconforming_copyrights();
+
+    if nonconforming > 0
+      && (yes
+        || confirm(&format!(
+          "{nonconforming} file(s) have rights tags not matching the copyright policy. Rewrite \
+           them?"
+        ))?)
+    {
+      organizer.rewrite_copyrights()?;
+    }
+  }
+
+  organizer.find_bursts(&config.bursts);
+  organizer.write_burst_keywords()?;
+
   // 4. Metadata synchronization across files.
 
   organizer.sync_live_photo_metadata()?;
@@ -74,11 +363,692 @@ fn run(mut organizer: Organizer, catalog: impl AsRef<Path>, force_move: bool) ->
   organizer.enable_camera_validation();
   organizer.enable_date_time_validation();
   organizer.enable_location_validation();
+  organizer.enable_plausibility_validation(config.plausibility);
+  organizer.add_validation_rules(config.rules);
+  // Imports verify integrity on loading (see `Organizer::import`).
+  if let Mode::Org { .. } = mode {
+    organizer.enable_file_name_validation(&catalog);
+  }
   organizer.validate();
 
   // 6. Move/rename files.
 
-  organizer.move_and_rename_files(catalog, force_move)
+  if let Mode::Org { yes } = mode {
+    let drifted = organizer.count_drifted_media();
+
+    if drifted == 0 {
+      log::info!("All file names match metadata.");
+    } else if !yes
+      && !confirm(&format!(
+        "{drifted} file(s) do not match their metadata. Move and rename them?"
+      ))?
+    {
+      organizer.skip_drifted_media();
+    }
+  }
+
+  let force_move = matches!(mode, Mode::Org { .. });
+  let run_report = organizer.move_and_rename_files(&catalog, force_move)?;
+
+  if let Some(mut manifest) = Manifest::load(&catalog)? {
+    log::info!("Updating manifest.");
+    manifest.apply(&catalog, &run_report)?;
+    manifest.save(&catalog)?;
+  }
+
+  if let Some(path) = runs::save(&catalog, &run_report)? {
+    log::info!("{}: Recorded tag changes.", path.display());
+  }
+
+  if let Some(report) = report {
+    log::info!("{}: Writing report.", report.display());
+    io::write_json(report, &run_report)?;
+  }
+
+  Ok(run_report)
+}
+
+/// Renames all sidecars in `catalog` to the naming scheme in its config (e.g.
+/// `image.xmp` for Lightroom). If `report` is `Some`, writes a JSON report of
+/// all changes there.
+pub fn convert_sidecars(catalog: impl AsRef<Path>, report: Option<&Path>) -> Result<(), String> {
+  log::info!("{}: Converting sidecars.", catalog.as_ref().display());
+
+  let config = Config::load(&catalog)?;
+  let mut organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  organizer.set_sidecar_naming(config.sidecar_naming);
+  organizer.convert_sidecars()?;
+  let run_report = organizer.into_report();
+
+  if let Some(mut manifest) = Manifest::load(&catalog)? {
+    log::info!("Updating manifest.");
+    manifest.apply(&catalog, &run_report)?;
+    manifest.save(&catalog)?;
+  }
+
+  if let Some(report) = report {
+    log::info!("{}: Writing report.", report.display());
+    io::write_json(report, &run_report)?;
+  }
+
+  Ok(())
+}
+
+/// Prints media files in `catalog` whose metadata matches `query` (see
+/// `Query`) to stdout, in `format`.
+pub fn find(catalog: impl AsRef<Path>, query: &str, format: FindFormat) -> Result<(), String> {
+  let query = Query::parse(query)?;
+  let organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  let found = organizer.find(&query);
+
+  match format {
+    FindFormat::Paths => {
+      for handle in &found {
+        println!("{}", organizer.get_media_path(*handle).display());
+      }
+    }
+    FindFormat::Table => {
+      let tags = query.get_tags();
+      let rows = found
+        .iter()
+        .map(|h| {
+          let metadata = organizer.get_media_metadata(*h);
+          [organizer.get_media_path(*h).display().to_string()]
+            .into_iter()
+            .chain(tags.iter().map(|t| metadata.get(t).unwrap_or_default()))
+            .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+      let header = ["Path"]
+        .into_iter()
+        .chain(tags.iter().copied())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+      print_table(&header, &rows);
+    }
+    FindFormat::Json => {
+      let files = found
+        .iter()
+        .map(|h| {
+          serde_json::json!({
+            "path": organizer.get_media_path(*h),
+            "tags": organizer
+              .get_media_metadata(*h)
+              .iter_tags()
+              .collect::<BTreeMap<_, _>>(),
+          })
+        })
+        .collect::<Vec<_>>();
+
+      println!(
+        "{}",
+        serde_json::to_string_pretty(&files)
+          .map_err(|e| format!("Failed to serialize JSON ({e})."))?
+      );
+    }
+  }
+
+  log::info!("{} file(s) found.", found.len());
+
+  Ok(())
+}
+
+/// Prints statistics on the composition and health of `catalog` (see
+/// `Organizer::stats`) to stdout, in `format`. Validation uses the same checks
+/// as `check`, except for file names and integrity.
+pub fn stats(catalog: impl AsRef<Path>, format: StatsFormat) -> Result<(), String> {
+  let config = Config::load(&catalog)?;
+  let mut organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  organizer.set_copyright_policy(config.copyright);
+  organizer.enable_plausibility_validation(config.plausibility);
+  organizer.add_validation_rules(config.rules);
+  organizer.enable_attribution_validation();
+  organizer.enable_camera_validation();
+  organizer.enable_date_time_validation();
+  organizer.enable_location_validation();
+  let stats = organizer.stats();
+
+  match format {
+    StatsFormat::Table => print_stats(&stats),
+    StatsFormat::Json => println!(
+      "{}",
+      serde_json::to_string_pretty(&stats)
+        .map_err(|e| format!("Failed to serialize JSON ({e})."))?
+    ),
+  }
+
+  Ok(())
+}
+
+/// Writes the locations of media files in `catalog` with GPS positions to
+/// `output`, as KML if it has a `.kml` extension, else `GeoJSON`. Files are
+/// filtered by `query` (see `Query`) and `date` (e.g. `2023-05..2023-08`).
+/// Points within `cluster` meters are merged, and if `tracks`, a track is drawn
+/// for each day.
+pub fn export_geo(
+  catalog: impl AsRef<Path>,
+  output: &Path,
+  query: Option<&str>,
+  date: Option<&str>,
+  cluster: Option<f64>,
+  tracks: bool,
+) -> Result<(), String> {
+  let query = ["has:GPSPosition".to_string()]
+    .into_iter()
+    .chain(query.map(|q| format!("({q})")))
+    .chain(date.map(|d| format!("date:{d}")))
+    .collect::<Vec<_>>()
+    .join(" and ");
+  let query = Query::parse(&query)?;
+
+  let organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  let points = organizer
+    .find(&query)
+    .into_iter()
+    .filter_map(|h| {
+      let metadata = organizer.get_media_metadata(h);
+      let (lat, lon) = metadata.get_lat_lon()?;
+      Some(prim::GeoPoint {
+        path: organizer.get_media_path(h),
+        lat,
+        lon,
+        time: metadata.get_date_time_original(),
+        city: metadata.city.clone(),
+      })
+    })
+    .collect::<Vec<_>>();
+
+  let features = prim::get_geo_features(&points, cluster, tracks);
+
+  log::info!(
+    "{}: Writing {} location(s) as {} feature(s).",
+    output.display(),
+    points.len(),
+    features.len()
+  );
+
+  if output
+    .extension()
+    .is_some_and(|e| e.eq_ignore_ascii_case("kml"))
+  {
+    fs::write(output, prim::to_kml(&features))
+      .map_err(|e| format!("{}: Failed to write file ({e}).", output.display()))
+  } else {
+    io::write_json(output, &prim::to_geojson(&features))
+  }
+}
+
+/// Writes a static HTML gallery of media files in `catalog` matching `query`
+/// (see `Query`) to `dir_out`, laid out by year and month as in the catalog.
+pub fn gallery(catalog: impl AsRef<Path>, query: &str, dir_out: &Path) -> Result<(), String> {
+  let query = Query::parse(query)?;
+
+  fs::create_dir_all(dir_out)
+    .map_err(|e| format!("{}: Failed to create directory ({e}).", dir_out.display()))?;
+  let dir_out = dir_out
+    .canonicalize()
+    .map_err(|e| format!("{}: Failed to resolve path ({e}).", dir_out.display()))?;
+
+  // Previews lack metadata, so would fail validation if organized.
+  if catalog
+    .as_ref()
+    .canonicalize()
+    .is_ok_and(|c| dir_out.starts_with(c))
+  {
+    return Err(format!(
+      "{}: Gallery cannot be within the catalog.",
+      dir_out.display()
+    ));
+  }
+
+  let organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  let items = organizer.get_gallery_items(&organizer.find(&query));
+
+  log::info!("{}: Writing gallery.", dir_out.display());
+
+  let count = gallery::build(&dir_out, &items)?;
+
+  log::info!("{count} file(s) in gallery.");
+
+  Ok(())
+}
+
+/// Writes tags to media files in `catalog`, routed through their sidecars (see
+/// `Organizer::set_tags`). `args` holds `Tag=Value` assignments, and either a
+/// query (see `Query`) or paths to media files. Old values are journaled for
+/// `set_undo`. If `report` is `Some`, writes a JSON report of all changes
+/// there.
+pub fn set(
+  catalog: impl AsRef<Path>,
+  args: &[String],
+  write_media: bool,
+  report: Option<&Path>,
+) -> Result<(), String> {
+  let (tags, targets): (Vec<_>, Vec<_>) = args.iter().partition(|a| parse_assignment(a).is_some());
+  let tags = tags
+    .into_iter()
+    .filter_map(|a| parse_assignment(a))
+    .collect::<Vec<_>>();
+
+  if tags.is_empty() {
+    return Err("No `Tag=Value` assignments given.".to_string());
+  }
+
+  let mut organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+
+  let handles = match targets.as_slice() {
+    [] => return Err("No query or paths given.".to_string()),
+    [query] if !Path::new(query).exists() => organizer.find(&Query::parse(query)?),
+    paths => paths
+      .iter()
+      .map(|p| {
+        let path = Path::new(p)
+          .canonicalize()
+          .map_err(|e| format!("{p}: Failed to resolve path ({e})."))?;
+        organizer
+          .find_media(&path)
+          .ok_or(format!("{p}: Not a media file in the catalog."))
+      })
+      .collect::<Result<Vec<_>, _>>()?,
+  };
+
+  let mut edits = Vec::new();
+  let result = organizer.set_tags(&handles, &tags, write_media, &mut edits);
+  let run_report = organizer.into_report();
+
+  if result.is_err() && edits.is_empty() {
+    return result;
+  }
+
+  // Saving edits even if some failed, so those already written can be undone.
+  runs::save_edits(&catalog, run_report.get_run(), &edits)?;
+  log::info!(
+    "{} file(s) edited. Undo with `c1a set --undo {}`.",
+    edits.len(),
+    run_report.get_run()
+  );
+
+  finish_edit(catalog, &run_report, report)?;
+
+  result
+}
+
+/// Restores tags edited by `set` during `run` (e.g. `20250101T120000000`) in
+/// `catalog`. If `report` is `Some`, writes a JSON report of all changes there.
+pub fn set_undo(catalog: impl AsRef<Path>, run: &str, report: Option<&Path>) -> Result<(), String> {
+  let edits = runs::load_edits(&catalog, run)?;
+
+  let mut organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  organizer.undo_tag_edits(&edits)?;
+
+  finish_edit(catalog, &organizer.into_report(), report)
+}
+
+/// Records the tags changed by `set` or `set_undo`, as recorded in
+/// `run_report`, in `catalog`'s runs and manifest, and in `report`.
+fn finish_edit(
+  catalog: impl AsRef<Path>,
+  run_report: &Report,
+  report: Option<&Path>,
+) -> Result<(), String> {
+  runs::save(&catalog, run_report)?;
+
+  if let Some(mut manifest) = Manifest::load(&catalog)? {
+    log::info!("Updating manifest.");
+    manifest.apply(&catalog, run_report)?;
+    manifest.save(&catalog)?;
+  }
+
+  if let Some(report) = report {
+    log::info!("{}: Writing report.", report.display());
+    io::write_json(report, run_report)?;
+  }
+
+  Ok(())
+}
+
+/// Parses `arg` as a `Tag=Value` assignment, where `Tag` may have a group
+/// (e.g. `XMP-dc:Creator`). Comparisons (e.g. `Rating==5`) are not assignments.
+fn parse_assignment(arg: &str) -> Option<(String, String)> {
+  let (tag, value) = arg.split_once('=')?;
+
+  let is_tag = !tag.is_empty()
+    && tag
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || [':', '-', '_'].contains(&c));
+
+  (is_tag && !value.starts_with('=')).then(|| (tag.to_string(), value.to_string()))
+}
+
+/// Logs the tags differing between `file_a` and `file_b`, ignoring which
+/// group (e.g. EXIF or XMP) each is in.
+pub fn diff_files(file_a: impl AsRef<Path>, file_b: impl AsRef<Path>) -> Result<(), String> {
+  let tags_a = io::read_tags(file_a)?;
+  let tags_b = io::read_tags(file_b)?;
+
+  let diffs = prim::diff_tags(
+    &prim::normalize_tags(tags_a.iter().map(|(t, v)| (t.as_str(), v.clone()))),
+    &prim::normalize_tags(tags_b.iter().map(|(t, v)| (t.as_str(), v.clone()))),
+  );
+
+  for diff in &diffs {
+    log::info!("{diff}");
+  }
+  log::info!("{} tag(s) differ.", diffs.len());
+
+  Ok(())
+}
+
+/// Logs the tags changed in each file during `run` (e.g. `20250101T120000000`)
+/// over `catalog`.
+pub fn diff_run(catalog: impl AsRef<Path>, run: &str) -> Result<(), String> {
+  let files = runs::load(catalog, run)?;
+
+  for file in &files {
+    match &file.final_path {
+      Some(final_path) => log::info!("{} -> {}:", file.path.display(), final_path.display()),
+      None => log::info!("{}:", file.path.display()),
+    }
+    for diff in &file.tag_diffs {
+      log::info!("  {diff}");
+    }
+  }
+  log::info!("{} file(s) changed.", files.len());
+
+  Ok(())
+}
+
+/// Lists every camera files in `catalog` were shot with, by number of files
+/// and owner (from the catalog's config), to help build the camera owner table.
+pub fn cameras(catalog: impl AsRef<Path>) -> Result<(), String> {
+  let config = Config::load(&catalog)?;
+  let organizer = Organizer::load_catalog(&catalog, None::<&Path>)?;
+  let cameras = organizer.count_cameras(&config.cameras);
+
+  for (camera, count) in &cameras {
+    let owners = count.owners.iter().cloned().collect::<Vec<_>>();
+    match owners.as_slice() {
+      [] => log::info!("{camera}: {} file(s), no owner.", count.files),
+      [owner] => log::info!("{camera}: {} file(s), owned by {owner}.", count.files),
+      _ => log::warn!(
+        "{camera}: {} file(s), ambiguous owners ({}).",
+        count.files,
+        owners.join(", ")
+      ),
+    }
+  }
+
+  log::info!("{} camera(s).", cameras.len());
+
+  Ok(())
+}
+
+/// Writes a checksum manifest of all files in `catalog`, replacing any existing
+/// one. Once written, `org` and `import` keep it up to date.
+pub fn manifest(catalog: impl AsRef<Path>) -> Result<(), String> {
+  log::info!("{}: Writing manifest.", catalog.as_ref().display());
+
+  let manifest = Manifest::build(&catalog)?;
+  manifest.save(&catalog)?;
+
+  log::info!("{} file(s) in manifest.", manifest.len());
+
+  Ok(())
+}
+
+/// Compares `catalog` against its manifest, logging files that are corrupted
+/// (content changed without modification time or size changing), modified,
+/// missing or unexpected. Errors if any are corrupted, missing or unexpected.
+pub fn verify(catalog: impl AsRef<Path>) -> Result<(), String> {
+  log::info!(
+    "{}: Verifying against manifest.",
+    catalog.as_ref().display()
+  );
+
+  let manifest = Manifest::load(&catalog)?
+    .ok_or("No manifest found. Create one with `c1a manifest`.".to_string())?;
+  let verification = manifest.verify(&catalog)?;
+
+  for path in &verification.corrupted {
+    log::error!(
+      "{}: Content changed without being modified (bit-rot).",
+      path.display()
+    );
+  }
+  for path in &verification.missing {
+    log::warn!("{}: Missing.", path.display());
+  }
+  for path in &verification.extra {
+    log::warn!("{}: Not in manifest.", path.display());
+  }
+  for path in &verification.modified {
+    log::info!("{}: Modified since manifest was written.", path.display());
+  }
+
+  if !verification.is_ok() {
+    return Err(format!(
+      "{} corrupted, {} missing and {} unexpected file(s).",
+      verification.corrupted.len(),
+      verification.missing.len(),
+      verification.extra.len()
+    ));
+  }
+
+  log::info!("{} file(s) verified.", manifest.len());
+
+  Ok(())
+}
+
+/// Lists all files in the catalog's trash, grouped by run and reason.
+pub fn trash_list(catalog: impl AsRef<Path>) -> Result<(), String> {
+  let journal = trash::read_journal(catalog.as_ref().join(trash::TRASH_PATH))?;
+
+  let mut groups = BTreeMap::<_, Vec<_>>::new();
+  for entry in &journal {
+    groups
+      .entry((entry.run.as_str(), entry.reason.as_str()))
+      .or_default()
+      .push(&entry.path);
+  }
+
+  for ((run, reason), paths) in groups {
+    log::info!("{run} ({reason}): {} file(s).", paths.len());
+    for path in paths {
+      log::info!("  {}", path.display());
+    }
+  }
+
+  log::info!("{} trashed file(s).", journal.len());
+
+  Ok(())
+}
+
+/// Restores files from the catalog's trash to their original paths. `target`
+/// is either a run, or a path within the trash.
+pub fn trash_restore(catalog: impl AsRef<Path>, target: &str) -> Result<(), String> {
+  let catalog = catalog.as_ref();
+
+  let restored = trash::restore(catalog, catalog.join(trash::TRASH_PATH), target)?;
+
+  for path in &restored {
+    log::debug!("{}: Restored.", path.display());
+  }
+  log::info!("{} file(s) restored.", restored.len());
+
+  Ok(())
+}
+
+/// Permanently deletes files trashed more than `older_than` ago.
+pub fn trash_purge(catalog: impl AsRef<Path>, older_than: TimeDelta) -> Result<(), String> {
+  let purged = trash::purge(
+    catalog.as_ref().join(trash::TRASH_PATH),
+    Utc::now() - older_than,
+  )?;
+
+  for path in &purged {
+    log::debug!("{}: Deleted.", path.display());
+  }
+  log::info!("{} file(s) permanently deleted.", purged.len());
+
+  Ok(())
+}
+
+/// Prints `rows` to stdout under `header`, with columns aligned.
+fn print_table(header: &[String], rows: &[Vec<String>]) {
+  let widths = header
+    .iter()
+    .enumerate()
+    .map(|(i, h)| {
+      rows
+        .iter()
+        .map(|r| r[i].chars().count())
+        .chain([h.chars().count()])
+        .max()
+        .unwrap_or_default()
+    })
+    .collect::<Vec<_>>();
+
+  for row in [header].into_iter().chain(rows.iter().map(Vec::as_slice)) {
+    let line = row
+      .iter()
+      .zip(&widths)
+      .map(|(cell, width)| format!("{cell:width$}"))
+      .collect::<Vec<_>>()
+      .join("  ");
+    println!("{}", line.trim_end());
+  }
+}
+
+/// Prints `stats` as a table per breakdown, followed by health summaries.
+#[allow(clippy::cast_precision_loss)]
+fn print_stats(stats: &CatalogStats) {
+  for (name, counts) in [
+    ("Year", &stats.by_year),
+    ("Month", &stats.by_month),
+    ("File Type", &stats.by_file_type),
+    ("Codec", &stats.by_codec),
+    ("Camera", &stats.by_camera),
+    ("Creator", &stats.by_creator),
+    ("Country", &stats.by_country),
+    ("City", &stats.by_city),
+  ] {
+    let rows = counts
+      .iter()
+      .map(|(key, count)| {
+        vec![
+          key.clone(),
+          count.files.to_string(),
+          format_size(count.bytes),
+        ]
+      })
+      .collect::<Vec<_>>();
+    print_table(&[name, "Files", "Size"].map(str::to_string), &rows);
+    println!();
+  }
+
+  let live_photos = &stats.live_photos;
+  let sidecars = &stats.sidecars;
+  let mut rows = vec![
+    ("Media files", stats.total.files),
+    ("Live Photo pairs", live_photos.pairs),
+    ("Live Photos missing video", live_photos.missing_video),
+    ("Live Photos missing image", live_photos.missing_image),
+    ("Live Photos duplicated", live_photos.duplicates),
+    ("Media with sidecar", sidecars.with_sidecar),
+    ("Media without sidecar", sidecars.without_sidecar),
+    ("darktable duplicates", sidecars.dupes),
+    ("Orphaned sidecars", sidecars.orphaned),
+  ];
+  if let Some(validation) = &stats.validation {
+    rows.push(("Passed validation", validation.passed));
+    rows.push(("Failed validation", validation.failed));
+    rows.extend(
+      validation
+        .by_rule
+        .iter()
+        .map(|(rule, files)| (rule.as_str(), *files)),
+    );
+  }
+
+  let rows = rows
+    .into_iter()
+    .map(|(name, files)| vec![name.to_string(), files.to_string()])
+    .collect::<Vec<_>>();
+  print_table(&["Health", "Files"].map(str::to_string), &rows);
+
+  if let Some(validation) = &stats.validation
+    && stats.total.files > 0
+  {
+    println!(
+      "\n{:.1}% passed validation.",
+      validation.passed as f64 * 100.0 / stats.total.files as f64
+    );
+  }
+}
+
+/// Formats `bytes` in decimal units (e.g. `1.5 GB`).
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+  let mut size = bytes as f64;
+  let mut units = ["B", "kB", "MB", "GB", "TB"].into_iter().peekable();
+
+  while size >= 1000.0 && units.len() > 1 {
+    size /= 1000.0;
+    units.next();
+  }
+
+  match units.peek() {
+    Some(&"B") => format!("{bytes} B"),
+    unit => format!("{size:.1} {}", unit.unwrap_or(&"TB")),
+  }
+}
+
+/// Asks the user a yes or no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> Result<bool, String> {
+  print!("{question} [y/N] ");
+  std_io::stdout()
+    .flush()
+    .map_err(|e| format!("Failed to write prompt ({e})."))?;
+
+  let mut answer = String::new();
+  std_io::stdin()
+    .read_line(&mut answer)
+    .map_err(|e| format!("Failed to read answer ({e})."))?;
+
+  Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod test_format_size {
+  use super::*;
+
+  #[test]
+  fn scales_units() {
+    assert_eq!(format_size(999), "999 B");
+    assert_eq!(format_size(1_500), "1.5 kB");
+    assert_eq!(format_size(2_340_000_000), "2.3 GB");
+  }
+}
+
+#[cfg(test)]
+mod test_parse_assignment {
+  use super::*;
+
+  #[test]
+  fn parses_assignments_only() {
+    assert_eq!(
+      parse_assignment("XMP-dc:Creator=Jane Doe"),
+      Some(("XMP-dc:Creator".to_string(), "Jane Doe".to_string()))
+    );
+    assert_eq!(
+      parse_assignment("Label="),
+      Some(("Label".to_string(), String::new()))
+    );
+    assert_eq!(parse_assignment("Rating==5"), None);
+    assert_eq!(parse_assignment("Country == Japan"), None);
+    assert_eq!(parse_assignment("/path/to/image.jpg"), None);
+  }
 }
 
 #[cfg(test)]
@@ -93,7 +1063,7 @@ mod test_import {
     );
 
     assert_err!(
-      import(d.root(), d.get_path("import")),
+      import(d.root(), d.get_path("import"), false, false, None),
       "Cannot import into self."
     );
   }
@@ -0,0 +1,223 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Per-catalog configuration, stored in `catalog/.c1a/config.json`.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+  io,
+  org::{BurstConfig, EditPolicy, PlausibilityConfig, Rule, VideoConfig},
+  prim::{CameraOwner, CopyrightConfig, SidecarNaming},
+};
+
+/// Location of the config file, relative to the catalog root. This is within a
+/// hidden directory so `ExifTool` does not scan it.
+const CONFIG_PATH: &str = ".c1a/config.json";
+
+/// User configuration for a catalog. Any missing sections use their defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  /// Validation rules, checked in addition to the built-in checks.
+  pub rules:          Vec<Rule>,
+  /// Bounds for date and location plausibility checks.
+  pub plausibility:   PlausibilityConfig,
+  /// Which versions of Apple Photos edits to keep.
+  pub apple_edits:    EditPolicy,
+  /// How to read video capture dates.
+  pub video:          VideoConfig,
+  /// How to group and lay out bursts.
+  pub bursts:         BurstConfig,
+  /// Which editors' scheme sidecars are named by.
+  pub sidecar_naming: SidecarNaming,
+  /// How to derive rights tags (e.g. `Copyright`) for each file.
+  pub copyright:      CopyrightConfig,
+  /// Owners of cameras, to set `Creator` from.
+  pub cameras:        Vec<CameraOwner>,
+}
+
+impl Config {
+  /// Loads the config for `catalog`, or the default config if there is none.
+  pub fn load(catalog: impl AsRef<Path>) -> Result<Self, String> {
+    let path = get_path(catalog);
+
+    if !path.exists() {
+      log::debug!("{}: No config found, using defaults.", path.display());
+      return Ok(Self::default());
+    }
+
+    log::info!("{}: Loading config.", path.display());
+
+    io::read_json(path)
+  }
+}
+
+/// Gets the path to the config file for `catalog`.
+fn get_path(catalog: impl AsRef<Path>) -> PathBuf {
+  catalog.as_ref().join(CONFIG_PATH)
+}
+
+#[cfg(test)]
+mod test_load {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn defaults_if_missing() {
+    let d = test_dir!();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert!(config.rules.is_empty());
+  }
+
+  #[test]
+  fn loads_rules() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(
+      d.get_path(CONFIG_PATH),
+      r#"{
+        "rules": [
+          {
+            "name": "copyright_year",
+            "severity": "warning",
+            "type": "matches",
+            "tag": "Copyright",
+            "pattern": "^Copyright \\d{4} .+"
+          },
+          {
+            "name": "rating",
+            "type": "required",
+            "tags": ["Rating"],
+            "file_types": ["JPEG"]
+          }
+        ]
+      }"#,
+    )
+    .unwrap();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert_eq!(config.rules.len(), 2);
+    assert_eq!(config.rules[0].name, "copyright_year");
+  }
+
+  #[test]
+  fn loads_plausibility_bounds() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(
+      d.get_path(CONFIG_PATH),
+      r#"{ "plausibility": { "reset_dates": ["2010-01-01"], "future_hours": 48 } }"#,
+    )
+    .unwrap();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert_eq!(config.plausibility.reset_dates.len(), 1);
+    assert_eq!(config.plausibility.future_hours, 48);
+    assert!(config.plausibility.time_zone);
+  }
+
+  #[test]
+  fn loads_edit_policy() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(d.get_path(CONFIG_PATH), r#"{ "apple_edits": "edited" }"#).unwrap();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert_eq!(config.apple_edits, EditPolicy::Edited);
+  }
+
+  #[test]
+  fn loads_sidecar_naming() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(
+      d.get_path(CONFIG_PATH),
+      r#"{ "sidecar_naming": "lightroom" }"#,
+    )
+    .unwrap();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert_eq!(config.sidecar_naming, SidecarNaming::Stem);
+  }
+
+  #[test]
+  fn loads_copyright_policy() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(
+      d.get_path(CONFIG_PATH),
+      r#"{
+        "copyright": {
+          "template": "© {year} {creator}",
+          "creators": { "Other": { "usage_terms": "CC BY 4.0" } }
+        }
+      }"#,
+    )
+    .unwrap();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert_eq!(
+      config.copyright.terms.template.as_deref(),
+      Some("© {year} {creator}")
+    );
+    assert_eq!(
+      config.copyright.creators["Other"].usage_terms.as_deref(),
+      Some("CC BY 4.0")
+    );
+  }
+
+  #[test]
+  fn loads_camera_owners() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(
+      d.get_path(CONFIG_PATH),
+      r#"{
+        "cameras": [
+          { "owner": "Owner", "make": "Canon", "serial_number": "012345" },
+          { "owner": "Other", "host_computer": "iPhone 15 Pro" }
+        ]
+      }"#,
+    )
+    .unwrap();
+
+    let config = Config::load(d.root()).unwrap();
+
+    assert_eq!(config.cameras.len(), 2);
+    assert_eq!(config.cameras[0].serial_number.as_deref(), Some("012345"));
+    assert_eq!(config.cameras[1].owner, "Other");
+  }
+
+  #[test]
+  fn errors_on_camera_without_owner() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(
+      d.get_path(CONFIG_PATH),
+      r#"{ "cameras": [{ "make": "Canon" }] }"#,
+    )
+    .unwrap();
+
+    assert_err!(Config::load(d.root()), "missing field `owner`");
+  }
+
+  #[test]
+  fn errors_on_invalid_config() {
+    let d = test_dir!();
+    fs::create_dir(d.get_path(".c1a")).unwrap();
+    fs::write(d.get_path(CONFIG_PATH), r#"{ "rules": [{ "name": "x" }] }"#).unwrap();
+
+    assert_err!(Config::load(d.root()), "Failed to parse JSON");
+  }
+}
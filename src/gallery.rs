@@ -0,0 +1,407 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Static HTML gallery of media files, laid out by year and month as in the
+//! catalog, using previews embedded in each file rather than re-encoding.
+
+use std::{
+  collections::{BTreeMap, HashSet},
+  fmt::Write,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::io;
+
+/// Embedded images to show on photo pages for formats browsers cannot show, in
+/// order of preference.
+const PREVIEW_TAGS: [&str; 3] = ["JpgFromRaw", "PreviewImage", "ThumbnailImage"];
+
+/// Embedded images to show on month pages, in order of preference.
+const THUMBNAIL_TAGS: [&str; 2] = ["ThumbnailImage", "PreviewImage"];
+
+/// Extensions browsers can show directly, copied as is to photo pages.
+const BROWSER_IMAGE_EXTS: [&str; 6] = ["jpg", "jpeg", "png", "gif", "webp", "avif"];
+
+/// Directory for media files without a date.
+const UNDATED_PATH: &str = "undated";
+
+/// Shared page style.
+const STYLE: &str = concat!(
+  "body{font-family:sans-serif;margin:2em auto;max-width:1200px;padding:0 1em}",
+  ".grid{display:flex;flex-wrap:wrap;gap:8px}",
+  ".grid a{display:block;width:160px;height:160px;background:#eee;overflow:hidden}",
+  ".grid img{width:100%;height:100%;object-fit:cover}",
+  "main img,main video{max-width:100%;max-height:80vh}",
+  "dt{font-weight:bold}",
+);
+
+/// A media file to include in a gallery.
+#[derive(Debug, Default)]
+pub struct GalleryItem {
+  /// Absolute path.
+  pub path:             PathBuf,
+  /// Where in the gallery, without extension, as it would be in the catalog
+  /// (e.g. `2023/05/230501_120000000`, in local time as `io::move_file` names
+  /// files). `None` if there is no date.
+  pub stem:             Option<String>,
+  pub is_video:         bool,
+  pub caption:          Option<String>,
+  pub date:             Option<String>,
+  pub location:         Option<String>,
+  pub camera:           Option<String>,
+  /// Absolute path to the paired Live Photo video, if any.
+  pub live_photo_video: Option<PathBuf>,
+}
+
+/// An item written to the gallery, alongside the names of its files.
+struct Entry<'a> {
+  item:      &'a GalleryItem,
+  name:      String,
+  image:     Option<String>,
+  thumbnail: Option<String>,
+  video:     Option<String>,
+}
+
+/// Writes a gallery of `items` to `dir_out`, with an index of months, a page
+/// per month and a page per item. Returns the number of items written.
+pub fn build(dir_out: impl AsRef<Path>, items: &[GalleryItem]) -> Result<usize, String> {
+  let dir_out = dir_out.as_ref();
+  let mut months = BTreeMap::<_, Vec<_>>::new();
+
+  let stems = items
+    .iter()
+    .map(|item| {
+      item.stem.clone().unwrap_or_else(|| {
+        Path::new(UNDATED_PATH)
+          .join(item.path.file_stem().unwrap_or_default())
+          .display()
+          .to_string()
+      })
+    })
+    .collect::<Vec<_>>();
+  let reserved = stems.iter().cloned().collect::<HashSet<_>>();
+  let mut taken = HashSet::new();
+
+  for (item, stem) in items.iter().zip(stems) {
+    // Bursts and files without sub-second times can share a stem. Numbered
+    // names skip any stem another item has.
+    let mut name = stem.clone();
+    let mut n = 1;
+    while taken.contains(&name) || (n > 1 && reserved.contains(&name)) {
+      n += 1;
+      name = format!("{stem}_{n}");
+    }
+    taken.insert(name.clone());
+    let stem = PathBuf::from(name);
+
+    let month = stem.parent().map(Path::to_path_buf).unwrap_or_default();
+    fs::create_dir_all(dir_out.join(&month))
+      .map_err(|e| format!("{}: Failed to create directory ({e}).", month.display()))?;
+
+    let entry = write_files(dir_out, &stem, item)?;
+    months.entry(month).or_default().push(entry);
+  }
+
+  for (month, entries) in &mut months {
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    let root = "../".repeat(month.components().count());
+
+    for (i, entry) in entries.iter().enumerate() {
+      let prev = i.checked_sub(1).and_then(|i| entries.get(i));
+      let next = entries.get(i + 1);
+      write_page(
+        dir_out.join(month).join(format!("{}.html", entry.name)),
+        &render_item(entry, prev, next, &root),
+      )?;
+    }
+
+    write_page(
+      dir_out.join(month).join("index.html"),
+      &render_month(month, entries, &root),
+    )?;
+  }
+
+  let counts = months
+    .iter()
+    .map(|(m, e)| (m.display().to_string(), e.len()))
+    .collect::<Vec<_>>();
+  write_page(dir_out.join("index.html"), &render_index(&counts))?;
+
+  Ok(items.len())
+}
+
+/// Writes the preview, thumbnail and video of `item` next to where its page
+/// will be, at `stem` within `dir_out`.
+fn write_files<'a>(
+  dir_out: &Path,
+  stem: &Path,
+  item: &'a GalleryItem,
+) -> Result<Entry<'a>, String> {
+  let name = stem.file_name().unwrap_or_default().display().to_string();
+  let mut entry = Entry {
+    item,
+    name: name.clone(),
+    image: None,
+    thumbnail: None,
+    video: None,
+  };
+
+  let copy = |src: &Path, suffix: &str| -> Result<String, String> {
+    let ext = src.extension().unwrap_or_default().display().to_string();
+    let file_name = format!("{name}{suffix}.{}", ext.to_lowercase());
+    fs::copy(src, dir_out.join(stem).with_file_name(&file_name))
+      .map_err(|e| format!("{}: Failed to copy file ({e}).", src.display()))?;
+    Ok(file_name)
+  };
+
+  let write = |image: &[u8], suffix: &str| -> Result<String, String> {
+    let file_name = format!("{name}{suffix}.jpg");
+    let path = dir_out.join(stem).with_file_name(&file_name);
+    fs::write(&path, image)
+      .map_err(|e| format!("{}: Failed to write file ({e}).", path.display()))?;
+    Ok(file_name)
+  };
+
+  if item.is_video {
+    entry.video = Some(copy(&item.path, "")?);
+  } else if item.path.extension().is_some_and(|e| {
+    BROWSER_IMAGE_EXTS.contains(&e.to_ascii_lowercase().to_str().unwrap_or_default())
+  }) {
+    entry.image = Some(copy(&item.path, "")?);
+  } else if let Some(preview) = io::extract_image(&item.path, &PREVIEW_TAGS)? {
+    entry.image = Some(write(&preview, "")?);
+  } else {
+    log::warn!("{}: No preview to show in gallery.", item.path.display());
+  }
+
+  entry.thumbnail = match io::extract_image(&item.path, &THUMBNAIL_TAGS)? {
+    Some(thumbnail) => Some(write(&thumbnail, "_thumb")?),
+    None => entry.image.clone(),
+  };
+
+  if let Some(video) = &item.live_photo_video {
+    entry.video = Some(copy(video, "_live")?);
+  }
+
+  Ok(entry)
+}
+
+/// Writes `html` to `path`.
+fn write_page(path: impl AsRef<Path>, html: &str) -> Result<(), String> {
+  let path = path.as_ref();
+  fs::write(path, html).map_err(|e| format!("{}: Failed to write file ({e}).", path.display()))
+}
+
+/// Renders the gallery index, linking to each month (e.g. `2023/05`)
+/// alongside its number of items.
+fn render_index(months: &[(String, usize)]) -> String {
+  let mut body = String::from("<h1>Gallery</h1>\n<ul>\n");
+
+  for (month, count) in months {
+    let _ = writeln!(
+      body,
+      "<li><a href=\"{}/index.html\">{}</a> ({count})</li>",
+      escape_html(month),
+      escape_html(month)
+    );
+  }
+
+  body.push_str("</ul>\n");
+  render_page("Gallery", &body)
+}
+
+/// Renders the page of `month`, with a thumbnail linking to each entry.
+fn render_month(month: &Path, entries: &[Entry], root: &str) -> String {
+  let title = month.display().to_string();
+  let mut body = format!(
+    "<p><a href=\"{root}index.html\">Gallery</a></p>\n<h1>{}</h1>\n<div class=\"grid\">\n",
+    escape_html(&title)
+  );
+
+  for entry in entries {
+    let thumbnail = entry
+      .thumbnail
+      .as_ref()
+      .map_or(escape_html(&entry.name), |t| {
+        format!("<img src=\"{}\" loading=\"lazy\" alt=\"\">", escape_html(t))
+      });
+    let _ = writeln!(
+      body,
+      "<a href=\"{}.html\">{thumbnail}</a>",
+      escape_html(&entry.name)
+    );
+  }
+
+  body.push_str("</div>\n");
+  render_page(&title, &body)
+}
+
+/// Renders the page of `entry`, linking to the entries before and after it in
+/// its month.
+fn render_item(entry: &Entry, prev: Option<&Entry>, next: Option<&Entry>, root: &str) -> String {
+  let item = entry.item;
+  let link = |e: Option<&Entry>, text: &str| {
+    e.map_or(String::new(), |e| {
+      format!(" <a href=\"{}.html\">{text}</a>", escape_html(&e.name))
+    })
+  };
+
+  let mut body = format!("<p><a href=\"{root}index.html\">Gallery</a>");
+  let _ = writeln!(
+    body,
+    " <a href=\"index.html\">Month</a>{}{}</p>\n<main>",
+    link(prev, "Previous"),
+    link(next, "Next"),
+  );
+
+  if let Some(image) = &entry.image {
+    let _ = writeln!(body, "<img src=\"{}\" alt=\"\">", escape_html(image));
+  }
+  if let Some(video) = &entry.video {
+    let attributes = if item.is_video { "" } else { " loop muted" };
+    let _ = writeln!(
+      body,
+      "<video src=\"{}\" controls playsinline{attributes}></video>",
+      escape_html(video)
+    );
+  }
+
+  body.push_str("</main>\n<dl>\n");
+  for (term, value) in [
+    ("Caption", &item.caption),
+    ("Date", &item.date),
+    ("Location", &item.location),
+    ("Camera", &item.camera),
+  ] {
+    if let Some(value) = value {
+      let _ = writeln!(body, "<dt>{term}</dt><dd>{}</dd>", escape_html(value));
+    }
+  }
+  body.push_str("</dl>\n");
+
+  render_page(item.caption.as_deref().unwrap_or(&entry.name), &body)
+}
+
+/// Renders a complete page with `title` and `body`.
+fn render_page(title: &str, body: &str) -> String {
+  let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+  let _ = writeln!(
+    html,
+    "<meta name=\"viewport\" content=\"width=device-width\">"
+  );
+  let _ = writeln!(html, "<title>{}</title>", escape_html(title));
+  let _ = writeln!(html, "<style>{STYLE}</style>\n</head>\n<body>");
+  html.push_str(body);
+  html.push_str("</body>\n</html>\n");
+  html
+}
+
+/// Escapes `text` for use in HTML.
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test_build {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn lays_out_by_month() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "video.mov": {},
+    );
+
+    let items = [
+      GalleryItem {
+        path: d.get_path("image.jpg"),
+        stem: Some("2023/05/230501_120000000".to_string()),
+        caption: Some("Fish & Chips".to_string()),
+        live_photo_video: Some(d.get_path("video.mov")),
+        ..Default::default()
+      },
+      GalleryItem {
+        path: d.get_path("image.jpg"),
+        stem: Some("2023/05/230501_120000000".to_string()),
+        ..Default::default()
+      },
+    ];
+
+    let out = d.get_path("gallery");
+    assert_eq!(build(&out, &items).unwrap(), 2);
+
+    let page = fs::read_to_string(out.join("2023/05/230501_120000000.html")).unwrap();
+    assert!(page.contains("<dd>Fish &amp; Chips</dd>"));
+    assert!(page.contains("<video src=\"230501_120000000_live.mov\""));
+    assert!(page.contains("<a href=\"230501_120000000_2.html\">Next</a>"));
+    assert!(out.join("2023/05/230501_120000000_live.mov").exists());
+    assert_eq!(
+      fs::read(out.join("2023/05/230501_120000000.jpg")).unwrap(),
+      fs::read(d.get_path("image.jpg")).unwrap()
+    );
+    assert!(out.join("2023/05/index.html").exists());
+    assert!(
+      fs::read_to_string(out.join("index.html"))
+        .unwrap()
+        .contains("<a href=\"2023/05/index.html\">2023/05</a> (2)")
+    );
+  }
+
+  #[test]
+  fn numbers_shared_stems_without_collisions() {
+    let d = test_dir!(
+      "image.jpg": {},
+    );
+
+    let item = |stem: &str| GalleryItem {
+      path: d.get_path("image.jpg"),
+      stem: Some(format!("2023/05/{stem}")),
+      ..Default::default()
+    };
+    let items = [item("a"), item("a"), item("a_2")];
+
+    let out = d.get_path("gallery");
+    build(&out, &items).unwrap();
+
+    for name in ["a", "a_2", "a_3"] {
+      assert!(out.join(format!("2023/05/{name}.html")).exists());
+    }
+  }
+}
+
+#[cfg(test)]
+mod test_render_item {
+  use super::*;
+
+  #[test]
+  fn shows_details_and_video() {
+    let item = GalleryItem {
+      path: PathBuf::from("/catalog/image.heic"),
+      date: Some("2023-05-01 12:00:00".to_string()),
+      location: Some("Kyoto, Japan".to_string()),
+      camera: Some("<Apple> iPhone".to_string()),
+      ..Default::default()
+    };
+    let entry = Entry {
+      item:      &item,
+      name:      "230501_120000000".to_string(),
+      image:     Some("230501_120000000.jpg".to_string()),
+      thumbnail: None,
+      video:     Some("230501_120000000_live.mov".to_string()),
+    };
+
+    let html = render_item(&entry, None, None, "../../");
+
+    assert!(html.contains("<a href=\"../../index.html\">Gallery</a>"));
+    assert!(html.contains("<img src=\"230501_120000000.jpg\""));
+    assert!(html.contains("controls playsinline loop muted"));
+    assert!(html.contains("<dd>Kyoto, Japan</dd>"));
+    assert!(html.contains("<dd>&lt;Apple&gt; iPhone</dd>"));
+    assert!(!html.contains("Previous"));
+  }
+}
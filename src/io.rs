@@ -3,15 +3,22 @@
 //! Functions for manipulating files.
 
 use std::{
+  collections::HashSet,
   ffi::{OsStr, OsString},
   fs,
   path::{Path, PathBuf},
   process::Command,
 };
 
+use chrono::Utc;
 use regex::Regex;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
 
-use crate::prim::Metadata;
+use crate::{
+  prim::{Metadata, SidecarNaming},
+  trash::{self, TrashEntry},
+};
 
 /// All `ExifTool` operations will use this format when extracting date & time.
 /// Follows RFC 3339 format for easy parsing with `chrono`.
@@ -26,13 +33,49 @@ pub const DATETIME_READ_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%f%:z";
 /// Input: January 1st, 2024 at 12:30:01.050, second image at this exact time.
 /// Output: `2024/01/240101_123001050_b.jpg`.
 /// darktable duplicate: `2024/01/240101_123001050_b_01.jpg.xmp`.
-const DATETIME_WRITE_FORMAT: &str = "%Y/%m/%y%m%d_%H%M%S%-3f%+lc";
+pub const DATETIME_WRITE_FORMAT: &str = "%Y/%m/%y%m%d_%H%M%S%-3f%+lc";
 
 /// When using `ExifTool` to read metadata, this converts the time zone to UTC
 /// in RFC 3339 format, and puts the output into JSON for easy parsing with
 /// `serde_json`.
 const READ_ARGS: [&str; 3] = ["-d", DATETIME_READ_FORMAT, "-json"];
 
+/// Adds video companion file extensions `ExifTool` does not process by default
+/// when scanning directories (DJI subtitles and low-resolution videos).
+const COMPANION_ARGS: [&str; 4] = ["-ext+", "SRT", "-ext+", "LRF"];
+
+/// Enables `ExifTool`'s validation, outputting only its results. Note that this
+/// only reports the first warning and error, as `-a` would duplicate JSON keys.
+const VALIDATE_ARGS: [&str; 6] = [
+  "-api",
+  "validate=1",
+  "-Validate",
+  "-Warning",
+  "-Error",
+  "-json",
+];
+
+/// Maximum number of files to validate per call to `ExifTool`.
+const VALIDATE_BATCH_SIZE: usize = 500;
+
+/// Output of `ExifTool`'s validation for a single file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ValidationOutput {
+  pub source_file: PathBuf,
+  pub validate:    Option<String>,
+  pub warning:     Option<String>,
+  pub error:       Option<String>,
+}
+
+/// Output of `ExifTool` when reading only `ContentIdentifier`.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContentIdOutput {
+  source_file:        PathBuf,
+  content_identifier: Option<String>,
+}
+
 /// Arguments for converting metadata from EXIF to XMP format.
 const COPY_EXIF_2_XMP: &str = include_str!("../third_party/exiftool/arg_files/exif2xmp.args");
 
@@ -76,8 +119,9 @@ pub fn copy_metadata(
   read_metadata(&file_dst)
 }
 
-/// Creates XMP for `file_media`, and reads back its metadata.
-pub fn create_xmp(file_media: impl AsRef<Path>) -> Result<Metadata, String> {
+/// Creates XMP for `file_media`, named by `naming`, and reads back its
+/// metadata.
+pub fn create_xmp(file_media: impl AsRef<Path>, naming: SidecarNaming) -> Result<Metadata, String> {
   let file_media = make_canonical(file_media)?;
 
   if file_media.extension().is_none_or(|e| e == "xmp") {
@@ -87,8 +131,7 @@ pub fn create_xmp(file_media: impl AsRef<Path>) -> Result<Metadata, String> {
     ));
   }
 
-  let mut file_xmp = file_media.clone();
-  file_xmp.add_extension("xmp");
+  let file_xmp = naming.get_path(&file_media);
 
   if file_xmp.exists() {
     return Err(format!(
@@ -110,13 +153,37 @@ pub fn exiftool_check() -> Result<(), String> {
   version_check(run_exiftool(None::<&Path>, ["-ver"])?, EXIFTOOL_MIN_VERSION)
 }
 
-/// Moves `file_src` to `yyyy/mm/yymmdd_hhmmssfff_c.ext` under `dir_dst`.
+/// Extracts the first embedded image named in `tags` (e.g. `PreviewImage`) from
+/// `file`, as stored (i.e. without re-encoding). Returns `None` if there are
+/// none.
+pub fn extract_image(file: impl AsRef<Path>, tags: &[&str]) -> Result<Option<Vec<u8>>, String> {
+  let file = make_canonical(file)?;
+
+  for tag in tags {
+    let tag = format!("-{tag}");
+    let image = run_exiftool(None::<&Path>, [
+      OsStr::new("-b"),
+      OsStr::new(&tag),
+      file.as_os_str(),
+    ])?;
+
+    if !image.is_empty() {
+      return Ok(Some(image));
+    }
+  }
+
+  Ok(None)
+}
+
+/// Moves `file_src` under `dir_dst`, named by the date and time `format`
+/// (generally `DATETIME_WRITE_FORMAT`, i.e. `yyyy/mm/yymmdd_hhmmssfff_c.ext`).
 /// Optionally, if `metadata_src` is `Some`, uses its metadata for the date and
 /// time instead. Returns the path to the new file.
 pub fn move_file(
   file_src: impl AsRef<Path>,
   metadata_src: Option<impl AsRef<Path>>,
   dir_dst: impl AsRef<Path>,
+  format: &str,
   ext: impl AsRef<OsStr>,
 ) -> Result<PathBuf, String> {
   let file_src = make_canonical(file_src)?;
@@ -133,7 +200,7 @@ pub fn move_file(
   }
 
   // `-v` needed to report renaming.
-  args.extend(["-v", "-d", DATETIME_WRITE_FORMAT].map(OsStr::new));
+  args.extend(["-v", "-d", format].map(OsStr::new));
 
   let mut args_rename = Vec::new();
 
@@ -169,14 +236,59 @@ pub fn move_file(
 
 /// Gets metadata for `file`.
 pub fn read_metadata(file: impl AsRef<Path>) -> Result<Metadata, String> {
+  read_metadata_with_args(file, &[])
+}
+
+/// Gets metadata for `file`, passing `ExifTool` additional `args` (e.g. `-api
+/// QuickTimeUTC`).
+pub fn read_metadata_with_args(
+  file: impl AsRef<Path>,
+  args_extra: &[&str],
+) -> Result<Metadata, String> {
   let file = make_canonical(file)?;
 
   let mut args = Vec::from(READ_ARGS.map(OsStr::new));
+  args.extend(args_extra.iter().map(OsStr::new));
   args.push(file.as_os_str());
 
   Ok(parse_vec(run_exiftool(None::<&Path>, args)?)?.remove(0))
 }
 
+/// Gets every tag in `file`, named by group (e.g. `XMP-dc:Creator`), including
+/// tags duplicated across groups. Non-string values are converted to their
+/// JSON form.
+pub fn read_tags(file: impl AsRef<Path>) -> Result<Vec<(String, String)>, String> {
+  let file = make_canonical(file)?;
+
+  let mut args = Vec::from(READ_ARGS.map(OsStr::new));
+  args.extend(["-G1", "-a"].map(OsStr::new));
+  args.push(file.as_os_str());
+
+  let stdout = run_exiftool(None::<&Path>, args)?;
+
+  let mut output = serde_json::from_slice::<Vec<Map<String, Value>>>(&stdout).map_err(|e| {
+    format!(
+      "Failed to parse ExifTool output as tags ({e}).\nstdout:\n{}",
+      String::from_utf8_lossy(&stdout)
+    )
+  })?;
+
+  if output.is_empty() {
+    return Err(format!("{}: No tags read.", file.display()));
+  }
+
+  Ok(
+    output
+      .swap_remove(0)
+      .into_iter()
+      .map(|(tag, value)| match value {
+        Value::String(s) => (tag, s),
+        v => (tag, v.to_string()),
+      })
+      .collect(),
+  )
+}
+
 /// Reads metadata from `dir_root` and all subdirectories, excluding `exclude`
 /// (e.g. `trash/`).
 pub fn read_metadata_recursive(
@@ -187,6 +299,7 @@ pub fn read_metadata_recursive(
   let dir_exclude = dir_exclude.map(make_canonical).transpose()?;
 
   let mut args = Vec::from(READ_ARGS.map(OsStr::new));
+  args.extend(COMPANION_ARGS.map(OsStr::new));
   args.extend(["-r", "."].map(OsStr::new));
 
   let exclude_relative;
@@ -209,12 +322,75 @@ pub fn read_metadata_recursive(
   parse_vec(run_exiftool(Some(dir_root), args)?)
 }
 
+/// Finds all files under `dir_root` (excluding hidden directories) whose
+/// `ContentIdentifier` is one of `content_ids`. Only this tag is read, to keep
+/// scanning large catalogs fast.
+pub fn find_by_content_id(
+  dir_root: impl AsRef<Path>,
+  content_ids: &HashSet<String>,
+) -> Result<Vec<PathBuf>, String> {
+  let dir_root = make_canonical(dir_root)?;
+
+  let stdout = run_exiftool(Some(&dir_root), [
+    "-json",
+    "-fast2",
+    "-ContentIdentifier",
+    "-r",
+    ".",
+  ])?;
+
+  if stdout.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let output = serde_json::from_slice::<Vec<ContentIdOutput>>(&stdout).map_err(|e| {
+    format!(
+      "Failed to parse ExifTool output as content identifiers ({e}).\nstdout:\n{}",
+      String::from_utf8_lossy(&stdout)
+    )
+  })?;
+
+  Ok(
+    output
+      .into_iter()
+      .filter(|o| {
+        o.content_identifier
+          .as_ref()
+          .is_some_and(|id| content_ids.contains(id))
+      })
+      .map(|o| dir_root.join(o.source_file))
+      .collect(),
+  )
+}
+
+/// Moves `file` to `dst` as is, without renaming by metadata. Errors if `dst`
+/// already exists.
+pub fn rename_file(file: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<PathBuf, String> {
+  let file = make_canonical(file)?;
+  let dst = dst.as_ref();
+
+  if dst.exists() {
+    return Err(format!(
+      "{}: Cannot move file, as {} already exists.",
+      file.display(),
+      dst.display()
+    ));
+  }
+
+  fs::rename(&file, dst).map_err(|e| format!("{}: Failed to move file ({e}).", file.display()))?;
+
+  make_canonical(dst)
+}
+
 /// Moves `file` under `dir_trash`, maintaining its directory structure relative
-/// to `dir_root`
+/// to `dir_root`, and records why (`reason`) and during which `run` in the
+/// trash journal.
 pub fn remove_file(
   dir_root: impl AsRef<Path>,
   dir_trash: impl AsRef<Path>,
   file: impl AsRef<Path>,
+  reason: &str,
+  run: &str,
 ) -> Result<(), String> {
   let dir_root = make_canonical(dir_root)?;
   let dir_trash = make_canonical(dir_trash)?;
@@ -247,9 +423,73 @@ pub fn remove_file(
   }
 
   fs::create_dir_all(path_trash.parent().unwrap()).unwrap();
-  fs::rename(file, path_trash).unwrap();
+  fs::rename(&file, path_trash).unwrap();
 
-  Ok(())
+  trash::record(&dir_trash, &TrashEntry {
+    path:    path_relative.to_path_buf(),
+    reason:  reason.to_string(),
+    run:     run.to_string(),
+    trashed: Utc::now(),
+  })
+}
+
+/// Runs `ExifTool`'s validation on `files`, returning each file's path
+/// alongside its `Validate` summary (e.g. `2 Warnings (1 minor)`) and first
+/// warning and error, if any.
+pub fn read_validation(
+  files: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<Vec<ValidationOutput>, String> {
+  let files = files
+    .into_iter()
+    .map(make_canonical)
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let mut output = Vec::new();
+
+  // Batch to keep the command line to a reasonable length.
+  for batch in files.chunks(VALIDATE_BATCH_SIZE) {
+    let mut args = Vec::from(VALIDATE_ARGS.map(OsStr::new));
+    args.extend(batch.iter().map(|f| f.as_os_str()));
+
+    let stdout = run_exiftool(None::<&Path>, args)?;
+
+    if stdout.is_empty() {
+      continue;
+    }
+
+    output.extend(
+      serde_json::from_slice::<Vec<ValidationOutput>>(&stdout).map_err(|e| {
+        format!(
+          "Failed to parse ExifTool output as validation ({e}).\nstdout:\n{}",
+          String::from_utf8_lossy(&stdout)
+        )
+      })?,
+    );
+  }
+
+  Ok(output)
+}
+
+/// Reads and deserializes the JSON in `file`.
+pub fn read_json<T: DeserializeOwned>(file: impl AsRef<Path>) -> Result<T, String> {
+  let file = file.as_ref();
+
+  let json =
+    fs::read(file).map_err(|e| format!("{}: Failed to read file ({e}).", file.display()))?;
+
+  serde_json::from_slice(&json)
+    .map_err(|e| format!("{}: Failed to parse JSON ({e}).", file.display()))
+}
+
+/// Writes `value` to `file` as pretty-printed JSON, overwriting any existing
+/// file.
+pub fn write_json(file: impl AsRef<Path>, value: &impl Serialize) -> Result<(), String> {
+  let file = file.as_ref();
+
+  let json = serde_json::to_vec_pretty(value)
+    .map_err(|e| format!("{}: Failed to serialize JSON ({e}).", file.display()))?;
+
+  fs::write(file, json).map_err(|e| format!("{}: Failed to write file ({e}).", file.display()))
 }
 
 /// Runs `ExifTool` with `args`, from optional working directory `dir_root`.
@@ -574,7 +814,7 @@ mod test_create_xmp {
       },
     );
 
-    create_xmp(d.get_path("image.jpg")).unwrap();
+    create_xmp(d.get_path("image.jpg"), SidecarNaming::FullName).unwrap();
 
     let metadata = read_metadata(d.get_path("image.jpg.xmp")).unwrap();
     assert_eq!(
@@ -598,7 +838,7 @@ mod test_create_xmp {
       },
     );
 
-    create_xmp(d.get_path("video.mov")).unwrap();
+    create_xmp(d.get_path("video.mov"), SidecarNaming::FullName).unwrap();
 
     let metadata = read_metadata(d.get_path("video.mov.xmp")).unwrap();
     assert_eq!(
@@ -617,7 +857,7 @@ mod test_create_xmp {
       "image.jpg": { "Creator": "Creator" },
     );
 
-    create_xmp(d.get_path("image.jpg")).unwrap();
+    create_xmp(d.get_path("image.jpg"), SidecarNaming::FullName).unwrap();
 
     let metadata = read_metadata(d.get_path("image.jpg.xmp")).unwrap();
     assert_eq!(metadata.source_file, d.get_path("image.jpg.xmp"));
@@ -630,7 +870,7 @@ mod test_create_xmp {
       "image.jpg": { "Creator": "Creator" },
     );
 
-    create_xmp(d.get_path("image.jpg")).unwrap();
+    create_xmp(d.get_path("image.jpg"), SidecarNaming::FullName).unwrap();
 
     assert_dir!(d, ["image.jpg", "image.jpg.xmp"]);
   }
@@ -642,7 +882,7 @@ mod test_create_xmp {
     );
 
     assert_err!(
-      create_xmp(d.get_path("image.jpg.xmp")),
+      create_xmp(d.get_path("image.jpg.xmp"), SidecarNaming::FullName),
       "Cannot create XMP (invalid extension)."
     );
   }
@@ -655,7 +895,7 @@ mod test_create_xmp {
     );
 
     assert_err!(
-      create_xmp(d.get_path("image.jpg")),
+      create_xmp(d.get_path("image.jpg"), SidecarNaming::FullName),
       "Cannot create XMP (file already exists)."
     );
   }
@@ -666,7 +906,7 @@ mod test_create_xmp {
       "image.jpg": { "Creator": "Creator" },
     );
 
-    let metadata_returned = create_xmp(d.get_path("image.jpg")).unwrap();
+    let metadata_returned = create_xmp(d.get_path("image.jpg"), SidecarNaming::FullName).unwrap();
 
     let metadata_read = read_metadata(d.get_path("image.jpg.xmp")).unwrap();
     assert_eq!(metadata_returned.source_file, metadata_read.source_file);
@@ -741,8 +981,22 @@ mod test_move_file {
       "image2.jpg": { "DateTimeOriginal": "2000-01-01T00:00:00", "OffsetTimeOriginal": "+00:00" },
     );
 
-    move_file(d.get_path("image1.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
-    move_file(d.get_path("image2.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    move_file(
+      d.get_path("image1.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
+    move_file(
+      d.get_path("image2.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_dir!(d, [
       "2000/01/000101_000000000.jpg",
@@ -757,7 +1011,13 @@ mod test_move_file {
     );
 
     assert_err!(
-      move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg"),
+      move_file(
+        d.get_path("image.jpg"),
+        None::<&Path>,
+        d.root(),
+        DATETIME_WRITE_FORMAT,
+        ".jpg"
+      ),
       "Failed to move file."
     );
   }
@@ -771,7 +1031,14 @@ mod test_move_file {
       },
     );
 
-    move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    move_file(
+      d.get_path("image.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_dir!(d, ["2025/01/250101_000000000.jpg"]);
   }
@@ -788,6 +1055,7 @@ mod test_move_file {
       d.get_path("image.jpg.xmp"),
       None::<&Path>,
       d.root(),
+      DATETIME_WRITE_FORMAT,
       ".jpg.xmp",
     )
     .unwrap();
@@ -806,7 +1074,14 @@ mod test_move_file {
       },
     );
 
-    move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    move_file(
+      d.get_path("image.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_dir!(d, ["2000/01/000101_000000000.jpg"]);
   }
@@ -824,6 +1099,7 @@ mod test_move_file {
       d.get_path("image.jpg.xmp"),
       None::<&Path>,
       d.root(),
+      DATETIME_WRITE_FORMAT,
       ".jpg.xmp",
     )
     .unwrap();
@@ -840,7 +1116,14 @@ mod test_move_file {
       },
     );
 
-    move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    move_file(
+      d.get_path("image.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_dir!(d, ["2000/01/000101_080000000.jpg"]);
   }
@@ -853,7 +1136,14 @@ mod test_move_file {
       },
     );
 
-    move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    move_file(
+      d.get_path("image.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_dir!(d, ["2000/01/000101_080000000.jpg"]);
   }
@@ -868,7 +1158,14 @@ mod test_move_file {
       },
     );
 
-    move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    move_file(
+      d.get_path("image.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_dir!(d, ["2000/01/000101_000000999.jpg"]);
   }
@@ -885,6 +1182,7 @@ mod test_move_file {
       d.get_path("image.jpg.xmp"),
       None::<&Path>,
       d.root(),
+      DATETIME_WRITE_FORMAT,
       ".jpg.xmp",
     )
     .unwrap();
@@ -898,7 +1196,14 @@ mod test_move_file {
       "image.jpg": { "DateTimeOriginal": "2000-01-01T00:00:00" },
     );
 
-    let p = move_file(d.get_path("image.jpg"), None::<&Path>, d.root(), ".jpg").unwrap();
+    let p = move_file(
+      d.get_path("image.jpg"),
+      None::<&Path>,
+      d.root(),
+      DATETIME_WRITE_FORMAT,
+      ".jpg",
+    )
+    .unwrap();
 
     assert_eq!(p, d.get_path("2000/01/000101_000000000.jpg"));
   }
@@ -914,6 +1219,7 @@ mod test_move_file {
       d.get_path("image.jpg"),
       Some(d.get_path("image.jpg.xmp")),
       d.root(),
+      DATETIME_WRITE_FORMAT,
       ".jpg",
     )
     .unwrap();
@@ -1071,7 +1377,13 @@ mod test_remove_file {
     fs::rename(d.get_path("image.jpg"), d.trash().join("image.jpg")).unwrap();
 
     assert_err!(
-      remove_file(d.root(), d.trash(), d.trash().join("image.jpg")),
+      remove_file(
+        d.root(),
+        d.trash(),
+        d.trash().join("image.jpg"),
+        "test",
+        "run"
+      ),
       "Cannot remove file already in trash"
     );
   }
@@ -1084,7 +1396,7 @@ mod test_remove_file {
     fs::copy(d.get_path("image.jpg"), d.trash().join("image.jpg")).unwrap();
 
     assert_err!(
-      remove_file(d.root(), d.trash(), d.get_path("image.jpg")),
+      remove_file(d.root(), d.trash(), d.get_path("image.jpg"), "test", "run"),
       "Cannot remove file due to name collision in trash"
     );
   }
@@ -1097,7 +1409,13 @@ mod test_remove_file {
     );
 
     assert_err!(
-      remove_file(d.root().join("dir"), d.trash(), d.root().join("image1.jpg")),
+      remove_file(
+        d.root().join("dir"),
+        d.trash(),
+        d.root().join("image1.jpg"),
+        "test",
+        "run"
+      ),
       "Cannot remove file outside root directory"
     );
   }
@@ -1108,7 +1426,7 @@ mod test_remove_file {
       "image.jpg": {},
     );
 
-    remove_file(d.root(), d.trash(), d.get_path("image.jpg")).unwrap();
+    remove_file(d.root(), d.trash(), d.get_path("image.jpg"), "test", "run").unwrap();
 
     assert_dir!(d, []);
     assert_trash!(d, ["image.jpg"]);
@@ -1120,11 +1438,39 @@ mod test_remove_file {
       "dir/image.jpg": {},
     );
 
-    remove_file(d.root(), d.trash(), d.get_path("dir/image.jpg")).unwrap();
+    remove_file(
+      d.root(),
+      d.trash(),
+      d.get_path("dir/image.jpg"),
+      "test",
+      "run",
+    )
+    .unwrap();
 
     assert_dir!(d, []);
     assert_trash!(d, ["dir/image.jpg"]);
   }
+
+  #[test]
+  fn records_reason_in_journal() {
+    let d = test_dir!(
+      "dir/image.jpg": {},
+    );
+
+    remove_file(
+      d.root(),
+      d.trash(),
+      d.get_path("dir/image.jpg"),
+      "test",
+      "run",
+    )
+    .unwrap();
+
+    let journal = trash::read_journal(d.trash()).unwrap();
+    assert_eq!(journal.len(), 1);
+    assert_eq!(journal[0].path, PathBuf::from("dir/image.jpg"));
+    assert_eq!(journal[0].reason, "test");
+  }
 }
 
 #[cfg(test)]
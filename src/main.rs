@@ -5,16 +5,25 @@
 
 #![feature(path_add_extension)]
 
+mod card;
 mod commands;
+mod config;
+mod gallery;
 mod io;
+mod manifest;
 mod org;
 mod prim;
+mod runs;
 mod setup;
+mod staging;
 #[cfg(test)]
 mod testing;
+mod trash;
+mod watch;
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
+use chrono::TimeDelta;
 use clap::{ArgAction, Parser, Subcommand};
 
 /// Command-line arguments.
@@ -28,6 +37,10 @@ struct Args {
   #[arg(short, action = ArgAction::Count, global = true)]
   verbose: u8,
 
+  /// Write a JSON report of every file touched to this path.
+  #[arg(long, global = true)]
+  report: Option<PathBuf>,
+
   /// Function to run.
   #[command(subcommand)]
   command: Commands,
@@ -37,9 +50,140 @@ struct Args {
 #[derive(Subcommand)]
 enum Commands {
   /// Clean catalog.
-  Org,
+  Org {
+    /// Move files whose names do not match their metadata without asking.
+    #[arg(short, long)]
+    yes: bool,
+  },
   /// Import photos from path into the catalog.
-  Import { path: PathBuf },
+  Import {
+    path: PathBuf,
+
+    /// Leave `path` untouched, importing from copies (or hardlinks) instead.
+    #[arg(long)]
+    copy: bool,
+
+    /// After a copy import, delete source files that were imported.
+    #[arg(long, requires = "copy")]
+    delete_source: bool,
+  },
+  /// Check catalog (or path within) for problems without modifying anything.
+  Check {
+    path: Option<PathBuf>,
+
+    /// Exit with an error if there are more error-severity failures than this.
+    #[arg(long, default_value_t = 0)]
+    max_failures: usize,
+  },
+  /// Rename all sidecars to the naming scheme in the catalog's config.
+  ConvertSidecars,
+  /// List cameras in the catalog by file count and owner.
+  Cameras,
+  /// List media files whose metadata matches a query (e.g. `Country == Japan
+  /// and date:2023 and not has:GPSLatitude`).
+  Find {
+    query: String,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t)]
+    format: commands::FindFormat,
+  },
+  /// Show statistics on the catalog's composition and health.
+  Stats {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t)]
+    format: commands::StatsFormat,
+  },
+  /// Export locations of media files as `GeoJSON`, or KML (by extension).
+  ExportGeo {
+    output: PathBuf,
+
+    /// Only export media files matching this query.
+    #[arg(long)]
+    query: Option<String>,
+
+    /// Only export media files taken in this range (e.g. `2023-05..2023-08`).
+    #[arg(long)]
+    date: Option<String>,
+
+    /// Merge points within this many meters.
+    #[arg(long, value_name = "METERS")]
+    cluster: Option<f64>,
+
+    /// Draw a track through each day's points, in time order.
+    #[arg(long)]
+    tracks: bool,
+  },
+  /// Build a static HTML gallery of media files matching a query.
+  Gallery {
+    query:   String,
+    /// Directory to write the gallery to, outside the catalog.
+    dir_out: PathBuf,
+  },
+  /// Set tags (`Tag=Value`) on media files matching a query, or at paths,
+  /// through their sidecars.
+  Set {
+    /// A query or paths, and `Tag=Value` assignments.
+    #[arg(required_unless_present = "undo", allow_hyphen_values = true)]
+    args: Vec<String>,
+
+    /// Also write tags to the media files themselves.
+    #[arg(long)]
+    media: bool,
+
+    /// Restore tags edited during a run (e.g. `20250101T120000000`).
+    #[arg(long, conflicts_with_all = ["args", "media"])]
+    undo: Option<String>,
+  },
+  /// Show tag differences between two files, or changes made during a run.
+  Diff {
+    #[arg(required_unless_present = "run", requires = "file_b")]
+    file_a: Option<PathBuf>,
+
+    file_b: Option<PathBuf>,
+
+    /// Run (e.g. `20250101T120000000`) to show changes from.
+    #[arg(long, conflicts_with = "file_a")]
+    run: Option<String>,
+  },
+  /// Write a checksum manifest of all files in the catalog.
+  Manifest,
+  /// Check catalog files against the manifest for bit-rot.
+  Verify,
+  /// Watch a folder, importing new files once they stop changing.
+  Watch {
+    inbox: PathBuf,
+
+    /// Seconds without changes before importing.
+    #[arg(long, default_value_t = 30)]
+    settle: u64,
+
+    /// Seconds to wait for the other half of a Live Photo before importing
+    /// anyway.
+    #[arg(long, default_value_t = 600)]
+    pair_timeout: u64,
+  },
+  /// Manage files removed to the catalog's trash.
+  Trash {
+    #[command(subcommand)]
+    command: TrashCommands,
+  },
+}
+
+/// Trash management functions.
+#[derive(Subcommand)]
+enum TrashCommands {
+  /// List trashed files by run and reason.
+  List,
+  /// Restore a run (e.g. `20250101T120000000`) or path to its original
+  /// location.
+  Restore { target: String },
+  /// Permanently delete trashed files.
+  Purge {
+    /// Only delete files trashed longer ago than this (e.g. `30d`, `2w`).
+    #[arg(long, value_parser = trash::parse_age)]
+    older_than: TimeDelta,
+  },
 }
 
 fn run() -> Result<(), String> {
@@ -52,8 +196,71 @@ fn run() -> Result<(), String> {
   let catalog = setup::get_or_update_catalog_path(args.catalog)?;
 
   match args.command {
-    Commands::Org => commands::org(&catalog),
-    Commands::Import { path } => commands::import(&catalog, &path),
+    Commands::Org { yes } => commands::org(&catalog, yes, args.report.as_deref()),
+    Commands::Import {
+      path,
+      copy,
+      delete_source,
+    } => commands::import(&catalog, &path, copy, delete_source, args.report.as_deref()),
+    Commands::Check { path, max_failures } => commands::check(
+      &catalog,
+      path.as_deref(),
+      max_failures,
+      args.report.as_deref(),
+    ),
+    Commands::ConvertSidecars => commands::convert_sidecars(&catalog, args.report.as_deref()),
+    Commands::Cameras => commands::cameras(&catalog),
+    Commands::Find { query, format } => commands::find(&catalog, &query, format),
+    Commands::Stats { format } => commands::stats(&catalog, format),
+    Commands::ExportGeo {
+      output,
+      query,
+      date,
+      cluster,
+      tracks,
+    } => commands::export_geo(
+      &catalog,
+      &output,
+      query.as_deref(),
+      date.as_deref(),
+      cluster,
+      tracks,
+    ),
+    Commands::Gallery { query, dir_out } => commands::gallery(&catalog, &query, &dir_out),
+    Commands::Set {
+      args: set_args,
+      media,
+      undo,
+    } => match undo {
+      Some(run) => commands::set_undo(&catalog, &run, args.report.as_deref()),
+      None => commands::set(&catalog, &set_args, media, args.report.as_deref()),
+    },
+    Commands::Diff {
+      file_a,
+      file_b,
+      run,
+    } => match (file_a, file_b, run) {
+      (Some(file_a), Some(file_b), _) => commands::diff_files(file_a, file_b),
+      (_, _, Some(run)) => commands::diff_run(&catalog, &run),
+      _ => Err("Either two files or `--run` are required.".to_string()),
+    },
+    Commands::Manifest => commands::manifest(&catalog),
+    Commands::Verify => commands::verify(&catalog),
+    Commands::Watch {
+      inbox,
+      settle,
+      pair_timeout,
+    } => commands::watch(
+      &catalog,
+      &inbox,
+      Duration::from_secs(settle),
+      Duration::from_secs(pair_timeout),
+    ),
+    Commands::Trash { command } => match command {
+      TrashCommands::List => commands::trash_list(&catalog),
+      TrashCommands::Restore { target } => commands::trash_restore(&catalog, &target),
+      TrashCommands::Purge { older_than } => commands::trash_purge(&catalog, older_than),
+    },
   }
 }
 
@@ -0,0 +1,422 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! SHA-256 manifest of all files in a catalog, stored in
+//! `catalog/.c1a/manifest.json`, for detecting bit-rot.
+
+use std::{
+  collections::BTreeMap,
+  fs::{self, File},
+  io as std_io,
+  path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+  io,
+  org::{Action, Report},
+};
+
+/// Location of the manifest, relative to the catalog root.
+const MANIFEST_PATH: &str = ".c1a/manifest.json";
+
+/// Checksums of every file in a catalog, keyed by path relative to the catalog.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+  files: BTreeMap<PathBuf, Entry>,
+}
+
+/// What is known about a single file when it was last hashed.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+struct Entry {
+  sha256:   String,
+  size:     u64,
+  modified: DateTime<Utc>,
+}
+
+/// Differences between a manifest and the files on disk.
+#[derive(Debug, Default)]
+pub struct Verification {
+  /// Content changed, but size and modification time did not (i.e. bit-rot).
+  pub corrupted: Vec<PathBuf>,
+  /// Content changed along with modification time (e.g. edited externally).
+  pub modified:  Vec<PathBuf>,
+  /// In the manifest, but not on disk.
+  pub missing:   Vec<PathBuf>,
+  /// On disk, but not in the manifest.
+  pub extra:     Vec<PathBuf>,
+}
+
+impl Manifest {
+  /// Hashes every file under `catalog`, skipping hidden files and directories
+  /// (e.g. `.c1a/` and `.trash/`).
+  pub fn build(catalog: impl AsRef<Path>) -> Result<Self, String> {
+    let catalog = catalog.as_ref();
+
+    let files = list_files(catalog)?
+      .into_iter()
+      .map(|path| Ok((path.clone(), Entry::new(catalog.join(path))?)))
+      .collect::<Result<_, String>>()?;
+
+    Ok(Self { files })
+  }
+
+  /// Loads the manifest for `catalog`, if there is one.
+  pub fn load(catalog: impl AsRef<Path>) -> Result<Option<Self>, String> {
+    let path = catalog.as_ref().join(MANIFEST_PATH);
+
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    io::read_json(path).map(Some)
+  }
+
+  /// Writes the manifest for `catalog`, replacing any existing one.
+  pub fn save(&self, catalog: impl AsRef<Path>) -> Result<(), String> {
+    let path = catalog.as_ref().join(MANIFEST_PATH);
+
+    fs::create_dir_all(path.parent().unwrap())
+      .map_err(|e| format!("{}: Failed to create directory ({e}).", path.display()))?;
+
+    io::write_json(path, self)
+  }
+
+  /// Number of files in the manifest.
+  pub fn len(&self) -> usize {
+    self.files.len()
+  }
+
+  /// Updates the manifest with everything `report` recorded happening in
+  /// `catalog`. Moved files keep their checksums unless their content was
+  /// also changed during the run, in which case they are hashed again.
+  pub fn apply(&mut self, catalog: impl AsRef<Path>, report: &Report) -> Result<(), String> {
+    let catalog = catalog.as_ref();
+    let catalog = catalog.canonicalize().unwrap_or(catalog.to_path_buf());
+    let relative = |path: &Path| -> Option<PathBuf> {
+      let path = report.get_source().join(path);
+      path.strip_prefix(&catalog).ok().map(Path::to_path_buf)
+    };
+
+    for file in report.iter_files() {
+      let from = relative(&file.path);
+
+      if file
+        .actions
+        .iter()
+        .any(|a| matches!(a, Action::Trashed { .. }))
+      {
+        if let Some(from) = from {
+          self.files.remove(&from);
+        }
+        continue;
+      }
+
+      let Some(to) = file.final_path.as_deref().map_or(from.clone(), relative) else {
+        continue;
+      };
+
+      let changed = !file.tag_changes.is_empty()
+        || file.actions.iter().any(|a| {
+          matches!(
+            a,
+            Action::MetadataUpdated { .. } | Action::MetadataSynced { .. }
+          )
+        });
+
+      let entry = match from.and_then(|f| self.files.remove(&f)) {
+        Some(entry) if !changed => entry,
+        _ if catalog.join(&to).exists() => Entry::new(catalog.join(&to))?,
+        _ => continue,
+      };
+
+      self.files.insert(to, entry);
+    }
+
+    // Sidecars created during the run that were not moved are only recorded
+    // against their media file.
+    for file in report.iter_files() {
+      for action in &file.actions {
+        if let Action::SidecarCreated { sidecar } = action
+          && let Some(sidecar) = relative(sidecar)
+          && !self.files.contains_key(&sidecar)
+          && catalog.join(&sidecar).exists()
+        {
+          let entry = Entry::new(catalog.join(&sidecar))?;
+          self.files.insert(sidecar, entry);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Compares the manifest against the files under `catalog`.
+  pub fn verify(&self, catalog: impl AsRef<Path>) -> Result<Verification, String> {
+    let catalog = catalog.as_ref();
+    let mut verification = Verification::default();
+
+    for (path, expected) in &self.files {
+      let full_path = catalog.join(path);
+
+      if !full_path.exists() {
+        verification.missing.push(path.clone());
+        continue;
+      }
+
+      let actual = Entry::new(&full_path)?;
+
+      if actual.sha256 == expected.sha256 {
+        continue;
+      }
+
+      if actual.size == expected.size && actual.modified == expected.modified {
+        verification.corrupted.push(path.clone());
+      } else {
+        verification.modified.push(path.clone());
+      }
+    }
+
+    verification.extra = list_files(catalog)?
+      .into_iter()
+      .filter(|p| !self.files.contains_key(p))
+      .collect();
+
+    Ok(verification)
+  }
+}
+
+impl Entry {
+  /// Hashes the file at `path`.
+  fn new(path: impl AsRef<Path>) -> Result<Self, String> {
+    let path = path.as_ref();
+
+    let stat = fs::metadata(path)
+      .map_err(|e| format!("{}: Failed to read file metadata ({e}).", path.display()))?;
+    let modified = stat.modified().map_err(|e| {
+      format!(
+        "{}: Failed to read modification time ({e}).",
+        path.display()
+      )
+    })?;
+
+    Ok(Self {
+      sha256:   hash_file(path)?,
+      size:     stat.len(),
+      modified: modified.into(),
+    })
+  }
+}
+
+impl Verification {
+  /// Whether no files are corrupted, missing or extra. Modified files are
+  /// expected (e.g. sidecars edited in darktable).
+  pub fn is_ok(&self) -> bool {
+    self.corrupted.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+  }
+}
+
+/// Gets the SHA-256 checksum of `path` as a hex string.
+pub fn hash_file(path: impl AsRef<Path>) -> Result<String, String> {
+  let path = path.as_ref();
+
+  let mut file =
+    File::open(path).map_err(|e| format!("{}: Failed to open file ({e}).", path.display()))?;
+  let mut hasher = Sha256::new();
+  std_io::copy(&mut file, &mut hasher)
+    .map_err(|e| format!("{}: Failed to read file ({e}).", path.display()))?;
+
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Lists all non-hidden files under `dir`, relative to `dir`.
+pub fn list_files(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, String> {
+  let dir = dir.as_ref();
+  let mut files = Vec::new();
+  let mut pending = vec![PathBuf::new()];
+
+  while let Some(rel) = pending.pop() {
+    let entries = fs::read_dir(dir.join(&rel)).map_err(|e| {
+      format!(
+        "{}: Failed to read directory ({e}).",
+        dir.join(&rel).display()
+      )
+    })?;
+
+    for entry in entries {
+      let entry =
+        entry.map_err(|e| format!("{}: Failed to read directory ({e}).", dir.display()))?;
+
+      if entry.file_name().to_string_lossy().starts_with('.') {
+        continue;
+      }
+
+      let path = rel.join(entry.file_name());
+      if entry.path().is_dir() {
+        pending.push(path);
+      } else {
+        files.push(path);
+      }
+    }
+  }
+
+  files.sort();
+
+  Ok(files)
+}
+
+#[cfg(test)]
+mod test_build {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn skips_hidden_files() {
+    let d = test_dir!();
+    fs::create_dir_all(d.get_path("2000/01")).unwrap();
+    fs::create_dir_all(d.get_path(".trash")).unwrap();
+    fs::write(d.get_path("2000/01/image.jpg"), "image").unwrap();
+    fs::write(d.get_path("2000/01/image.jpg.xmp"), "xmp").unwrap();
+    fs::write(d.get_path(".trash/old.jpg"), "old").unwrap();
+
+    let manifest = Manifest::build(d.root()).unwrap();
+
+    assert_eq!(manifest.files.keys().collect::<Vec<_>>(), vec![
+      Path::new("2000/01/image.jpg"),
+      Path::new("2000/01/image.jpg.xmp")
+    ]);
+    assert_eq!(
+      manifest.files[Path::new("2000/01/image.jpg")].sha256,
+      "6105d6cc76af400325e94d588ce511be5bfdbb73b437dc51eca43917d7a43e3d"
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_apply {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn follows_moves() {
+    let d = test_dir!();
+    fs::write(d.get_path("image.jpg"), "image").unwrap();
+    let mut manifest = Manifest::build(d.root()).unwrap();
+    let entry = manifest.files[Path::new("image.jpg")].clone();
+
+    fs::create_dir_all(d.get_path("2000/01")).unwrap();
+    fs::rename(d.get_path("image.jpg"), d.get_path("2000/01/moved.jpg")).unwrap();
+    let mut report = Report::new(d.root());
+    report.action("image.jpg", Action::Moved {
+      to: d.get_path("2000/01/moved.jpg"),
+    });
+
+    manifest.apply(d.root(), &report).unwrap();
+
+    assert_eq!(manifest.files.len(), 1);
+    assert_eq!(manifest.files[Path::new("2000/01/moved.jpg")], entry);
+  }
+
+  #[test]
+  fn removes_trashed_files() {
+    let d = test_dir!();
+    fs::write(d.get_path("image.jpg"), "image").unwrap();
+    let mut manifest = Manifest::build(d.root()).unwrap();
+
+    let mut report = Report::new(d.root());
+    report.action("image.jpg", Action::Trashed {
+      reason: "test".to_string(),
+    });
+
+    manifest.apply(d.root(), &report).unwrap();
+
+    assert_eq!(manifest.len(), 0);
+  }
+
+  #[test]
+  fn rehashes_changed_files() {
+    let d = test_dir!();
+    fs::write(d.get_path("image.jpg.xmp"), "before").unwrap();
+    let mut manifest = Manifest::build(d.root()).unwrap();
+
+    fs::write(d.get_path("image.jpg.xmp"), "after").unwrap();
+    let mut report = Report::new(d.root());
+    report.action("image.jpg.xmp", Action::MetadataUpdated {
+      pass: "test".to_string(),
+    });
+
+    manifest.apply(d.root(), &report).unwrap();
+
+    assert!(manifest.verify(d.root()).unwrap().modified.is_empty());
+  }
+}
+
+#[cfg(test)]
+mod test_verify {
+  use std::time::SystemTime;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn finds_missing_and_extra_files() {
+    let d = test_dir!();
+    fs::write(d.get_path("a.jpg"), "a").unwrap();
+    let manifest = Manifest::build(d.root()).unwrap();
+
+    fs::rename(d.get_path("a.jpg"), d.get_path("b.jpg")).unwrap();
+
+    let verification = manifest.verify(d.root()).unwrap();
+
+    assert_eq!(verification.missing, vec![PathBuf::from("a.jpg")]);
+    assert_eq!(verification.extra, vec![PathBuf::from("b.jpg")]);
+    assert!(!verification.is_ok());
+  }
+
+  #[test]
+  fn finds_bit_rot() {
+    let d = test_dir!();
+    fs::write(d.get_path("image.jpg"), "image").unwrap();
+    let manifest = Manifest::build(d.root()).unwrap();
+    let modified = fs::metadata(d.get_path("image.jpg"))
+      .unwrap()
+      .modified()
+      .unwrap();
+
+    // Same size and modification time, different content.
+    fs::write(d.get_path("image.jpg"), "imagf").unwrap();
+    File::options()
+      .write(true)
+      .open(d.get_path("image.jpg"))
+      .unwrap()
+      .set_modified(modified)
+      .unwrap();
+
+    let verification = manifest.verify(d.root()).unwrap();
+
+    assert_eq!(verification.corrupted, vec![PathBuf::from("image.jpg")]);
+    assert!(verification.modified.is_empty());
+  }
+
+  #[test]
+  fn separates_modified_files() {
+    let d = test_dir!();
+    fs::write(d.get_path("image.jpg.xmp"), "before").unwrap();
+    let manifest = Manifest::build(d.root()).unwrap();
+
+    fs::write(d.get_path("image.jpg.xmp"), "after!").unwrap();
+    File::options()
+      .write(true)
+      .open(d.get_path("image.jpg.xmp"))
+      .unwrap()
+      .set_modified(SystemTime::UNIX_EPOCH)
+      .unwrap();
+
+    let verification = manifest.verify(d.root()).unwrap();
+
+    assert_eq!(verification.modified, vec![PathBuf::from("image.jpg.xmp")]);
+    assert!(verification.is_ok());
+  }
+}
@@ -0,0 +1,140 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Grouping of bursts and bracketed exposures.
+
+use std::ffi::OsStr;
+
+use chrono::TimeDelta;
+use serde::Deserialize;
+
+use super::{Organizer, report::Action};
+use crate::{
+  io,
+  org,
+  prim::{self, BurstLayout, LivePhotoComponentType, Sidecar},
+};
+
+/// Settings for grouping bursts.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct BurstConfig {
+  /// Maximum time between consecutive images of a camera's sequence, in
+  /// milliseconds.
+  pub max_gap_ms: u32,
+  /// How bursts are laid out in the catalog.
+  pub layout:     BurstLayout,
+}
+
+impl Default for BurstConfig {
+  fn default() -> Self {
+    Self {
+      max_gap_ms: 1000,
+      layout:     BurstLayout::None,
+    }
+  }
+}
+
+impl Organizer {
+  /// Finds bursts and bracketed exposures: images sharing Apple's `BurstUUID`,
+  /// or marked by the camera as part of a sequence (`SequenceNumber`,
+  /// `BracketMode` or `AEBBracketValue`) and shot by the same camera within
+  /// `config.max_gap_ms` of each other. These are then named per
+  /// `config.layout`. Edits and Live Photo videos follow their image.
+  pub fn find_bursts(&mut self, config: &BurstConfig) {
+    log::info!("Finding bursts.");
+
+    let images = self
+      .media
+      .iter_data_indexed()
+      .filter(|(handle, media)| {
+        !self.edit_originals.contains_key(handle)
+          && media.get_live_photo_component_type() != Some(LivePhotoComponentType::Video)
+      })
+      .map(|(handle, media)| {
+        (
+          handle,
+          media
+            .get_sidecar()
+            .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata()),
+        )
+      });
+
+    for (burst, members) in
+      prim::find_bursts(images, TimeDelta::milliseconds(config.max_gap_ms.into()))
+    {
+      log::debug!("Found burst {} ({} images).", burst.id, members.len());
+
+      for handle in members {
+        self.bursts.insert(handle, burst.clone());
+      }
+    }
+
+    self.burst_layout = config.layout;
+  }
+
+  /// Records burst membership (see `find_bursts`) in each image's metadata, as
+  /// a `burst:<id>` keyword.
+  pub fn write_burst_keywords(&mut self) -> Result<(), String> {
+    if self.bursts.is_empty() {
+      return Ok(());
+    }
+
+    log::info!("Writing burst keywords.");
+
+    for (handle, burst) in &self.bursts {
+      let media = &mut self.media[*handle];
+      let metadata = media
+        .get_sidecar()
+        .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata());
+
+      let keyword = burst.get_keyword();
+      if metadata.has_keyword(&keyword) {
+        continue;
+      }
+
+      log::trace!("{media}: Writing burst keyword.");
+
+      let path = org::to_abs_path(&self.source, &metadata.source_file);
+      let arg = format!("-XMP-dc:Subject+={keyword}");
+      io::run_exiftool(Some(&self.source), [OsStr::new(&arg), path.as_os_str()])?;
+
+      let metadata = io::read_metadata(&path)?;
+
+      self.report.action(&path, Action::MetadataUpdated {
+        pass: "burst".to_string(),
+      });
+      self
+        .report
+        .tag_change(&path, "Subject", metadata.get("Subject").as_deref());
+
+      org::update_media_metadata(&mut self.report, &mut self.sidecars, media, metadata);
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test_write_burst_keywords {
+  use super::*;
+  use crate::{prim::Burst, testing::*};
+
+  #[test]
+  fn writes_keyword_to_sidecar_once() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": {},
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    let handle = o.media.find(d.get_path("image.jpg")).unwrap();
+    o.bursts.insert(handle, Burst {
+      id:   "UUID".to_string(),
+      name: "000101_000000000".to_string(),
+    });
+    o.write_burst_keywords().unwrap();
+    o.write_burst_keywords().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Subject", "burst:UUID");
+  }
+}
@@ -0,0 +1,80 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Listing of the cameras in a catalog, for building the camera owner table.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Organizer;
+use crate::prim::{self, Camera, CameraOwner, OwnerMatch};
+
+/// Files shot with a camera and who they are attributed to.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CameraCount {
+  pub files:  usize,
+  /// Owners matched in `owners` (see `count_cameras`). More than one means the
+  /// camera is shared, or its entries are ambiguous.
+  pub owners: BTreeSet<String>,
+}
+
+impl Organizer {
+  /// Counts media files by the camera they were shot with, alongside the
+  /// owners they match in `owners`.
+  pub fn count_cameras(&self, owners: &[CameraOwner]) -> BTreeMap<Camera, CameraCount> {
+    let mut cameras = BTreeMap::<_, CameraCount>::new();
+
+    for media in self.media.iter_data() {
+      let metadata = media.get_metadata();
+      let count = cameras.entry(Camera::from_metadata(metadata)).or_default();
+
+      count.files += 1;
+      match prim::find_camera_owner(owners, metadata) {
+        OwnerMatch::Owner(owner) => {
+          count.owners.insert(owner.to_string());
+        }
+        OwnerMatch::Ambiguous(matched) => {
+          count.owners.extend(matched.into_iter().map(str::to_string));
+        }
+        OwnerMatch::None => {}
+      }
+    }
+
+    cameras
+  }
+}
+
+#[cfg(test)]
+mod test_count_cameras {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn counts_by_camera() {
+    let d = test_dir!(
+      "a.jpg": { "Make": "Canon", "Model": "R5" },
+      "b.jpg": { "Make": "Canon", "Model": "R5" },
+      "c.jpg": { "Make": "Apple", "Model": "iPhone 15 Pro" },
+    );
+
+    let owners = [CameraOwner {
+      owner: "Owner".to_string(),
+      model: Some("R5".to_string()),
+      ..Default::default()
+    }];
+
+    let o = Organizer::import(d.root()).unwrap();
+    let cameras = o.count_cameras(&owners);
+
+    assert_eq!(cameras.len(), 2);
+    assert_eq!(
+      cameras[&Camera {
+        make: Some("Canon".to_string()),
+        model: Some("R5".to_string()),
+        ..Default::default()
+      }],
+      CameraCount {
+        files:  2,
+        owners: BTreeSet::from(["Owner".to_string()]),
+      }
+    );
+  }
+}
@@ -0,0 +1,277 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Linking of imported files to their partners already in the catalog, such as
+//! a Live Photo video imported after its image, or vice versa.
+
+use std::{
+  collections::HashSet,
+  path::{Path, PathBuf},
+};
+
+use super::Organizer;
+use crate::{
+  io,
+  prim::{LivePhotoComponentType, Media, Sidecar, SidecarNaming},
+};
+
+impl Organizer {
+  /// Links Live Photo components being imported without their other half to
+  /// that half already in `catalog`, found by `ContentIdentifier`. Linked
+  /// videos are then kept rather than removed as leftovers, synchronized from
+  /// their catalogued image, and named to match it. Linked images are named to
+  /// match their catalogued video, which is synchronized from them.
+  ///
+  /// Sidecars and darktable duplicates imported without their media file are
+  /// not yet linked to media files in the catalog, as they carry nothing to
+  /// match them by once renamed, so a warning is logged for them.
+  pub fn link_catalog(&mut self, catalog: impl AsRef<Path>) -> Result<(), String> {
+    let unlinked = self
+      .sidecars
+      .iter_data()
+      .filter(|s| s.is_leftover())
+      .count()
+      + self.dupes.iter_data().filter(|d| d.is_leftover()).count();
+    if unlinked > 0 {
+      log::warn!(
+        "{unlinked} sidecar(s) imported without their media file cannot be linked to the catalog, \
+         and will be removed as leftovers."
+      );
+    }
+
+    let content_ids = self
+      .live_photo_map
+      .iter()
+      .filter(|(id, l)| {
+        (l.is_leftover_videos() && !self.catalog_images.contains_key(id))
+          || (l.is_missing_video() && !self.catalog_videos.contains_key(id))
+      })
+      .map(|(id, _)| id.0.clone())
+      .collect::<HashSet<_>>();
+
+    if content_ids.is_empty() {
+      return Ok(());
+    }
+
+    log::info!(
+      "{}: Searching for {} Live Photo partner(s).",
+      catalog.as_ref().display(),
+      content_ids.len()
+    );
+
+    for path in io::find_by_content_id(&catalog, &content_ids)? {
+      let media = Media::new(io::read_metadata(&path)?)?;
+      let Some((id, link)) = media
+        .content_id()
+        .and_then(|id| self.live_photo_map.get(&id).map(|l| (id, l)))
+      else {
+        continue;
+      };
+
+      match media.get_live_photo_component_type() {
+        Some(LivePhotoComponentType::Image) if link.is_leftover_videos() => {
+          log::debug!("{}: Linking imported Live Photo video(s).", path.display());
+          self.catalog_images.insert(id, path);
+        }
+        Some(LivePhotoComponentType::Video) if link.is_missing_video() => {
+          log::debug!("{}: Linking imported Live Photo image(s).", path.display());
+          self.catalog_videos.insert(id, path);
+        }
+        _ => {}
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Gets the file to read a catalogued Live Photo image's metadata from, which
+/// is its sidecar (by either naming scheme) if present.
+pub fn get_catalog_metadata_source(image: impl AsRef<Path>) -> PathBuf {
+  [SidecarNaming::FullName, SidecarNaming::Stem]
+    .into_iter()
+    .map(|n| n.get_path(&image))
+    .find(|p| p.exists())
+    .unwrap_or(image.as_ref().to_path_buf())
+}
+
+#[cfg(test)]
+mod test_link_catalog {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn links_video_to_catalogued_image() {
+    let d = test_dir!(
+      "catalog/2000/01/000101_000000000.heic": {
+        "ContentIdentifier": "ID",
+      },
+      "import/video.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+      },
+    );
+
+    let mut o = Organizer::import(d.get_path("import")).unwrap();
+    o.link_catalog(d.get_path("catalog")).unwrap();
+
+    assert_eq!(o.catalog_images.len(), 1);
+  }
+
+  #[test]
+  fn keeps_and_renames_linked_video() {
+    let d = test_dir!(
+      "catalog/2000/01/000101_000000000.heic": {
+        "ContentIdentifier": "ID",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+      },
+      "import/video.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+        "CreateDate": "2000-01-01T00:00:01",
+      },
+    );
+
+    let mut o = Organizer::import(d.get_path("import")).unwrap();
+    o.link_catalog(d.get_path("catalog")).unwrap();
+    o.remove_live_photo_leftovers().unwrap();
+    o.move_and_rename_files(d.get_path("catalog"), true)
+      .unwrap();
+
+    assert_dir!(d, [
+      "catalog/2000/01/000101_000000000.heic",
+      "catalog/2000/01/000101_000000000.mov",
+    ]);
+  }
+
+  #[test]
+  fn skips_video_already_in_catalog() {
+    let d = test_dir!(
+      "catalog/2000/01/000101_000000000.heic": {
+        "ContentIdentifier": "ID",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+      },
+      "catalog/2000/01/000101_000000000.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+      },
+      "import/video.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+        "CreateDate": "2000-01-01T00:00:01",
+      },
+    );
+
+    let mut o = Organizer::import(d.get_path("import")).unwrap();
+    o.link_catalog(d.get_path("catalog")).unwrap();
+    o.remove_live_photo_leftovers().unwrap();
+    o.move_and_rename_files(d.get_path("catalog"), true)
+      .unwrap();
+
+    assert_dir!(d, [
+      "catalog/2000/01/000101_000000000.heic",
+      "catalog/2000/01/000101_000000000.mov",
+      "import/video.mov",
+    ]);
+  }
+
+  #[test]
+  fn links_and_renames_image_to_catalogued_video() {
+    let d = test_dir!(
+      "catalog/2000/01/000101_000000000.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+      },
+      "import/image.heic": {
+        "ContentIdentifier": "ID",
+        "DateTimeOriginal": "2000-01-01T00:00:01",
+      },
+    );
+
+    let mut o = Organizer::import(d.get_path("import")).unwrap();
+    o.link_catalog(d.get_path("catalog")).unwrap();
+
+    assert_eq!(o.catalog_videos.len(), 1);
+    assert_eq!(o.count_incomplete_live_photos(), 0);
+
+    o.move_and_rename_files(d.get_path("catalog"), true)
+      .unwrap();
+
+    assert_dir!(d, [
+      "catalog/2000/01/000101_000000000.heic",
+      "catalog/2000/01/000101_000000000.mov",
+    ]);
+  }
+
+  #[test]
+  fn moves_duplicate_videos_with_copy_number() {
+    let d = test_dir!(
+      "catalog/2000/01/000101_000000000.heic": {
+        "ContentIdentifier": "ID",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+      },
+      "import/video.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+      },
+      "import/video_2.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "avc1",
+      },
+    );
+
+    let mut o = Organizer::import(d.get_path("import")).unwrap();
+    o.link_catalog(d.get_path("catalog")).unwrap();
+    o.move_and_rename_files(d.get_path("catalog"), true)
+      .unwrap();
+
+    assert_dir!(d, [
+      "catalog/2000/01/000101_000000000.heic",
+      "catalog/2000/01/000101_000000000.mov",
+      "catalog/2000/01/000101_000000000_b.mov",
+    ]);
+  }
+
+  #[test]
+  fn ignores_unrelated_images() {
+    let d = test_dir!(
+      "catalog/2000/01/000101_000000000.heic": {
+        "ContentIdentifier": "OTHER",
+      },
+      "import/video.mov": {
+        "ContentIdentifier": "ID",
+        "CompressorID": "hvc1",
+      },
+    );
+
+    let mut o = Organizer::import(d.get_path("import")).unwrap();
+    o.link_catalog(d.get_path("catalog")).unwrap();
+
+    assert!(o.catalog_images.is_empty());
+  }
+}
+
+#[cfg(test)]
+mod test_get_catalog_metadata_source {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn prefers_sidecar() {
+    let d = test_dir!();
+    fs::write(d.get_path("image.heic"), "image").unwrap();
+
+    assert_eq!(
+      get_catalog_metadata_source(d.get_path("image.heic")),
+      d.get_path("image.heic")
+    );
+
+    fs::write(d.get_path("image.heic.xmp"), "sidecar").unwrap();
+
+    assert_eq!(
+      get_catalog_metadata_source(d.get_path("image.heic")),
+      d.get_path("image.heic.xmp")
+    );
+  }
+}
@@ -0,0 +1,193 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Read-only catalog checks, combining metadata validation with structural
+//! checks of file associations (sidecars and Live Photos).
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  path::{Path, PathBuf},
+};
+
+use super::{Organizer, Report, report::Finding, rules::Severity};
+use crate::prim::Sidecar;
+
+/// Files failing each check, keyed by rule name, alongside the rule's
+/// severity.
+#[derive(Debug, Default)]
+pub struct CheckSummary {
+  rules: BTreeMap<String, (Severity, BTreeSet<PathBuf>)>,
+}
+
+impl CheckSummary {
+  /// Number of error-severity failures. Files failing multiple rules are
+  /// counted once per rule.
+  pub fn errors(&self) -> usize {
+    self
+      .rules
+      .values()
+      .filter(|(s, _)| *s == Severity::Error)
+      .map(|(_, f)| f.len())
+      .sum()
+  }
+
+  /// Iterates over each failed rule, alongside its severity and the files that
+  /// failed it.
+  pub fn iter_rules(&self) -> impl Iterator<Item = (&str, Severity, &BTreeSet<PathBuf>)> {
+    self.rules.iter().map(|(r, (s, f))| (r.as_str(), *s, f))
+  }
+}
+
+impl Organizer {
+  /// Runs all validation checks, as well as structural checks, without
+  /// modifying any files. Structural checks include:
+  /// - Sidecars (initial or duplicate) without a media file.
+  /// - Live Photos missing either their image or video.
+  /// - Live Photos with multiple images or videos.
+  pub fn check(&mut self) -> CheckSummary {
+    log::info!("Checking catalog.");
+
+    for sidecar in self.sidecars.iter_data().filter(|s| s.is_leftover()) {
+      fail(
+        &mut self.report,
+        sidecar,
+        "orphaned_sidecar",
+        "No media file.",
+      );
+    }
+
+    for dupe in self.dupes.iter_data().filter(|s| s.is_leftover()) {
+      fail(&mut self.report, dupe, "orphaned_sidecar", "No media file.");
+    }
+
+    for link in self.live_photo_map.values() {
+      let image_failures = [
+        (
+          link.is_missing_video(),
+          "live_photo_missing_video",
+          "Live Photo image has no video.",
+        ),
+        (
+          link.has_duplicate_images(),
+          "live_photo_duplicate",
+          "Live Photo has multiple images.",
+        ),
+      ];
+
+      let video_failures = [
+        (
+          link.is_leftover_videos(),
+          "live_photo_missing_image",
+          "Live Photo video has no image.",
+        ),
+        (
+          link.has_duplicate_videos(),
+          "live_photo_duplicate",
+          "Live Photo has multiple videos.",
+        ),
+      ];
+
+      for (_, rule, message) in image_failures.into_iter().filter(|(f, ..)| *f) {
+        for handle in link.iter_images() {
+          fail(&mut self.report, &self.media[handle], rule, message);
+        }
+      }
+
+      for (_, rule, message) in video_failures.into_iter().filter(|(f, ..)| *f) {
+        for handle in link.iter_videos() {
+          fail(&mut self.report, &self.media[handle], rule, message);
+        }
+      }
+    }
+
+    self.enable_attribution_validation();
+    self.enable_camera_validation();
+    self.enable_date_time_validation();
+    self.enable_location_validation();
+    self.validate();
+
+    summarize(&self.report)
+  }
+}
+
+/// Logs and records a structural check failure for the file at `path`.
+fn fail(report: &mut Report, path: impl AsRef<Path>, rule: &str, message: &str) {
+  log::warn!("{}: {message}", path.as_ref().display());
+
+  report.finding(path, Finding {
+    rule:     rule.to_string(),
+    severity: Severity::Error,
+    message:  message.to_string(),
+  });
+}
+
+/// Groups all findings in `report` by rule.
+fn summarize(report: &Report) -> CheckSummary {
+  let mut summary = CheckSummary::default();
+
+  for file in report.iter_files() {
+    for finding in &file.findings {
+      summary
+        .rules
+        .entry(finding.rule.clone())
+        .or_insert((finding.severity, BTreeSet::new()))
+        .1
+        .insert(file.path.clone());
+    }
+  }
+
+  summary
+}
+
+#[cfg(test)]
+mod test_check {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn finds_structural_failures() {
+    let d = test_dir!(
+      "image.jpg": {
+        "ContentIdentifier": "A",
+      },
+      "video.mov": {
+        "ContentIdentifier": "B",
+        "CompressorID": "hvc1",
+      },
+      "orphan.jpg.xmp": {},
+    );
+
+    let mut organizer = Organizer::load_catalog(d.root(), None::<&Path>).unwrap();
+    let summary = organizer.check();
+
+    let rules = summary
+      .iter_rules()
+      .map(|(r, _, f)| (r, f.iter().cloned().collect::<Vec<_>>()))
+      .collect::<BTreeMap<_, _>>();
+
+    assert_eq!(rules["orphaned_sidecar"], vec![PathBuf::from(
+      "orphan.jpg.xmp"
+    )]);
+    assert_eq!(rules["live_photo_missing_video"], vec![PathBuf::from(
+      "image.jpg"
+    )]);
+    assert_eq!(rules["live_photo_missing_image"], vec![PathBuf::from(
+      "video.mov"
+    )]);
+  }
+
+  #[test]
+  fn counts_files_once_per_rule() {
+    let d = test_dir!(
+      "image.jpg": {},
+    );
+
+    let mut organizer = Organizer::load_catalog(d.root(), None::<&Path>).unwrap();
+    let summary = organizer.check();
+
+    assert_eq!(summary.errors(), 4);
+    assert_eq!(
+      summary.iter_rules().map(|(r, ..)| r).collect::<Vec<_>>(),
+      vec!["attribution", "camera", "date_time", "location"]
+    );
+  }
+}
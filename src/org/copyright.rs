@@ -0,0 +1,138 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Enforcement of the copyright policy on existing rights tags.
+
+use std::ffi::OsStr;
+
+use super::{Organizer, report::Action};
+use crate::{
+  io,
+  org,
+  prim::{self, CopyrightConfig, Sidecar},
+};
+
+impl Organizer {
+  /// Sets the policy that rights tags (e.g. `Copyright`) are written and
+  /// validated against.
+  pub fn set_copyright_policy(&mut self, config: CopyrightConfig) {
+    self.copyright = config;
+  }
+
+  /// Counts media files whose rights tags do not match the copyright policy.
+  /// Existing `Copyright` tags are only checked if a template is configured.
+  pub fn count_nonconforming_copyrights(&self) -> usize {
+    self
+      .media
+      .iter_data()
+      .map(|media| {
+        media
+          .get_sidecar()
+          .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata())
+      })
+      .filter(|metadata| {
+        let expected = self.copyright.get_enforced(metadata);
+        !prim::find_rights_mismatches(metadata, &expected).is_empty()
+      })
+      .count()
+  }
+
+  /// Rewrites rights tags not matching the copyright policy (see
+  /// `count_nonconforming_copyrights`), including existing ones.
+  pub fn rewrite_copyrights(&mut self) -> Result<(), String> {
+    log::info!("Rewriting copyrights.");
+
+    for media in self.media.iter_data_mut() {
+      let metadata = media
+        .get_sidecar()
+        .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata());
+
+      let expected = self.copyright.get_enforced(metadata);
+      let mismatches = prim::find_rights_mismatches(metadata, &expected);
+
+      if mismatches.is_empty() {
+        continue;
+      }
+
+      log::trace!("{media}: Rewriting copyright.");
+
+      let path = org::to_abs_path(&self.source, &metadata.source_file);
+      let args = mismatches
+        .iter()
+        .map(|(tag, value)| prim::get_rights_write_arg(tag, value))
+        .collect::<Vec<_>>();
+      let tags = mismatches.iter().map(|(tag, _)| *tag).collect::<Vec<_>>();
+
+      io::run_exiftool(
+        Some(&self.source),
+        args.iter().map(OsStr::new).chain([path.as_os_str()]),
+      )?;
+
+      let metadata = io::read_metadata(&path)?;
+
+      self.report.action(&path, Action::MetadataUpdated {
+        pass: "copyright_rewrite".to_string(),
+      });
+      for tag in tags {
+        self
+          .report
+          .tag_change(&path, tag, metadata.get(tag).as_deref());
+      }
+
+      org::update_media_metadata(&mut self.report, &mut self.sidecars, media, metadata);
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test_rewrite_copyrights {
+  use super::*;
+  use crate::{prim::CopyrightTerms, testing::*};
+
+  #[test]
+  fn rewrites_nonconforming_copyright() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": {
+        "Copyright": "Copyright Creator",
+        "Creator": "Creator",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+      },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_copyright_policy(CopyrightConfig {
+      terms: CopyrightTerms {
+        template: Some("© {year} {creator}".to_string()),
+        ..Default::default()
+      },
+      ..Default::default()
+    });
+
+    assert_eq!(o.count_nonconforming_copyrights(), 1);
+    o.rewrite_copyrights().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Copyright", "© 2000 Creator");
+    assert_eq!(o.count_nonconforming_copyrights(), 0);
+  }
+
+  #[test]
+  fn keeps_custom_copyright_without_template() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": {
+        "Copyright": "© Creator",
+        "Creator": "Creator",
+      },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_copyright_policy(CopyrightConfig::default());
+
+    assert_eq!(o.count_nonconforming_copyrights(), 0);
+    o.rewrite_copyrights().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Copyright", "© Creator");
+  }
+}
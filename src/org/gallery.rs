@@ -0,0 +1,101 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Selection of media files and their details for a static HTML gallery.
+
+use std::collections::HashSet;
+
+use super::{Organizer, stage_6_organization};
+use crate::{
+  gallery::GalleryItem,
+  prim::{Handle, Media},
+};
+
+impl Organizer {
+  /// Gets the gallery items for the media files at `handles`, using metadata
+  /// from their sidecars where present. Live Photo videos are shown with their
+  /// image, so are only included if it is not among `handles`.
+  pub fn get_gallery_items(&self, handles: &[Handle<Media>]) -> Vec<GalleryItem> {
+    let shown_videos = handles
+      .iter()
+      .filter_map(|&h| self.get_live_photo_video(h))
+      .collect::<HashSet<_>>();
+
+    handles
+      .iter()
+      .filter(|h| !shown_videos.contains(*h))
+      .map(|&handle| {
+        let metadata = self.get_media_metadata(handle);
+
+        let location = [&metadata.city, &metadata.state, &metadata.country]
+          .into_iter()
+          .flatten()
+          .cloned()
+          .collect::<Vec<_>>();
+        let camera = [&metadata.make, &metadata.model]
+          .into_iter()
+          .flatten()
+          .cloned()
+          .collect::<Vec<_>>();
+
+        GalleryItem {
+          path:             self.get_media_path(handle),
+          stem:             stage_6_organization::get_expected_stem(metadata),
+          is_video:         self.media[handle].is_video(),
+          caption:          ["Description", "ImageDescription", "Caption-Abstract"]
+            .into_iter()
+            .find_map(|t| metadata.get(t)),
+          date:             metadata
+            .get_date_time_original()
+            .map(|(d, _)| d.format("%Y-%m-%d %H:%M:%S").to_string()),
+          location:         (!location.is_empty()).then(|| location.join(", ")),
+          camera:           (!camera.is_empty()).then(|| camera.join(" ")),
+          live_photo_video: self
+            .get_live_photo_video(handle)
+            .map(|h| self.get_media_path(h)),
+        }
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test_get_gallery_items {
+  use super::*;
+  use crate::{org::Query, testing::*};
+
+  #[test]
+  fn shows_live_photo_video_with_image() {
+    let d = test_dir!(
+      "image.jpg": {
+        "ContentIdentifier": "A",
+        "DateTimeOriginal": "2023:05:01 12:00:00",
+        "City": "Kyoto",
+        "Country": "Japan",
+      },
+      "image.mov": { "ContentIdentifier": "A", "CompressorID": "hvc1" },
+    );
+
+    let o = Organizer::import(d.root()).unwrap();
+    let items = o.get_gallery_items(&o.find(&Query::parse("has:ContentIdentifier").unwrap()));
+
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].stem.as_deref(), Some("2023/05/230501_120000000"));
+    assert_eq!(items[0].location.as_deref(), Some("Kyoto, Japan"));
+    assert_eq!(items[0].live_photo_video, Some(d.get_path("image.mov")));
+  }
+
+  #[test]
+  fn shows_live_photo_video_without_selected_image() {
+    let d = test_dir!(
+      "image.jpg": { "ContentIdentifier": "A" },
+      "image.mov": { "ContentIdentifier": "A", "CompressorID": "hvc1" },
+    );
+
+    let o = Organizer::import(d.root()).unwrap();
+    let items = o.get_gallery_items(&o.find(&Query::parse("has:CompressorID").unwrap()));
+
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_video);
+    assert_eq!(items[0].path, d.get_path("image.mov"));
+  }
+}
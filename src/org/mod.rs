@@ -3,24 +3,54 @@
 //! Core organizer module for managing a catalog of media files and their
 //! sidecars.
 
+mod bursts;
+mod cameras;
+mod catalog_links;
+mod check;
+mod copyright;
+mod gallery;
+mod query;
+mod report;
+mod rules;
+mod set_tags;
+mod sidecar_naming;
 mod stage_1_cleanup;
 mod stage_2_sidecars;
 mod stage_3_metadata;
 mod stage_4_synchronization;
 mod stage_5_validation;
 mod stage_6_organization;
+mod stats;
+mod takeout;
+mod video;
 
 use std::{
   collections::{HashMap, HashSet},
+  ffi::OsStr,
   path::{Path, PathBuf},
 };
 
+pub use bursts::BurstConfig;
+pub use query::Query;
+use report::FileRole;
+pub use report::{Action, Report};
+pub use rules::Rule;
+pub use set_tags::TagEdit;
+pub use stage_1_cleanup::EditPolicy;
 use stage_3_metadata::MetadataUpdateConfig;
+pub use stage_5_validation::PlausibilityConfig;
 use stage_5_validation::ValidationConfig;
+pub use stats::CatalogStats;
+pub use video::VideoConfig;
 
 use crate::{
   io,
   prim::{
+    self,
+    Burst,
+    BurstLayout,
+    CopyrightConfig,
+    EditGroup,
     FileCategory,
     FileMap,
     Handle,
@@ -32,6 +62,7 @@ use crate::{
     Sidecar,
     SidecarDupe,
     SidecarInitial,
+    SidecarNaming,
   },
 };
 
@@ -50,9 +81,9 @@ use crate::{
 /// 6. Automatic moving and renaming by timestamp.
 ///
 /// Note that the `Organizer` will assume that any file associations (e.g.
-/// sidecars or Live Photos) are represented within the input directory. This
-/// means, for example, that a Live Photo video imported separately from its
-/// image will not be linked correctly.
+/// sidecars or Live Photos) are represented within the input directory. The
+/// one exception is Live Photo components imported separately from their other
+/// half, which can be linked to the catalog with `link_catalog`.
 #[derive(Default)]
 pub struct Organizer {
   source: PathBuf,
@@ -61,20 +92,42 @@ pub struct Organizer {
   media:    FileMap<Media>,
   sidecars: FileMap<SidecarInitial>,
   dupes:    FileMap<SidecarDupe>,
+  takeout:  Vec<PathBuf>,
+
+  adjustments:    Vec<PathBuf>,
+  edit_groups:    HashMap<Handle<Media>, EditGroup>,
+  edit_originals: HashMap<Handle<Media>, Handle<Media>>,
+
+  companions:       Vec<PathBuf>,
+  video_companions: HashMap<Handle<Media>, Vec<PathBuf>>,
 
   live_photo_map: HashMap<LivePhotoID, LivePhotoLinker>,
+  catalog_images: HashMap<LivePhotoID, PathBuf>,
+  catalog_videos: HashMap<LivePhotoID, PathBuf>,
+
+  bursts:       HashMap<Handle<Media>, Burst>,
+  burst_layout: BurstLayout,
+
+  sidecar_naming: SidecarNaming,
 
   metadata_updates: MetadataUpdateConfig,
+  copyright:        CopyrightConfig,
 
-  validation:  ValidationConfig,
-  valid_media: HashSet<Handle<Media>>,
+  validation:     ValidationConfig,
+  valid_media:    HashSet<Handle<Media>>,
+  drifted_media:  Option<HashSet<Handle<Media>>>,
+  declined_media: HashSet<Handle<Media>>,
+
+  report: Report,
 }
 
 impl Organizer {
   /// Create a new `Organizer` importing all multimedia files from path
-  /// (recursively).
+  /// (recursively). The integrity of each media file is verified first (see
+  /// `verify_integrity`), leaving out those found damaged so nothing else (e.g.
+  /// Live Photo duplicate removal) acts on them.
   pub fn import(path: impl AsRef<Path>) -> Result<Self, String> {
-    Self::new(path, None::<&Path>)
+    Self::new(path, None::<&Path>, true)
   }
 
   /// Create a new `Organizer` cleaning up an existing catalog at `path`,
@@ -83,11 +136,15 @@ impl Organizer {
     path: impl AsRef<Path>,
     trash: Option<impl AsRef<Path>>,
   ) -> Result<Self, String> {
-    Self::new(path, trash)
+    Self::new(path, trash, false)
   }
 
-  /// Create a new `Organizer`.
-  fn new(path: impl AsRef<Path>, trash: Option<impl AsRef<Path>>) -> Result<Self, String> {
+  /// Create a new `Organizer`, optionally leaving out damaged media files.
+  fn new(
+    path: impl AsRef<Path>,
+    trash: Option<impl AsRef<Path>>,
+    verify: bool,
+  ) -> Result<Self, String> {
     if path.as_ref().is_relative() {
       return Err(format!(
         "{}: Catalog path is not absolute.",
@@ -122,18 +179,60 @@ impl Organizer {
     let mut organizer = Self {
       source: path.as_ref().to_path_buf(),
       trash: trash.map(|p| p.as_ref().to_path_buf()),
+      report: Report::new(&path),
       ..Default::default()
     };
 
     let metadata = io::read_metadata_recursive(path, organizer.trash.as_ref())?;
 
     organizer.load_metadata(metadata)?;
+    if verify {
+      organizer.verify_integrity()?;
+      organizer.exclude_damaged_media();
+    }
     organizer.link_sidecars();
+    organizer.link_edit_groups();
+    organizer.link_companions();
     organizer.link_live_photos();
+    organizer.record_groups();
 
     Ok(organizer)
   }
 
+  /// Consumes the `Organizer`, returning the report of everything done so far.
+  pub fn into_report(self) -> Report {
+    self.report
+  }
+
+  /// Counts Live Photos with only an image or only a video present, such as
+  /// when the other half has not finished syncing yet. Components linked to
+  /// their other half in the catalog are complete.
+  pub fn count_incomplete_live_photos(&self) -> usize {
+    self
+      .live_photo_map
+      .iter()
+      .filter(|(id, l)| {
+        (l.is_missing_video() && !self.catalog_videos.contains_key(id))
+          || (l.is_leftover_videos() && !self.catalog_images.contains_key(id))
+      })
+      .count()
+  }
+
+  /// Counts camera RAW files without an image sharing their name (e.g. the
+  /// JPEG of a RAW+JPEG pair), such as when it has not finished syncing yet.
+  pub fn count_incomplete_raw_pairs(&self) -> usize {
+    map_by_stem(&self.source, &self.media)
+      .into_values()
+      .filter(|c| {
+        c.iter().any(|&h| self.media[h].is_raw())
+          && !c.iter().any(|&h| {
+            let media = &self.media[h];
+            !media.is_raw() && !media.is_video()
+          })
+      })
+      .count()
+  }
+
   /// Loads in all metadata (generally for `ExifTool`'s scan).
   fn load_metadata(&mut self, metadata: impl IntoIterator<Item = Metadata>) -> Result<(), String> {
     log::info!("Parsing metadata.");
@@ -143,6 +242,9 @@ impl Organizer {
       &mut self.media,
       &mut self.sidecars,
       &mut self.dupes,
+      &mut self.takeout,
+      &mut self.adjustments,
+      &mut self.companions,
       metadata,
     )
   }
@@ -165,12 +267,150 @@ impl Organizer {
       &mut self.media,
       Media::add_dupe,
     );
+    link_stem_sidecars(&self.source, &mut self.sidecars, &mut self.media);
+  }
+
+  fn link_edit_groups(&mut self) {
+    log::info!("Linking Apple Photos edits to originals.");
+
+    link_edit_groups(
+      &self.source,
+      &self.media,
+      &mut self.adjustments,
+      &mut self.edit_groups,
+      &mut self.edit_originals,
+    );
+  }
+
+  fn link_companions(&mut self) {
+    log::info!("Linking companion files to videos.");
+
+    link_companions(
+      &self.source,
+      &self.media,
+      &mut self.companions,
+      &mut self.video_companions,
+    );
   }
 
   fn link_live_photos(&mut self) {
     log::info!("Linking Live Photos images to videos.");
 
-    link_live_photos(&mut self.media, &mut self.live_photo_map);
+    link_live_photos(
+      &mut self.media,
+      &self.edit_originals,
+      &mut self.live_photo_map,
+    );
+  }
+
+  /// Unloads media files with integrity errors, leaving them in place. This
+  /// must run before linking, so that no other file refers to them.
+  fn exclude_damaged_media(&mut self) {
+    let damaged = self
+      .media
+      .iter_data_indexed()
+      .filter_map(|(h, m)| {
+        let errors = &m.get_integrity()?.errors;
+        (!errors.is_empty()).then(|| (h, errors.join(" ")))
+      })
+      .collect::<Vec<_>>();
+
+    for (handle, errors) in damaged {
+      let Some(media) = self.media.get_entry_mut(handle).take() else {
+        continue;
+      };
+
+      log::warn!("{media}: Not importing damaged file ({errors}).");
+      self.report.action(&media, Action::NotMoved {
+        reason: format!("File failed integrity check ({errors})."),
+      });
+    }
+  }
+
+  /// Records which group each loaded file belongs to in the run report. A
+  /// group is named for its main media file, which for Live Photos is the best
+  /// image, and for edits is (the group of) the original.
+  fn record_groups(&mut self) {
+    for (handle_media, media) in self.media.iter_data_indexed() {
+      let role = if self.edit_originals.contains_key(&handle_media) {
+        FileRole::Edit
+      } else {
+        match media.get_live_photo_component_type() {
+          Some(LivePhotoComponentType::Image) => FileRole::LivePhotoImage,
+          Some(LivePhotoComponentType::Video) => FileRole::LivePhotoVideo,
+          None => FileRole::Media,
+        }
+      };
+
+      let group = self.media[self.get_group_handle(handle_media)].as_ref();
+
+      self.report.set_group(media, role, Some(group));
+
+      if let Some(handle_sidecar) = media.get_sidecar() {
+        self.report.set_group(
+          &self.sidecars[handle_sidecar],
+          FileRole::Sidecar,
+          Some(group),
+        );
+      }
+
+      for handle_dupe in media.iter_dupes() {
+        self
+          .report
+          .set_group(&self.dupes[handle_dupe], FileRole::Dupe, Some(group));
+      }
+    }
+
+    for (handle_original, edit_group) in &self.edit_groups {
+      let group = self.media[self.get_group_handle(*handle_original)].as_ref();
+
+      for path in edit_group.iter_adjustments() {
+        self
+          .report
+          .set_group(path, FileRole::Adjustments, Some(group));
+      }
+    }
+
+    for path in &self.adjustments {
+      self.report.set_group(path, FileRole::Adjustments, None);
+    }
+
+    for (handle_video, companions) in &self.video_companions {
+      let group = self.media[self.get_group_handle(*handle_video)].as_ref();
+
+      for path in companions {
+        self
+          .report
+          .set_group(path, FileRole::Companion, Some(group));
+      }
+    }
+
+    for path in &self.companions {
+      self.report.set_group(path, FileRole::Companion, None);
+    }
+
+    for sidecar in self.sidecars.iter_data().filter(|s| s.is_leftover()) {
+      self.report.set_group(sidecar, FileRole::Sidecar, None);
+    }
+
+    for dupe in self.dupes.iter_data().filter(|s| s.is_leftover()) {
+      self.report.set_group(dupe, FileRole::Dupe, None);
+    }
+  }
+}
+
+impl Organizer {
+  /// Gets the main media file of the group `handle` belongs to.
+  fn get_group_handle(&self, handle: Handle<Media>) -> Handle<Media> {
+    if let Some(&handle_original) = self.edit_originals.get(&handle) {
+      return self.get_group_handle(handle_original);
+    }
+
+    self.media[handle]
+      .content_id()
+      .and_then(|id| self.live_photo_map.get(&id))
+      .filter(|l| !l.is_leftover_videos())
+      .map_or(handle, LivePhotoLinker::get_image_best)
   }
 }
 
@@ -178,12 +418,35 @@ fn to_abs_path(dir: impl AsRef<Path>, path_rel: impl AsRef<Path>) -> PathBuf {
   dir.as_ref().join(path_rel).clone()
 }
 
-/// Converts metadata into collections of media files and sidecars.
+/// Updates the metadata of `media`, or of its sidecar if it has one, to
+/// `metadata`, recording which tags changed in `report`.
+fn update_media_metadata(
+  report: &mut Report,
+  sidecars: &mut FileMap<SidecarInitial>,
+  media: &mut Media,
+  metadata: Metadata,
+) {
+  if let Some(sidecar) = media.get_sidecar().map(|h| &mut sidecars[h]) {
+    report.metadata_diff(&*sidecar, sidecar.get_metadata(), &metadata);
+    sidecar.update_metadata(metadata);
+  } else {
+    report.metadata_diff(&*media, media.get_metadata(), &metadata);
+    media.update_metadata(metadata);
+  }
+}
+
+/// Converts metadata into collections of media files and sidecars, including
+/// the paths of Google Takeout JSON sidecars, Apple Photos adjustments and
+/// video companion files.
+#[allow(clippy::too_many_arguments)]
 fn load_metadata(
   dir_root: impl AsRef<Path>,
   media: &mut FileMap<Media>,
   sidecars: &mut FileMap<SidecarInitial>,
   dupes: &mut FileMap<SidecarDupe>,
+  takeout: &mut Vec<PathBuf>,
+  adjustments: &mut Vec<PathBuf>,
+  companions: &mut Vec<PathBuf>,
   metadata: impl IntoIterator<Item = Metadata>,
 ) -> Result<(), String> {
   for m in metadata {
@@ -197,6 +460,18 @@ fn load_metadata(
       FileCategory::SidecarDupe => {
         dupes.insert(to_abs_path(&dir_root, &m), SidecarDupe::new(m)?);
       }
+      FileCategory::Takeout => {
+        takeout.push(to_abs_path(&dir_root, &m));
+      }
+      FileCategory::Adjustments => {
+        adjustments.push(to_abs_path(&dir_root, &m));
+      }
+      FileCategory::Companion => {
+        companions.push(to_abs_path(&dir_root, &m));
+      }
+      FileCategory::Other => {
+        log::debug!("{m}: Ignoring file, as it is neither media nor a sidecar.");
+      }
     }
   }
 
@@ -212,6 +487,10 @@ fn link_sidecars_by_type<S: Sidecar>(
   add_sidecar: fn(&mut Media, Handle<S>),
 ) {
   for (handle_sidecar, sidecar) in sidecar_map.iter_data_mut_indexed() {
+    if sidecar.get_naming() == SidecarNaming::Stem {
+      continue;
+    }
+
     if let Some(handle_media) = media_map.find(to_abs_path(&dir_root, sidecar.get_media_path())) {
       add_sidecar(&mut media_map[handle_media], handle_sidecar);
       sidecar.set_media_handle(handle_media);
@@ -219,13 +498,183 @@ fn link_sidecars_by_type<S: Sidecar>(
   }
 }
 
+/// Links stem-named sidecars (e.g. `image.xmp`, from Lightroom or Capture One)
+/// to a media file sharing their stem within the same directory, and not
+/// already linked to a sidecar. Where several share it (e.g. `image.cr2` and
+/// `image.jpg`), see `pick_stem_sidecar_media`. Must run after full-name
+/// sidecars are linked, as sidecars left unlinked by those are tried here too
+/// (e.g. `2023.01.01.xmp` for `2023.01.01.cr2`, named as if full-name).
+fn link_stem_sidecars(
+  dir_root: impl AsRef<Path>,
+  sidecar_map: &mut FileMap<SidecarInitial>,
+  media_map: &mut FileMap<Media>,
+) {
+  let by_stem = map_by_stem(&dir_root, media_map);
+
+  for (handle_sidecar, sidecar) in sidecar_map.iter_data_mut_indexed() {
+    if sidecar.get_media_handle().is_some() {
+      continue;
+    }
+
+    let candidates = by_stem
+      .get(&to_abs_path(&dir_root, &*sidecar).with_extension(""))
+      .into_iter()
+      .flatten()
+      .map(|&h| (h, &media_map[h]))
+      .filter(|(_, m)| m.is_missing_sidecar());
+
+    if let Some(handle_media) = prim::pick_stem_sidecar_media(candidates) {
+      media_map[handle_media].set_sidecar(handle_sidecar);
+      sidecar.set_media_handle(handle_media);
+      sidecar.set_stem_named();
+    }
+  }
+}
+
+/// Links Apple Photos edited renders (e.g. `IMG_E1234.HEIC`) and adjustments
+/// (e.g. `IMG_1234.AAE`) to their original media files, by file name within the
+/// same directory. Unmatched adjustments are left in `adjustments`.
+fn link_edit_groups(
+  dir_root: impl AsRef<Path>,
+  media_map: &FileMap<Media>,
+  adjustments: &mut Vec<PathBuf>,
+  edit_groups: &mut HashMap<Handle<Media>, EditGroup>,
+  edit_originals: &mut HashMap<Handle<Media>, Handle<Media>>,
+) {
+  let by_stem = map_by_stem(&dir_root, media_map);
+
+  for (handle, media) in media_map.iter_data_indexed() {
+    let path = to_abs_path(&dir_root, media);
+
+    let Some(original_stem) = path
+      .file_stem()
+      .and_then(OsStr::to_str)
+      .and_then(prim::get_edited_original_stem)
+    else {
+      continue;
+    };
+
+    if let Some(original) = by_stem
+      .get(&path.with_file_name(original_stem))
+      .and_then(|c| pick_original(c, media_map, Some(&media.get_metadata().file_type)))
+    {
+      edit_groups.entry(original).or_default().insert_edit(handle);
+      edit_originals.insert(handle, original);
+    }
+  }
+
+  adjustments.retain(|path| {
+    let Some(stem) = path.file_stem().and_then(OsStr::to_str) else {
+      return true;
+    };
+    let (original_stem, _) = prim::get_adjustments_original_stem(stem);
+
+    match by_stem
+      .get(&path.with_file_name(original_stem))
+      .and_then(|c| pick_original(c, media_map, None))
+    {
+      Some(original) => {
+        edit_groups
+          .entry(original)
+          .or_default()
+          .insert_adjustments(path.clone());
+        false
+      }
+      None => true,
+    }
+  });
+}
+
+/// Links companion files (e.g. `GX010123.THM` or `DJI_0001.SRT`) to the video
+/// sharing their stem within the same directory. `GoPro` names its
+/// low-resolution proxies `GL` rather than `GH` or `GX`, so these are matched
+/// too. Unmatched companions are left in `companions`.
+fn link_companions(
+  dir_root: impl AsRef<Path>,
+  media_map: &FileMap<Media>,
+  companions: &mut Vec<PathBuf>,
+  video_companions: &mut HashMap<Handle<Media>, Vec<PathBuf>>,
+) {
+  let by_stem = map_by_stem(&dir_root, media_map);
+
+  companions.retain(|path| {
+    let Some(stem) = path.file_stem().and_then(OsStr::to_str) else {
+      return true;
+    };
+
+    let stems = match stem.strip_prefix("GL") {
+      Some(rest) => vec![stem.to_string(), format!("GH{rest}"), format!("GX{rest}")],
+      None => vec![stem.to_string()],
+    };
+
+    let video = stems
+      .iter()
+      .filter_map(|s| by_stem.get(&path.with_file_name(s)))
+      .flatten()
+      .copied()
+      .find(|&h| media_map[h].is_video());
+
+    match video {
+      Some(video) => {
+        video_companions
+          .entry(video)
+          .or_default()
+          .push(path.clone());
+        false
+      }
+      None => true,
+    }
+  });
+}
+
+/// Maps the absolute path without extension of each media file to those
+/// sharing it.
+fn map_by_stem(
+  dir_root: impl AsRef<Path>,
+  media_map: &FileMap<Media>,
+) -> HashMap<PathBuf, Vec<Handle<Media>>> {
+  let mut by_stem = HashMap::<PathBuf, Vec<Handle<Media>>>::new();
+  for (handle, media) in media_map.iter_data_indexed() {
+    by_stem
+      .entry(to_abs_path(&dir_root, media).with_extension(""))
+      .or_default()
+      .push(handle);
+  }
+  by_stem
+}
+
+/// Picks which of `candidates` sharing a stem is the original, preferring the
+/// same `file_type` (e.g. `IMG_1234.MOV` for `IMG_E1234.MOV`), then any besides
+/// a Live Photo video.
+fn pick_original(
+  candidates: &[Handle<Media>],
+  media_map: &FileMap<Media>,
+  file_type: Option<&str>,
+) -> Option<Handle<Media>> {
+  candidates
+    .iter()
+    .copied()
+    .find(|&h| file_type.is_some_and(|t| media_map[h].get_metadata().file_type == t))
+    .or_else(|| {
+      candidates.iter().copied().find(|&h| {
+        media_map[h].get_live_photo_component_type() != Some(LivePhotoComponentType::Video)
+      })
+    })
+}
+
 /// Link Live Photo images to their videos, and vice versa. This is based on the
-/// `ContentIdentifier` tag from `ExifTool`.
+/// `ContentIdentifier` tag from `ExifTool`. Apple Photos edits
+/// (`edit_originals`) are left out, so are not treated as duplicates.
 fn link_live_photos(
   media_map: &mut FileMap<Media>,
+  edit_originals: &HashMap<Handle<Media>, Handle<Media>>,
   live_photo_map: &mut HashMap<LivePhotoID, LivePhotoLinker>,
 ) {
   for (media_handle, media) in media_map.iter_data_mut_indexed() {
+    if edit_originals.contains_key(&media_handle) {
+      continue;
+    }
+
     if let Some(comp_type) = media.get_live_photo_component_type() {
       let link = live_photo_map
         .entry(media.content_id().unwrap())
@@ -251,7 +700,7 @@ mod test_new {
   #[test]
   fn errors_if_catalog_path_does_not_exist() {
     assert_err!(
-      Organizer::new("/path/does/not/exist", None::<&Path>),
+      Organizer::new("/path/does/not/exist", None::<&Path>, false),
       "Catalog path does not exist."
     );
   }
@@ -259,7 +708,7 @@ mod test_new {
   #[test]
   fn errors_if_catalog_path_is_relative() {
     assert_err!(
-      Organizer::new("relative/path", None::<&Path>),
+      Organizer::new("relative/path", None::<&Path>, false),
       "Catalog path is not absolute."
     );
   }
@@ -268,7 +717,7 @@ mod test_new {
   fn errors_if_trash_path_does_not_exist() {
     let d = test_dir!();
     assert_err!(
-      Organizer::new(d.root(), Some("/path/does/not/exist")),
+      Organizer::new(d.root(), Some("/path/does/not/exist"), false),
       "Trash path does not exist."
     );
   }
@@ -277,12 +726,40 @@ mod test_new {
   fn errors_if_trash_path_is_relative() {
     let d = test_dir!();
     assert_err!(
-      Organizer::new(d.root(), Some("relative/path")),
+      Organizer::new(d.root(), Some("relative/path"), false),
       "Trash path is not absolute."
     );
   }
 }
 
+#[cfg(test)]
+mod test_import {
+  use std::fs::OpenOptions;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn excludes_damaged_media() {
+    let d = test_dir!(
+      "a.jpg": {},
+      "b.jpg": {},
+      "b.jpg.xmp": {},
+    );
+    let file = OpenOptions::new()
+      .write(true)
+      .open(d.get_path("b.jpg"))
+      .unwrap();
+    file.set_len(file.metadata().unwrap().len() - 2).unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+
+    assert_eq!(o.media.iter_data().count(), 1);
+    assert!(o.media.find(d.get_path("a.jpg")).is_some());
+    assert!(o.sidecars.iter_data().all(Sidecar::is_leftover));
+  }
+}
+
 #[cfg(test)]
 mod test_load_metadata {
   use super::*;
@@ -301,7 +778,17 @@ mod test_load_metadata {
     let mut dupes = FileMap::new();
     let metadata = io::read_metadata_recursive(d.root(), d.some_trash()).unwrap();
 
-    load_metadata(d.root(), &mut media, &mut sidecars, &mut dupes, metadata).unwrap();
+    load_metadata(
+      d.root(),
+      &mut media,
+      &mut sidecars,
+      &mut dupes,
+      &mut Vec::new(),
+      &mut Vec::new(),
+      &mut Vec::new(),
+      metadata,
+    )
+    .unwrap();
 
     assert!(media.iter_data().count() == 1);
     assert!(media.find(d.get_path("image.jpg")).is_some());
@@ -312,6 +799,26 @@ mod test_load_metadata {
   }
 }
 
+#[cfg(test)]
+mod test_count_incomplete_raw_pairs {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn counts_raw_without_image() {
+    let mut o = Organizer::default();
+    o.load_metadata([
+      metadata!("SourceFile": "a.cr2", "FileType": "CR2", "FileTypeExtension": "cr2"),
+      metadata!("SourceFile": "a.jpg", "FileType": "JPEG", "FileTypeExtension": "jpg"),
+      metadata!("SourceFile": "b.cr2", "FileType": "CR2", "FileTypeExtension": "cr2"),
+      metadata!("SourceFile": "c.jpg", "FileType": "JPEG", "FileTypeExtension": "jpg"),
+    ])
+    .unwrap();
+
+    assert_eq!(o.count_incomplete_raw_pairs(), 1);
+  }
+}
+
 #[cfg(test)]
 mod test_link_sidecars {
   use super::*;
@@ -330,7 +837,17 @@ mod test_link_sidecars {
     let mut dupes = FileMap::new();
     let metadata = io::read_metadata_recursive(d.root(), d.some_trash()).unwrap();
 
-    load_metadata(d.root(), &mut media, &mut sidecars, &mut dupes, metadata).unwrap();
+    load_metadata(
+      d.root(),
+      &mut media,
+      &mut sidecars,
+      &mut dupes,
+      &mut Vec::new(),
+      &mut Vec::new(),
+      &mut Vec::new(),
+      metadata,
+    )
+    .unwrap();
 
     let handle_media = media.find(d.get_path("image.jpg")).unwrap();
     let handle_sidecar = sidecars.find(d.get_path("image.jpg.xmp")).unwrap();
@@ -360,7 +877,17 @@ mod test_link_sidecars {
     let mut dupes = FileMap::new();
     let metadata = io::read_metadata_recursive(d.root(), d.some_trash()).unwrap();
 
-    load_metadata(d.root(), &mut media, &mut sidecars, &mut dupes, metadata).unwrap();
+    load_metadata(
+      d.root(),
+      &mut media,
+      &mut sidecars,
+      &mut dupes,
+      &mut Vec::new(),
+      &mut Vec::new(),
+      &mut Vec::new(),
+      metadata,
+    )
+    .unwrap();
 
     let handle_media = media.find(d.get_path("image.jpg")).unwrap();
     let handle_sidecar = sidecars.find(d.get_path("image.jpg.xmp")).unwrap();
@@ -377,6 +904,118 @@ mod test_link_sidecars {
   }
 }
 
+#[cfg(test)]
+mod test_link_stem_sidecars {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn links_stem_sidecar_to_image_over_video() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.mov": { "CompressorID": "avc1" },
+      "image.mov.xmp": {},
+      "other.jpg.xmp": {},
+    );
+    fs::rename(d.get_path("other.jpg.xmp"), d.get_path("image.xmp")).unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+
+    let handle_image = o.media.find(d.get_path("image.jpg")).unwrap();
+    let handle_video = o.media.find(d.get_path("image.mov")).unwrap();
+    let handle_stem = o.sidecars.find(d.get_path("image.xmp")).unwrap();
+    let handle_full = o.sidecars.find(d.get_path("image.mov.xmp")).unwrap();
+
+    assert_eq!(o.media[handle_image].get_sidecar(), Some(handle_stem));
+    assert_eq!(o.media[handle_video].get_sidecar(), Some(handle_full));
+  }
+
+  #[test]
+  fn links_stem_sidecar_with_dotted_stem() {
+    let d = test_dir!(
+      "2023.01.01.jpg": {},
+      "other.jpg.xmp": {},
+    );
+    fs::rename(d.get_path("other.jpg.xmp"), d.get_path("2023.01.01.xmp")).unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+
+    let handle_image = o.media.find(d.get_path("2023.01.01.jpg")).unwrap();
+    let handle_sidecar = o.sidecars.find(d.get_path("2023.01.01.xmp")).unwrap();
+
+    assert_eq!(o.media[handle_image].get_sidecar(), Some(handle_sidecar));
+    assert_eq!(o.sidecars[handle_sidecar].get_naming(), SidecarNaming::Stem);
+  }
+}
+
+#[cfg(test)]
+mod test_link_edit_groups {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn links_edits_and_adjustments_to_original() {
+    let d = test_dir!(
+      "IMG_1234.heic": { "ContentIdentifier": "ID" },
+      "IMG_E1234.heic": { "ContentIdentifier": "ID" },
+      "IMG_1234.mov": { "ContentIdentifier": "ID", "CompressorID": "hvc1" },
+    );
+    fs::write(d.get_path("IMG_1234.aae"), "<plist></plist>").unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+
+    let handle_original = o.media.find(d.get_path("IMG_1234.heic")).unwrap();
+    let handle_edit = o.media.find(d.get_path("IMG_E1234.heic")).unwrap();
+
+    assert_eq!(o.edit_originals[&handle_edit], handle_original);
+    assert_eq!(
+      o.edit_groups[&handle_original]
+        .iter_adjustments()
+        .collect::<Vec<_>>(),
+      [&d.get_path("IMG_1234.aae")]
+    );
+    assert!(o.live_photo_map[&LivePhotoID("ID".to_string())].is_pair());
+  }
+}
+
+#[cfg(test)]
+mod test_link_companions {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn links_companions_to_video() {
+    let d = test_dir!(
+      "GX010123.mov": { "CompressorID": "avc1" },
+      "GX010123.jpg": {},
+      "GL010123.mov": { "CompressorID": "avc1" },
+      "DJI_0001.mov": { "CompressorID": "avc1" },
+    );
+    fs::rename(d.get_path("GX010123.jpg"), d.get_path("GX010123.THM")).unwrap();
+    fs::rename(d.get_path("GL010123.mov"), d.get_path("GL010123.LRV")).unwrap();
+    fs::write(d.get_path("DJI_0001.SRT"), "1\n").unwrap();
+    fs::write(d.get_path("DJI_0002.SRT"), "1\n").unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+
+    let handle_gopro = o.media.find(d.get_path("GX010123.mov")).unwrap();
+    let handle_dji = o.media.find(d.get_path("DJI_0001.mov")).unwrap();
+
+    assert_eq!(o.video_companions[&handle_gopro].len(), 2);
+    assert_eq!(
+      o.video_companions[&handle_dji],
+      [d.get_path("DJI_0001.SRT")]
+    );
+    assert_eq!(o.companions, [d.get_path("DJI_0002.SRT")]);
+  }
+}
+
 #[cfg(test)]
 mod test_link_live_photos {
   use super::*;
@@ -408,14 +1047,24 @@ mod test_link_live_photos {
     let mut live_photos = HashMap::new();
     let metadata = io::read_metadata_recursive(d.root(), d.some_trash()).unwrap();
 
-    load_metadata(d.root(), &mut media, &mut sidecars, &mut dupes, metadata).unwrap();
+    load_metadata(
+      d.root(),
+      &mut media,
+      &mut sidecars,
+      &mut dupes,
+      &mut Vec::new(),
+      &mut Vec::new(),
+      &mut Vec::new(),
+      metadata,
+    )
+    .unwrap();
 
     let handle_image = media.find(d.get_path("image.heic")).unwrap();
     let handle_image_dupe = media.find(d.get_path("image_dupe.jpg")).unwrap();
     let handle_video = media.find(d.get_path("video.mov")).unwrap();
     let handle_video_dupe = media.find(d.get_path("video_dupe.mov")).unwrap();
 
-    link_live_photos(&mut media, &mut live_photos);
+    link_live_photos(&mut media, &HashMap::new(), &mut live_photos);
 
     let id = LivePhotoID("ID".to_string());
     assert!(live_photos.contains_key(&id));
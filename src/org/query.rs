@@ -0,0 +1,510 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Query language for finding media files by their metadata (e.g. `Country ==
+//! Japan and date:2023 and not has:GPSLatitude`).
+
+use std::{
+  fmt::{self, Display, Formatter},
+  iter::Peekable,
+  path::{Path, PathBuf},
+  vec::IntoIter,
+};
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use regex::Regex;
+
+use super::{
+  Organizer,
+  rules::{self, Op},
+  set_tags,
+};
+use crate::{
+  org,
+  prim::{Handle, Media, Metadata, Sidecar},
+};
+
+/// Characters making up comparison operators.
+const OP_CHARS: [char; 5] = ['=', '!', '<', '>', '~'];
+
+/// Names of `predicate:value` atoms. Other words containing `:` are tags
+/// qualified by group (e.g. `XMP-dc:Subject`).
+const PREDICATES: [&str; 3] = ["has", "keyword", "date"];
+
+/// A query over metadata tags (by `ExifTool` name), made up of:
+///
+/// - `Tag op value`: `Tag` may be qualified by group (e.g. `XMP-dc:Subject`),
+///   though as metadata is read without groups, the group is ignored. `op` is a
+///   comparison as in validation rules (`==`, `!=`, `<`, `<=`, `>`, `>=`), or
+///   `~` for a regex match.
+/// - `has:Tag`: `Tag` is set.
+/// - `keyword:Value`: `Value` is among the keywords (`Subject`).
+/// - `date:From..To`: captured within the range (inclusive), where each end is
+///   a year, month or day (e.g. `2023`, `2023-05..2023-08`, `2023-05-01..`).
+/// - `and`, `or`, `not` and parentheses.
+///
+/// Values containing spaces, parentheses or operators are quoted (e.g.
+/// `City == "New York"`).
+#[derive(Debug)]
+pub enum Query {
+  Compare {
+    tag:   String,
+    op:    Op,
+    value: String,
+  },
+  Matches {
+    tag:     String,
+    pattern: Regex,
+  },
+  Has(String),
+  Keyword(String),
+  /// Captured on or after `from`, and before `to`.
+  Date {
+    from: Option<NaiveDate>,
+    to:   Option<NaiveDate>,
+  },
+  Not(Box<Query>),
+  And(Box<Query>, Box<Query>),
+  Or(Box<Query>, Box<Query>),
+}
+
+/// A lexical unit of a query.
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+  Open,
+  Close,
+  Op(String),
+  Word(String),
+  Quoted(String),
+}
+
+impl Display for Token {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Token::Open => write!(f, "`(`"),
+      Token::Close => write!(f, "`)`"),
+      Token::Op(s) | Token::Word(s) => write!(f, "`{s}`"),
+      Token::Quoted(s) => write!(f, "\"{s}\""),
+    }
+  }
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+impl Query {
+  /// Parses `query` (see `Query`).
+  pub fn parse(query: &str) -> Result<Self, String> {
+    let mut tokens = tokenize(query)?.into_iter().peekable();
+    let parsed = parse_or(&mut tokens)?;
+
+    match tokens.next() {
+      Some(t) => Err(format!("Unexpected {t} in query.")),
+      None => Ok(parsed),
+    }
+  }
+
+  /// Whether `metadata` matches this query.
+  pub fn matches(&self, metadata: &Metadata) -> bool {
+    match self {
+      Query::Compare { tag, op, value } => metadata
+        .get(tag)
+        .is_some_and(|v| op.holds(rules::compare(&v, value))),
+      Query::Matches { tag, pattern } => metadata.get(tag).is_some_and(|v| pattern.is_match(&v)),
+      Query::Has(tag) => metadata.get(tag).is_some(),
+      Query::Keyword(keyword) => metadata.has_keyword(keyword),
+      Query::Date { from, to } => {
+        metadata
+          .get_date_time_original()
+          .is_some_and(|(date_time, _)| {
+            let date = date_time.date();
+            from.is_none_or(|f| date >= f) && to.is_none_or(|t| date < t)
+          })
+      }
+      Query::Not(q) => !q.matches(metadata),
+      Query::And(l, r) => l.matches(metadata) && r.matches(metadata),
+      Query::Or(l, r) => l.matches(metadata) || r.matches(metadata),
+    }
+  }
+
+  /// Gets the tags this query refers to, in order of first use.
+  pub fn get_tags(&self) -> Vec<&str> {
+    let mut tags = Vec::new();
+    self.collect_tags(&mut tags);
+    tags
+  }
+
+  fn collect_tags<'a>(&'a self, tags: &mut Vec<&'a str>) {
+    let tag = match self {
+      Query::Compare { tag, .. } | Query::Matches { tag, .. } | Query::Has(tag) => tag.as_str(),
+      Query::Keyword(_) => "Subject",
+      Query::Date { .. } => "DateTimeOriginal",
+      Query::Not(q) => return q.collect_tags(tags),
+      Query::And(l, r) | Query::Or(l, r) => {
+        l.collect_tags(tags);
+        return r.collect_tags(tags);
+      }
+    };
+
+    if !tags.contains(&tag) {
+      tags.push(tag);
+    }
+  }
+}
+
+impl Organizer {
+  /// Finds media files whose metadata (their sidecar's, if they have one)
+  /// matches `query`, ordered by path.
+  pub fn find(&self, query: &Query) -> Vec<Handle<Media>> {
+    let mut found = self
+      .media
+      .iter_data_indexed()
+      .filter(|(handle, _)| query.matches(self.get_media_metadata(*handle)))
+      .collect::<Vec<_>>();
+
+    found.sort_by(|(_, a), (_, b)| a.as_ref().cmp(b.as_ref()));
+    found.into_iter().map(|(handle, _)| handle).collect()
+  }
+
+  /// Finds the media file at the absolute, canonical `path`.
+  pub fn find_media(&self, path: impl AsRef<Path>) -> Option<Handle<Media>> {
+    self.media.iter_data_indexed().find_map(|(handle, media)| {
+      org::to_abs_path(&self.source, media)
+        .canonicalize()
+        .is_ok_and(|p| p == path.as_ref())
+        .then_some(handle)
+    })
+  }
+
+  /// Gets the metadata of the media file at `handle`, from its sidecar if it
+  /// has one.
+  pub fn get_media_metadata(&self, handle: Handle<Media>) -> &Metadata {
+    let media = &self.media[handle];
+
+    media
+      .get_sidecar()
+      .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata())
+  }
+
+  /// Gets the absolute path of the media file at `handle`.
+  pub fn get_media_path(&self, handle: Handle<Media>) -> PathBuf {
+    org::to_abs_path(&self.source, &self.media[handle])
+  }
+}
+
+/// Splits `query` into tokens.
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+  let mut tokens = Vec::new();
+  let mut chars = query.chars().peekable();
+
+  while let Some(&c) = chars.peek() {
+    if c.is_whitespace() {
+      chars.next();
+    } else if c == '(' {
+      chars.next();
+      tokens.push(Token::Open);
+    } else if c == ')' {
+      chars.next();
+      tokens.push(Token::Close);
+    } else if c == '"' {
+      chars.next();
+      let mut value = String::new();
+      loop {
+        match chars.next() {
+          Some('"') => break,
+          Some('\\') => value.extend(chars.next()),
+          Some(c) => value.push(c),
+          None => return Err("Unterminated quote in query.".to_string()),
+        }
+      }
+      tokens.push(Token::Quoted(value));
+    } else {
+      let is_op = OP_CHARS.contains(&c);
+      let mut value = String::new();
+      while let Some(&c) = chars.peek()
+        && OP_CHARS.contains(&c) == is_op
+        && !c.is_whitespace()
+        && !['(', ')', '"'].contains(&c)
+      {
+        value.push(c);
+        chars.next();
+      }
+      tokens.push(if is_op {
+        Token::Op(value)
+      } else {
+        Token::Word(value)
+      });
+    }
+  }
+
+  Ok(tokens)
+}
+
+/// Whether `token` is the (case-insensitive) keyword `keyword`.
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+  matches!(token, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+}
+
+fn parse_or(tokens: &mut Tokens) -> Result<Query, String> {
+  let mut query = parse_and(tokens)?;
+  while tokens.next_if(|t| is_keyword(t, "or")).is_some() {
+    query = Query::Or(Box::new(query), Box::new(parse_and(tokens)?));
+  }
+  Ok(query)
+}
+
+fn parse_and(tokens: &mut Tokens) -> Result<Query, String> {
+  let mut query = parse_not(tokens)?;
+  while tokens.next_if(|t| is_keyword(t, "and")).is_some() {
+    query = Query::And(Box::new(query), Box::new(parse_not(tokens)?));
+  }
+  Ok(query)
+}
+
+fn parse_not(tokens: &mut Tokens) -> Result<Query, String> {
+  if tokens.next_if(|t| is_keyword(t, "not")).is_some() {
+    Ok(Query::Not(Box::new(parse_not(tokens)?)))
+  } else {
+    parse_atom(tokens)
+  }
+}
+
+fn parse_atom(tokens: &mut Tokens) -> Result<Query, String> {
+  match tokens.next() {
+    Some(Token::Open) => {
+      let query = parse_or(tokens)?;
+      match tokens.next() {
+        Some(Token::Close) => Ok(query),
+        _ => Err("Expected `)` in query.".to_string()),
+      }
+    }
+    Some(Token::Word(word)) => match word.split_once(':') {
+      Some((predicate, "")) if PREDICATES.contains(&predicate) => {
+        parse_predicate(predicate, &parse_value(tokens)?)
+      }
+      Some((predicate, value)) if PREDICATES.contains(&predicate) => {
+        parse_predicate(predicate, value)
+      }
+      _ => parse_comparison(&word, tokens),
+    },
+    Some(t) => Err(format!("Unexpected {t} in query.")),
+    None => Err("Unexpected end of query.".to_string()),
+  }
+}
+
+/// Parses a value, bare or quoted.
+fn parse_value(tokens: &mut Tokens) -> Result<String, String> {
+  match tokens.next() {
+    Some(Token::Word(v) | Token::Quoted(v)) => Ok(v),
+    Some(t) => Err(format!("Expected a value in query, found {t}.")),
+    None => Err("Expected a value at end of query.".to_string()),
+  }
+}
+
+/// Parses `tag op value`, `tag` having already been read.
+fn parse_comparison(tag: &str, tokens: &mut Tokens) -> Result<Query, String> {
+  let op = match tokens.next() {
+    Some(Token::Op(op)) => op,
+    Some(t) => return Err(format!("Expected an operator after `{tag}`, found {t}.")),
+    None => return Err(format!("Expected an operator after `{tag}`.")),
+  };
+  let value = parse_value(tokens)?;
+  let tag = set_tags::get_tag_name(tag).to_string();
+
+  let op = match op.as_str() {
+    "~" => {
+      let pattern = Regex::new(&value).map_err(|e| format!("Invalid regex in query ({e})."))?;
+      return Ok(Query::Matches { tag, pattern });
+    }
+    "==" | "=" => Op::Eq,
+    "!=" => Op::Ne,
+    "<" => Op::Lt,
+    "<=" => Op::Le,
+    ">" => Op::Gt,
+    ">=" => Op::Ge,
+    _ => return Err(format!("Unknown operator `{op}` in query.")),
+  };
+
+  Ok(Query::Compare { tag, op, value })
+}
+
+/// Parses `predicate:value` (e.g. `has:GPSLatitude`).
+fn parse_predicate(predicate: &str, value: &str) -> Result<Query, String> {
+  match predicate {
+    "has" => Ok(Query::Has(set_tags::get_tag_name(value).to_string())),
+    "keyword" => Ok(Query::Keyword(value.to_string())),
+    "date" => {
+      let (from, to) = value.split_once("..").unwrap_or((value, value));
+      Ok(Query::Date {
+        from: parse_date_bound(from)?.map(|(start, _)| start),
+        to:   parse_date_bound(to)?.map(|(_, end)| end),
+      })
+    }
+    _ => Err(format!("Unknown predicate `{predicate}:` in query.")),
+  }
+}
+
+/// Parses a year, month or day (e.g. `2023-05`) to the first day within it and
+/// the first day after it. Empty bounds are open.
+fn parse_date_bound(bound: &str) -> Result<Option<(NaiveDate, NaiveDate)>, String> {
+  if bound.is_empty() {
+    return Ok(None);
+  }
+
+  let parts = bound
+    .split('-')
+    .map(str::parse::<u32>)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|_| format!("Invalid date `{bound}` in query."))?;
+
+  let range = match parts.as_slice() {
+    [y] => i32::try_from(*y).ok().and_then(|y| {
+      Some((
+        NaiveDate::from_ymd_opt(y, 1, 1)?,
+        NaiveDate::from_ymd_opt(y + 1, 1, 1)?,
+      ))
+    }),
+    [y, m] => i32::try_from(*y).ok().and_then(|y| {
+      let start = NaiveDate::from_ymd_opt(y, *m, 1)?;
+      Some((start, start.checked_add_months(Months::new(1))?))
+    }),
+    [y, m, d] => i32::try_from(*y).ok().and_then(|y| {
+      let start = NaiveDate::from_ymd_opt(y, *m, *d)?;
+      Some((start, start.checked_add_days(Days::new(1))?))
+    }),
+    _ => None,
+  };
+
+  range
+    .filter(|(start, _)| start.year() > 0)
+    .map(Some)
+    .ok_or(format!("Invalid date `{bound}` in query."))
+}
+
+#[cfg(test)]
+mod test_parse {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn respects_precedence() {
+    let query = Query::parse("a == 1 or not b == 2 and has:c").unwrap();
+
+    let Query::Or(_, r) = query else {
+      panic!("Expected `or` at top level.");
+    };
+    let Query::And(l, _) = *r else {
+      panic!("Expected `and` under `or`.");
+    };
+    assert!(matches!(*l, Query::Not(_)));
+  }
+
+  #[test]
+  fn parses_quoted_values() {
+    let query = Query::parse(r#"City == "New York" and keyword:"Summer (2023)""#).unwrap();
+
+    assert_eq!(query.get_tags(), ["City", "Subject"]);
+  }
+
+  #[test]
+  fn parses_group_qualified_tags() {
+    let query = Query::parse("XMP-dc:Subject == x and has:XMP-dc:Creator").unwrap();
+
+    assert_eq!(query.get_tags(), ["Subject", "Creator"]);
+  }
+
+  #[test]
+  fn errors_on_invalid_queries() {
+    assert_err!(Query::parse("City =="), "Expected a value");
+    assert_err!(Query::parse("(has:City"), "Expected `)`");
+    assert_err!(Query::parse("City ? x"), "Expected an operator");
+    assert_err!(Query::parse("date:2023-13"), "Invalid date");
+    assert_err!(
+      Query::parse("size:5"),
+      "Expected an operator after `size:5`"
+    );
+    assert_err!(Query::parse("Model ~ \"(\""), "Invalid regex");
+  }
+}
+
+#[cfg(test)]
+mod test_matches {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn matches_example() {
+    let query =
+      Query::parse("Country == Japan and date:2023 and not has:GPSLatitude and Rating >= 4")
+        .unwrap();
+
+    assert!(query.matches(&metadata!(
+      "Country": "Japan",
+      "DateTimeOriginal": "2023-12-31T23:59:59",
+      "Rating": 5,
+    )));
+    assert!(!query.matches(&metadata!(
+      "Country": "Japan",
+      "DateTimeOriginal": "2024-01-01T00:00:00",
+      "Rating": 5,
+    )));
+    assert!(!query.matches(&metadata!(
+      "Country": "Japan",
+      "DateTimeOriginal": "2023-06-01T00:00:00",
+      "GPSLatitude": "35.0",
+      "Rating": 5,
+    )));
+  }
+
+  #[test]
+  fn matches_group_qualified_tags() {
+    let query = Query::parse("XMP-dc:Creator == Jane and has:XMP-dc:Creator").unwrap();
+
+    assert!(query.matches(&metadata!(
+      "Creator": "Jane",
+    )));
+    assert!(!query.matches(&metadata!()));
+  }
+
+  #[test]
+  fn matches_date_ranges() {
+    let query = Query::parse("date:2023-05..2023-06-15").unwrap();
+
+    assert!(query.matches(&metadata!("DateTimeOriginal": "2023-05-01T00:00:00")));
+    assert!(query.matches(&metadata!("DateTimeOriginal": "2023-06-15T23:59:59")));
+    assert!(!query.matches(&metadata!("DateTimeOriginal": "2023-06-16T00:00:00")));
+    assert!(!query.matches(&metadata!()));
+
+    let query = Query::parse("date:2023-05..").unwrap();
+    assert!(query.matches(&metadata!("DateTimeOriginal": "2030-01-01T00:00:00")));
+  }
+
+  #[test]
+  fn matches_regexes_and_keywords() {
+    let query = Query::parse(r#"Model ~ "^ILCE-" or keyword:Family"#).unwrap();
+
+    assert!(query.matches(&metadata!("Model": "ILCE-7M4")));
+    assert!(query.matches(&metadata!("Subject": "Family")));
+    assert!(!query.matches(&metadata!("Model": "iPhone 15 Pro")));
+  }
+}
+
+#[cfg(test)]
+mod test_find {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn finds_by_sidecar_metadata() {
+    let d = test_dir!(
+      "a.jpg": {},
+      "a.jpg.xmp": { "Country": "Japan" },
+      "b.jpg": { "Country": "Japan" },
+      "b.jpg.xmp": { "Country": "France" },
+    );
+
+    let o = Organizer::import(d.root()).unwrap();
+    let found = o.find(&Query::parse("Country == Japan").unwrap());
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(o.get_media_path(found[0]), d.get_path("a.jpg"));
+  }
+}
@@ -0,0 +1,272 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Machine-readable record of what the `Organizer` did to each file during a
+//! run.
+
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use serde::{Serialize, Serializer};
+
+use super::{rules::Severity, to_abs_path};
+use crate::prim::{self, Metadata, TagDiff};
+
+/// The part a file plays within its group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileRole {
+  Media,
+  LivePhotoImage,
+  LivePhotoVideo,
+  Sidecar,
+  Dupe,
+  Edit,
+  Adjustments,
+  Companion,
+}
+
+/// A single operation performed on (or decided for) a file.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+  /// Moved to the trash directory.
+  Trashed { reason: String },
+  /// Would have been trashed, but no trash directory was given (e.g. during
+  /// import), so it was only dropped from the run.
+  Dropped { reason: String },
+  /// A new XMP sidecar was created for this media file.
+  SidecarCreated { sidecar: PathBuf },
+  /// Tags were rewritten by a metadata update pass.
+  MetadataUpdated { pass: String },
+  /// Metadata was copied into this file from another.
+  MetadataSynced { from: PathBuf },
+  /// Moved and renamed into the catalog.
+  Moved { to: PathBuf },
+  /// Left in place.
+  NotMoved { reason: String },
+}
+
+/// A tag written during the run, alongside its new value.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct TagChange {
+  pub tag:   String,
+  pub value: Option<String>,
+}
+
+/// A validation check that did not pass.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+  pub rule:     String,
+  pub severity: Severity,
+  pub message:  String,
+}
+
+/// Everything recorded for a single file.
+#[derive(Debug, Default, Serialize)]
+pub struct FileReport {
+  pub path:        PathBuf,
+  pub role:        Option<FileRole>,
+  pub group:       Option<PathBuf>,
+  pub actions:     Vec<Action>,
+  pub tag_changes: Vec<TagChange>,
+  /// Tags whose values changed, across every metadata update in the run.
+  pub tag_diffs:   Vec<TagDiff>,
+  pub findings:    Vec<Finding>,
+  pub final_path:  Option<PathBuf>,
+}
+
+/// Per-file record of a full `Organizer` run, keyed by each file's path
+/// relative to the scanned directory at the start of the run.
+#[derive(Default, Serialize)]
+pub struct Report {
+  run:    String,
+  source: PathBuf,
+  #[serde(serialize_with = "serialize_values")]
+  files:  BTreeMap<PathBuf, FileReport>,
+}
+
+impl Report {
+  /// Creates an empty report for a run over `source`.
+  pub fn new(source: impl AsRef<Path>) -> Self {
+    let source = source.as_ref();
+
+    Self {
+      run:    Local::now().format("%Y%m%dT%H%M%S%3f").to_string(),
+      source: source.canonicalize().unwrap_or(source.to_path_buf()),
+      files:  BTreeMap::new(),
+    }
+  }
+
+  /// Records `action` against the file at `path`. Any paths within `action`
+  /// are stored relative to the scanned directory.
+  pub fn action(&mut self, path: impl AsRef<Path>, mut action: Action) {
+    match &mut action {
+      Action::SidecarCreated { sidecar: p } | Action::MetadataSynced { from: p } => {
+        *p = self.relative(&p);
+      }
+      Action::Moved { to } => {
+        *to = self.relative(&to);
+        self.entry(&path).final_path = Some(to.clone());
+      }
+      _ => (),
+    }
+
+    self.entry(path).actions.push(action);
+  }
+
+  /// Gets the group the file at `path` was recorded as belonging to, relative
+  /// to the scanned directory.
+  pub fn get_group(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+    self.files.get(&self.relative(path))?.group.clone()
+  }
+
+  /// Gets when the run started (e.g. `20250101T120000000`).
+  pub fn get_run(&self) -> &str {
+    &self.run
+  }
+
+  /// Gets the scanned directory, which all recorded paths are relative to.
+  pub fn get_source(&self) -> &Path {
+    &self.source
+  }
+
+  /// Iterates over the records of all files, ordered by path.
+  pub fn iter_files(&self) -> impl Iterator<Item = &FileReport> {
+    self.files.values()
+  }
+
+  /// Sets which group the file at `path` belongs to, as identified by the
+  /// group's main media file. Leftover sidecars have no group.
+  pub fn set_group(&mut self, path: impl AsRef<Path>, role: FileRole, group: Option<&Path>) {
+    let group = group.map(|g| self.relative(g));
+    let entry = self.entry(path);
+    entry.role = Some(role);
+    entry.group = group;
+  }
+
+  /// Records `tag` having been written as `value` to the file at `path`.
+  pub fn tag_change(&mut self, path: impl AsRef<Path>, tag: &str, value: Option<&str>) {
+    self.entry(path).tag_changes.push(TagChange {
+      tag:   tag.to_string(),
+      value: value.map(str::to_string),
+    });
+  }
+
+  /// Records the tags differing between `before` and `after`, the metadata of
+  /// the file at `path` before and after an update, logging each.
+  pub fn metadata_diff(&mut self, path: impl AsRef<Path>, before: &Metadata, after: &Metadata) {
+    let diffs = prim::diff_metadata(before, after);
+
+    for diff in &diffs {
+      log::debug!("{}: {diff}", path.as_ref().display());
+    }
+
+    self.entry(path).tag_diffs.extend(diffs);
+  }
+
+  /// Records a failed validation check for the file at `path`.
+  pub fn finding(&mut self, path: impl AsRef<Path>, finding: Finding) {
+    self.entry(path).findings.push(finding);
+  }
+
+  /// Gets the record for the file at `path`, creating it if needed.
+  fn entry(&mut self, path: impl AsRef<Path>) -> &mut FileReport {
+    let path = self.relative(path);
+    self
+      .files
+      .entry(path.clone())
+      .or_insert_with(|| FileReport {
+        path,
+        ..Default::default()
+      })
+  }
+
+  /// Converts `path` to be relative to the scanned directory, without any
+  /// `./` components. Paths outside of the scanned directory stay absolute.
+  fn relative(&self, path: impl AsRef<Path>) -> PathBuf {
+    let path = to_abs_path(&self.source, path)
+      .components()
+      .collect::<PathBuf>();
+
+    path
+      .strip_prefix(&self.source)
+      .map_or_else(|_| path.clone(), Path::to_path_buf)
+  }
+}
+
+/// Writes out a map's values as a JSON array.
+fn serialize_values<S: Serializer>(
+  files: &BTreeMap<PathBuf, FileReport>,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  serializer.collect_seq(files.values())
+}
+
+#[cfg(test)]
+mod test_entry {
+  use super::*;
+
+  #[test]
+  fn merges_relative_and_absolute_paths() {
+    let mut report = Report::new("/catalog");
+
+    report.action("./dir/image.jpg", Action::MetadataUpdated {
+      pass: "test".to_string(),
+    });
+    report.action("/catalog/dir/image.jpg", Action::MetadataUpdated {
+      pass: "test".to_string(),
+    });
+
+    let files = report.files.values().collect::<Vec<_>>();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, PathBuf::from("dir/image.jpg"));
+    assert_eq!(files[0].actions.len(), 2);
+  }
+}
+
+#[cfg(test)]
+mod test_metadata_diff {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn records_changed_tags() {
+    let mut report = Report::new("/catalog");
+
+    report.metadata_diff(
+      "image.jpg",
+      &metadata!("Creator": "Creator"),
+      &metadata!("Creator": "Other"),
+    );
+
+    let file = report.files.values().next().unwrap();
+    assert_eq!(file.tag_diffs, [TagDiff {
+      tag:    "Creator".to_string(),
+      before: Some("Creator".to_string()),
+      after:  Some("Other".to_string()),
+    }]);
+  }
+}
+
+#[cfg(test)]
+mod test_action {
+  use super::*;
+
+  #[test]
+  fn records_final_path_relative_to_source() {
+    let mut report = Report::new("/catalog");
+
+    report.action("image.jpg", Action::Moved {
+      to: PathBuf::from("/catalog/2000/01/000101_000000000.jpg"),
+    });
+
+    let file = report.files.values().next().unwrap();
+    assert_eq!(
+      file.final_path,
+      Some(PathBuf::from("2000/01/000101_000000000.jpg"))
+    );
+  }
+}
@@ -0,0 +1,275 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! User-defined validation rules, declared in the catalog config.
+//!
+//! Each rule has a condition on metadata tags (by `ExifTool` name), a severity,
+//! and optionally the file types (e.g. `JPEG`) it applies to.
+
+use std::cmp::Ordering;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, de::Error};
+
+use crate::prim::{self, Metadata};
+
+/// How serious a failed rule is. Only errors prevent files from being moved
+/// into the catalog.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+  Info,
+  Warning,
+  #[default]
+  Error,
+}
+
+/// A single validation rule.
+#[derive(Debug, Deserialize)]
+pub struct Rule {
+  pub name:       String,
+  #[serde(default)]
+  pub severity:   Severity,
+  /// `FileType`s this rule applies to. Applies to all if empty.
+  #[serde(default)]
+  pub file_types: Vec<String>,
+  #[serde(flatten)]
+  pub condition:  Condition,
+}
+
+/// What a rule checks for.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+  /// All `tags` must be set.
+  Required { tags: Vec<String> },
+  /// `tag`, if set, must match `pattern`.
+  Matches {
+    tag:     String,
+    #[serde(deserialize_with = "deserialize_regex")]
+    pattern: Regex,
+  },
+  /// `left` and `right`, if both set, must compare as `op`. Values are compared
+  /// as date times, then numbers, then strings.
+  Compare {
+    left:  String,
+    op:    Op,
+    right: String,
+  },
+}
+
+/// Comparison operators for `Condition::Compare`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Op {
+  #[serde(rename = "==")]
+  Eq,
+  #[serde(rename = "!=")]
+  Ne,
+  #[serde(rename = "<")]
+  Lt,
+  #[serde(rename = "<=")]
+  Le,
+  #[serde(rename = ">")]
+  Gt,
+  #[serde(rename = ">=")]
+  Ge,
+}
+
+impl Rule {
+  /// Checks `metadata`, belonging to a media file of type `file_type`,
+  /// returning a description of each problem found.
+  pub fn check(&self, file_type: &str, metadata: &Metadata) -> Vec<String> {
+    if !self.file_types.is_empty() && !self.file_types.iter().any(|t| t == file_type) {
+      return Vec::new();
+    }
+
+    match &self.condition {
+      Condition::Required { tags } => tags
+        .iter()
+        .filter(|t| metadata.get(t).is_none())
+        .map(|t| format!("Missing `{t}` tag."))
+        .collect(),
+      Condition::Matches { tag, pattern } => metadata
+        .get(tag)
+        .filter(|v| !pattern.is_match(v))
+        .map(|v| format!("`{tag}` (\"{v}\") does not match `{pattern}`."))
+        .into_iter()
+        .collect(),
+      Condition::Compare { left, op, right } => {
+        let (Some(l), Some(r)) = (metadata.get(left), metadata.get(right)) else {
+          return Vec::new();
+        };
+
+        if op.holds(compare(&l, &r)) {
+          Vec::new()
+        } else {
+          vec![format!(
+            "Expected `{left}` {op} `{right}` (\"{l}\" vs. \"{r}\")."
+          )]
+        }
+      }
+    }
+  }
+}
+
+impl Op {
+  /// Whether this operator is satisfied by `ordering`.
+  pub fn holds(self, ordering: Ordering) -> bool {
+    match self {
+      Op::Eq => ordering.is_eq(),
+      Op::Ne => ordering.is_ne(),
+      Op::Lt => ordering.is_lt(),
+      Op::Le => ordering.is_le(),
+      Op::Gt => ordering.is_gt(),
+      Op::Ge => ordering.is_ge(),
+    }
+  }
+}
+
+impl std::fmt::Display for Op {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let op = match self {
+      Op::Eq => "==",
+      Op::Ne => "!=",
+      Op::Lt => "<",
+      Op::Le => "<=",
+      Op::Gt => ">",
+      Op::Ge => ">=",
+    };
+    write!(f, "{op}")
+  }
+}
+
+/// Compares two tag values. Date times are compared as instants if both have
+/// time zones, else as local times.
+pub fn compare(left: &str, right: &str) -> Ordering {
+  if let (Ok((l, l_tz)), Ok((r, r_tz))) =
+    (prim::parse_date_time(left), prim::parse_date_time(right))
+  {
+    return match (l_tz, r_tz) {
+      (Some(l_tz), Some(r_tz)) => (l - l_tz).cmp(&(r - r_tz)),
+      _ => l.cmp(&r),
+    };
+  }
+
+  if let (Ok(l), Ok(r)) = (left.parse::<f64>(), right.parse::<f64>()) {
+    return l.total_cmp(&r);
+  }
+
+  left.cmp(right)
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+  let pattern = String::deserialize(deserializer)?;
+  Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod test_check {
+  use serde_json::json;
+
+  use super::*;
+  use crate::testing::*;
+
+  fn rule(value: serde_json::Value) -> Rule {
+    serde_json::from_value(value).unwrap()
+  }
+
+  #[test]
+  fn finds_missing_tags() {
+    let rule = rule(json!({
+      "name": "required",
+      "type": "required",
+      "tags": ["Creator", "Rating"],
+    }));
+    let metadata = metadata!(
+      "Creator": "Seth",
+    );
+
+    assert_eq!(rule.check("JPEG", &metadata), vec![
+      "Missing `Rating` tag.".to_string()
+    ]);
+  }
+
+  #[test]
+  fn finds_mismatched_pattern() {
+    let rule = rule(json!({
+      "name": "copyright",
+      "type": "matches",
+      "tag": "Copyright",
+      "pattern": r"^Copyright \d{4} .+",
+    }));
+
+    assert_eq!(
+      rule
+        .check("JPEG", &metadata!("Copyright": "Copyright Seth"))
+        .len(),
+      1
+    );
+    assert!(
+      rule
+        .check("JPEG", &metadata!("Copyright": "Copyright 2000 Seth"))
+        .is_empty()
+    );
+  }
+
+  #[test]
+  fn compares_date_times_across_time_zones() {
+    let rule = rule(json!({
+      "name": "order",
+      "type": "compare",
+      "left": "CreateDate",
+      "op": ">=",
+      "right": "DateTimeOriginal",
+    }));
+
+    assert!(
+      rule
+        .check(
+          "JPEG",
+          &metadata!(
+            "CreateDate": "2000-01-01T01:00:00-08:00",
+            "DateTimeOriginal": "2000-01-01T02:00:00-07:00",
+          )
+        )
+        .is_empty()
+    );
+    assert_eq!(
+      rule
+        .check(
+          "JPEG",
+          &metadata!(
+            "CreateDate": "2000-01-01T00:00:00",
+            "DateTimeOriginal": "2000-01-01T00:00:01",
+          )
+        )
+        .len(),
+      1
+    );
+  }
+
+  #[test]
+  fn skips_other_file_types() {
+    let rule = rule(json!({
+      "name": "required",
+      "type": "required",
+      "tags": ["Rating"],
+      "file_types": ["HEIC"],
+    }));
+
+    assert!(rule.check("JPEG", &metadata!()).is_empty());
+    assert_eq!(rule.check("HEIC", &metadata!()).len(), 1);
+  }
+
+  #[test]
+  fn errors_on_invalid_pattern() {
+    assert!(
+      serde_json::from_value::<Rule>(json!({
+        "name": "bad",
+        "type": "matches",
+        "tag": "Copyright",
+        "pattern": "(",
+      }))
+      .is_err()
+    );
+  }
+}
@@ -0,0 +1,445 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Bulk tag editing, routed through sidecars, with a journal of old values for
+//! undoing edits.
+
+use std::{
+  collections::BTreeMap,
+  ffi::OsStr,
+  path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Organizer, report::Action};
+use crate::{
+  io,
+  org,
+  prim::{Handle, LivePhotoComponentType, Media, Metadata, Sidecar, SidecarDupe, SidecarInitial},
+};
+
+/// Values of tags in a file before they were edited, for undoing the edit.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TagEdit {
+  /// Relative to the catalog.
+  pub path: PathBuf,
+  /// Old values by tag, `None` where the tag was not set.
+  pub old:  BTreeMap<String, Option<TagValue>>,
+}
+
+/// Value of a tag, which for list tags (e.g. `Subject`) is written element by
+/// element.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TagValue {
+  Single(String),
+  List(Vec<String>),
+}
+
+/// A loaded file tags can be written to.
+#[derive(Clone, Copy)]
+enum Target {
+  Media(Handle<Media>),
+  Sidecar(Handle<SidecarInitial>),
+  Dupe(Handle<SidecarDupe>),
+}
+
+impl Organizer {
+  /// Writes `tags` (as `ExifTool` tag names and values) to the initial
+  /// sidecars of the media files at `handles`, creating any missing. Tags are
+  /// also written to darktable duplicates and the sidecars of Live Photo videos
+  /// (as in stage 4), and if `write_media`, to the media files themselves.
+  /// The old values of each file are pushed to `edits` as it is written, so
+  /// edits made before any error can still be undone.
+  pub fn set_tags(
+    &mut self,
+    handles: &[Handle<Media>],
+    tags: &[(String, String)],
+    write_media: bool,
+    edits: &mut Vec<TagEdit>,
+  ) -> Result<(), String> {
+    log::info!("Setting tags on {} media file(s).", handles.len());
+
+    let tags = tags
+      .iter()
+      .map(|(tag, value)| (tag.clone(), Some(TagValue::Single(value.clone()))))
+      .collect::<Vec<_>>();
+
+    let stem_named = self.get_stem_named_media();
+
+    for &handle in handles {
+      let handle_sidecar = if let Some(h) = self.media[handle].get_sidecar() {
+        h
+      } else {
+        let naming = self.get_sidecar_naming(handle, &stem_named);
+        let h = self.create_sidecar(handle, naming)?;
+        self.media[handle].set_sidecar(h);
+        self.sidecars[h].set_media_handle(handle);
+        h
+      };
+
+      let mut targets = vec![Target::Sidecar(handle_sidecar)];
+      targets.extend(self.media[handle].iter_dupes().map(Target::Dupe));
+      targets.extend(
+        self
+          .get_live_photo_video_sidecar(handle)
+          .map(Target::Sidecar),
+      );
+      if write_media {
+        targets.push(Target::Media(handle));
+      }
+
+      for target in targets {
+        edits.push(self.write_tags(target, &tags)?);
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Restores the old values in `edits` (see `set_tags`), latest first, so
+  /// files written more than once end with their oldest values. Files no
+  /// longer in the catalog are skipped.
+  pub fn undo_tag_edits(&mut self, edits: &[TagEdit]) -> Result<(), String> {
+    log::info!("Undoing tag edits to {} file(s).", edits.len());
+
+    for edit in edits.iter().rev() {
+      let Some(target) = self.find_target(self.source.join(&edit.path)) else {
+        log::warn!(
+          "{}: Not undoing tag edits, as the file is no longer in the catalog.",
+          edit.path.display()
+        );
+        continue;
+      };
+
+      let tags = edit
+        .old
+        .iter()
+        .map(|(tag, value)| (tag.clone(), value.clone()))
+        .collect::<Vec<_>>();
+      self.write_tags(target, &tags)?;
+    }
+
+    Ok(())
+  }
+
+  /// Gets the Live Photo video paired with the media file at `handle`, if it
+  /// is the best image of a Live Photo.
+  pub fn get_live_photo_video(&self, handle: Handle<Media>) -> Option<Handle<Media>> {
+    let media = &self.media[handle];
+
+    if media.get_live_photo_component_type() != Some(LivePhotoComponentType::Image) {
+      return None;
+    }
+
+    let linker = self.live_photo_map.get(&media.content_id()?)?;
+
+    if linker.iter_images().next().is_none()
+      || linker.iter_videos().next().is_none()
+      || linker.get_image_best() != handle
+    {
+      return None;
+    }
+
+    Some(linker.get_video_best())
+  }
+
+  /// Gets the initial sidecar of the Live Photo video paired with the media
+  /// file at `handle` (see `get_live_photo_video`).
+  fn get_live_photo_video_sidecar(&self, handle: Handle<Media>) -> Option<Handle<SidecarInitial>> {
+    let video = &self.media[self.get_live_photo_video(handle)?];
+    let sidecar = video.get_sidecar();
+
+    if sidecar.is_none() {
+      log::debug!("{video}: Cannot set tags on Live Photo video without sidecar.");
+    }
+
+    sidecar
+  }
+
+  /// Finds the loaded file at `path`.
+  fn find_target(&self, path: impl AsRef<Path>) -> Option<Target> {
+    let path = normalize(path);
+    let is_path = |p: &Path| normalize(org::to_abs_path(&self.source, p)) == path;
+
+    self
+      .sidecars
+      .iter_data_indexed()
+      .find_map(|(h, s)| is_path(s.as_ref()).then_some(Target::Sidecar(h)))
+      .or_else(|| {
+        self
+          .dupes
+          .iter_data_indexed()
+          .find_map(|(h, d)| is_path(d.as_ref()).then_some(Target::Dupe(h)))
+      })
+      .or_else(|| {
+        self
+          .media
+          .iter_data_indexed()
+          .find_map(|(h, m)| is_path(m.as_ref()).then_some(Target::Media(h)))
+      })
+  }
+
+  /// Gets the metadata of `target`.
+  fn get_target_metadata(&self, target: Target) -> &Metadata {
+    match target {
+      Target::Media(h) => self.media[h].get_metadata(),
+      Target::Sidecar(h) => self.sidecars[h].get_metadata(),
+      Target::Dupe(h) => self.dupes[h].get_metadata(),
+    }
+  }
+
+  /// Writes `tags` to `target`, deleting those without values, and returns
+  /// their old values.
+  fn write_tags(
+    &mut self,
+    target: Target,
+    tags: &[(String, Option<TagValue>)],
+  ) -> Result<TagEdit, String> {
+    let metadata = self.get_target_metadata(target);
+    let path = normalize(org::to_abs_path(&self.source, &metadata.source_file));
+
+    log::debug!("{}: Setting tags.", path.display());
+
+    let old = tags
+      .iter()
+      .map(|(tag, _)| {
+        let name = get_tag_name(tag);
+        let value = (metadata.get_list(name).map(TagValue::List))
+          .or_else(|| metadata.get(name).map(TagValue::Single));
+        (tag.clone(), value)
+      })
+      .collect();
+
+    let args = get_write_args(tags);
+    io::run_exiftool(
+      Some(&self.source),
+      args.iter().map(OsStr::new).chain([path.as_os_str()]),
+    )?;
+
+    let metadata_new = io::read_metadata(&path)?;
+
+    self.report.action(&path, Action::MetadataUpdated {
+      pass: "set".to_string(),
+    });
+    for (tag, _) in tags {
+      self
+        .report
+        .tag_change(&path, tag, metadata_new.get(get_tag_name(tag)).as_deref());
+    }
+
+    // Borrowing fields directly, as `report` is borrowed mutably.
+    let metadata_old = match target {
+      Target::Media(h) => self.media[h].get_metadata(),
+      Target::Sidecar(h) => self.sidecars[h].get_metadata(),
+      Target::Dupe(h) => self.dupes[h].get_metadata(),
+    };
+    self
+      .report
+      .metadata_diff(&path, metadata_old, &metadata_new);
+
+    match target {
+      Target::Media(h) => self.media[h].update_metadata(metadata_new),
+      Target::Sidecar(h) => self.sidecars[h].update_metadata(metadata_new),
+      Target::Dupe(h) => self.dupes[h].update_metadata(metadata_new),
+    }
+
+    Ok(TagEdit {
+      path: path
+        .strip_prefix(normalize(&self.source))
+        .map_or(path.clone(), Path::to_path_buf),
+      old,
+    })
+  }
+}
+
+/// Gets the `ExifTool` arguments to write `tags`, deleting those without
+/// values. Lists are cleared first, so they are replaced rather than added to.
+fn get_write_args(tags: &[(String, Option<TagValue>)]) -> Vec<String> {
+  tags
+    .iter()
+    .flat_map(|(tag, value)| match value {
+      None => vec![format!("-{tag}=")],
+      Some(TagValue::Single(value)) => vec![format!("-{tag}={value}")],
+      Some(TagValue::List(values)) => std::iter::once(format!("-{tag}="))
+        .chain(values.iter().map(|v| format!("-{tag}={v}")))
+        .collect(),
+    })
+    .collect()
+}
+
+/// Gets `tag` without any group (e.g. `Subject` for `XMP-dc:Subject`).
+pub fn get_tag_name(tag: &str) -> &str {
+  tag.rsplit(':').next().unwrap_or(tag)
+}
+
+/// Removes `.` components from `path`.
+fn normalize(path: impl AsRef<Path>) -> PathBuf {
+  path.as_ref().components().collect()
+}
+
+#[cfg(test)]
+mod test_set_tags {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn writes_to_sidecars_and_dupes() {
+    let d = test_dir!(
+      "image.jpg": { "Creator": "Creator" },
+      "image.jpg.xmp": { "Creator": "Creator" },
+      "image_01.jpg.xmp": { "Creator": "Creator" },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    let handles = o.find(&org::Query::parse("has:Creator").unwrap());
+    let mut edits = Vec::new();
+    o.set_tags(
+      &handles,
+      &[("Creator".to_string(), "Other".to_string())],
+      false,
+      &mut edits,
+    )
+    .unwrap();
+
+    assert_eq!(edits.len(), 2);
+    assert_tag!(d, "image.jpg", "Creator", "Creator");
+    assert_tag!(d, "image.jpg.xmp", "Creator", "Other");
+    assert_tag!(d, "image_01.jpg.xmp", "Creator", "Other");
+  }
+
+  #[test]
+  fn creates_missing_sidecar() {
+    let d = test_dir!(
+      "image.jpg": {},
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    let handles = o.find(&org::Query::parse("not has:Creator").unwrap());
+    o.set_tags(
+      &handles,
+      &[("Creator".to_string(), "Other".to_string())],
+      true,
+      &mut Vec::new(),
+    )
+    .unwrap();
+
+    assert_tag!(d, "image.jpg", "Creator", "Other");
+    assert_tag!(d, "image.jpg.xmp", "Creator", "Other");
+  }
+}
+
+#[cfg(test)]
+mod test_undo_tag_edits {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn restores_old_values() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": { "Creator": "Creator" },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    let handles = o.find(&org::Query::parse("has:Creator").unwrap());
+    let mut edits = Vec::new();
+    o.set_tags(
+      &handles,
+      &[
+        ("Creator".to_string(), "Other".to_string()),
+        ("Label".to_string(), "Red".to_string()),
+      ],
+      false,
+      &mut edits,
+    )
+    .unwrap();
+    o.undo_tag_edits(&edits).unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Creator", "Creator");
+    assert_tag!(d, "image.jpg.xmp", "Label", None);
+  }
+
+  #[test]
+  fn restores_files_written_twice() {
+    let d = test_dir!(
+      "image.heic": { "ContentIdentifier": "ID" },
+      "image.heic.xmp": { "Creator": "Creator" },
+      "video.mov": { "CompressorID": "avc1", "ContentIdentifier": "ID" },
+      "video.mov.xmp": { "Creator": "Creator" },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    let handles = o.find(&org::Query::parse("has:Creator").unwrap());
+    let mut edits = Vec::new();
+    o.set_tags(
+      &handles,
+      &[("Creator".to_string(), "Other".to_string())],
+      false,
+      &mut edits,
+    )
+    .unwrap();
+    o.undo_tag_edits(&edits).unwrap();
+
+    assert_tag!(d, "image.heic.xmp", "Creator", "Creator");
+    assert_tag!(d, "video.mov.xmp", "Creator", "Creator");
+  }
+}
+
+#[cfg(test)]
+mod test_get_write_args {
+  use super::*;
+
+  #[test]
+  fn writes_lists_by_element() {
+    let tags = [
+      (
+        "Subject".to_string(),
+        Some(TagValue::List(vec!["a".to_string(), "b".to_string()])),
+      ),
+      (
+        "Creator".to_string(),
+        Some(TagValue::Single("a, b".to_string())),
+      ),
+      ("Label".to_string(), None),
+    ];
+
+    assert_eq!(get_write_args(&tags), [
+      "-Subject=",
+      "-Subject=a",
+      "-Subject=b",
+      "-Creator=a, b",
+      "-Label=",
+    ]);
+  }
+}
+
+#[cfg(test)]
+mod test_tag_value {
+  use super::*;
+
+  #[test]
+  fn serializes_lists_as_arrays() {
+    let old = BTreeMap::from([
+      (
+        "Label".to_string(),
+        Some(TagValue::Single("Red".to_string())),
+      ),
+      (
+        "Subject".to_string(),
+        Some(TagValue::List(vec!["a".to_string(), "b".to_string()])),
+      ),
+    ]);
+
+    let json = serde_json::to_value(&old).unwrap();
+
+    assert_eq!(
+      json,
+      serde_json::json!({ "Label": "Red", "Subject": ["a", "b"] })
+    );
+    assert_eq!(
+      serde_json::from_value::<BTreeMap<String, Option<TagValue>>>(json).unwrap(),
+      old
+    );
+  }
+}
@@ -0,0 +1,152 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Sidecar naming schemes, for catalogs shared with editors other than
+//! darktable.
+
+use std::collections::HashSet;
+
+use super::{Organizer, report::Action};
+use crate::{
+  io,
+  org,
+  prim::{self, Handle, Media, Sidecar, SidecarNaming},
+};
+
+impl Organizer {
+  /// Sets the scheme new sidecars are named by (see `convert_sidecars` for
+  /// existing ones).
+  pub fn set_sidecar_naming(&mut self, naming: SidecarNaming) {
+    self.sidecar_naming = naming;
+  }
+
+  /// Renames existing sidecars to the scheme set by `set_sidecar_naming`.
+  /// Duplicate sidecars are left as is, as only darktable writes these.
+  pub fn convert_sidecars(&mut self) -> Result<(), String> {
+    log::info!("Renaming sidecars to {:?} naming.", self.sidecar_naming);
+
+    let stem_named = self.get_stem_named_media();
+
+    let pairs = self
+      .media
+      .iter_data_indexed()
+      .filter_map(|(handle, media)| Some((handle, media.get_sidecar()?)))
+      .collect::<Vec<_>>();
+
+    for (handle_media, handle_sidecar) in pairs {
+      let naming = self.get_sidecar_naming(handle_media, &stem_named);
+      let sidecar = &self.sidecars[handle_sidecar];
+
+      if sidecar.get_naming() == naming {
+        continue;
+      }
+
+      let from = org::to_abs_path(&self.source, sidecar);
+      let to = naming.get_path(org::to_abs_path(&self.source, &self.media[handle_media]));
+
+      if to.exists() {
+        log::warn!(
+          "{sidecar}: Not renaming sidecar, as {} already exists.",
+          to.display()
+        );
+        continue;
+      }
+
+      log::debug!("{sidecar}: Renaming to {}.", to.display());
+
+      let to = io::rename_file(&from, to)?;
+      self.report.action(&from, Action::Moved { to: to.clone() });
+      self.sidecars[handle_sidecar].update_metadata(io::read_metadata(to)?);
+    }
+
+    Ok(())
+  }
+
+  /// Gets the media files whose sidecars should be named by stem: with the
+  /// `Stem` scheme, one per stem (see `pick_stem_sidecar_media`). Other media
+  /// files sharing the stem fall back to full names.
+  pub fn get_stem_named_media(&self) -> HashSet<Handle<Media>> {
+    if self.sidecar_naming != SidecarNaming::Stem {
+      return HashSet::new();
+    }
+
+    super::map_by_stem(&self.source, &self.media)
+      .into_values()
+      .filter_map(|c| prim::pick_stem_sidecar_media(c.iter().map(|&h| (h, &self.media[h]))))
+      .collect()
+  }
+
+  /// Gets the scheme to name the sidecar of the media file at `handle` by,
+  /// given `stem_named` (see `get_stem_named_media`). This falls back to full
+  /// names if another file already has the stem-named path.
+  pub fn get_sidecar_naming(
+    &self,
+    handle: Handle<Media>,
+    stem_named: &HashSet<Handle<Media>>,
+  ) -> SidecarNaming {
+    if !stem_named.contains(&handle) {
+      return SidecarNaming::FullName;
+    }
+
+    let media = &self.media[handle];
+    let path = SidecarNaming::Stem.get_path(org::to_abs_path(&self.source, media));
+    let is_own = media
+      .get_sidecar()
+      .is_some_and(|h| org::to_abs_path(&self.source, &self.sidecars[h]) == path);
+
+    if path.exists() && !is_own {
+      SidecarNaming::FullName
+    } else {
+      SidecarNaming::Stem
+    }
+  }
+}
+
+#[cfg(test)]
+mod test_convert_sidecars {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn renames_to_stem() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": {},
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_sidecar_naming(SidecarNaming::Stem);
+    o.convert_sidecars().unwrap();
+
+    assert_dir!(d, ["image.jpg", "image.xmp"]);
+  }
+
+  #[test]
+  fn keeps_full_names_for_shared_stems() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": {},
+      "image.mov": { "CompressorID": "avc1" },
+      "image.mov.xmp": {},
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_sidecar_naming(SidecarNaming::Stem);
+    o.convert_sidecars().unwrap();
+
+    assert_dir!(d, ["image.jpg", "image.mov", "image.mov.xmp", "image.xmp"]);
+  }
+
+  #[test]
+  fn renames_to_full_name() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": {},
+    );
+    std::fs::rename(d.get_path("image.jpg.xmp"), d.get_path("image.xmp")).unwrap();
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.convert_sidecars().unwrap();
+
+    assert_dir!(d, ["image.jpg", "image.jpg.xmp"]);
+  }
+}
@@ -2,14 +2,30 @@
 
 //! Organizer Stage 1: Automatic deletion of duplicates and leftovers.
 
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, mem, path::Path};
 
-use super::Organizer;
+use serde::Deserialize;
+
+use super::{
+  Organizer,
+  report::{Action, Report},
+};
 use crate::{
   io,
-  prim::{Handle, LivePhotoLinker, Media, Sidecar},
+  prim::{Handle, LivePhotoComponentType, LivePhotoLinker, Media, Sidecar},
 };
 
+/// Which versions of Apple Photos edits to keep: the original, the edited
+/// render (e.g. `IMG_E1234.HEIC`), or both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditPolicy {
+  #[default]
+  Both,
+  Edited,
+  Original,
+}
+
 /// Allows using `LivePhotoLink::drain_images` and `drain_videos` as generics in
 /// `remove_live_photo_duplicates_by_type`, without calls to those functions
 /// borrowing `self` mutably past the point at which their returned iterators
@@ -34,17 +50,93 @@ where
 }
 
 impl Organizer {
+  /// Removes versions of Apple Photos edits per `policy`, alongside their
+  /// sidecars, and adjustments (`.AAE`) without an original. When keeping
+  /// edited renders, these replace their originals (e.g. within Live Photos),
+  /// and adjustments to the removed originals are removed too.
+  pub fn apply_edit_policy(&mut self, policy: EditPolicy) -> Result<(), String> {
+    log::info!("Applying Apple Photos edit policy ({policy:?}).");
+
+    for path in mem::take(&mut self.adjustments) {
+      remove_by_path(
+        &self.source,
+        path,
+        self.trash.as_ref(),
+        &mut self.report,
+        "adjustments leftover",
+      )?;
+    }
+
+    match policy {
+      EditPolicy::Both => (),
+      EditPolicy::Original => {
+        let mut edits = Vec::new();
+        for edit_group in self.edit_groups.values_mut() {
+          edits.extend(edit_group.drain_edits());
+        }
+
+        for handle in edits {
+          self.edit_originals.remove(&handle);
+          self.remove_media_with_deps(handle, "edited version")?;
+        }
+      }
+      EditPolicy::Edited => {
+        for (handle_original, mut edit_group) in mem::take(&mut self.edit_groups) {
+          if !edit_group.has_edits() {
+            self.edit_groups.insert(handle_original, edit_group);
+            continue;
+          }
+
+          for path in edit_group.drain_adjustments() {
+            remove_by_path(
+              &self.source,
+              path,
+              self.trash.as_ref(),
+              &mut self.report,
+              "adjustments to original version",
+            )?;
+          }
+
+          let edits = edit_group.drain_edits().collect::<Vec<_>>();
+          for handle in &edits {
+            self.edit_originals.remove(handle);
+          }
+
+          if let Some(id) = self.media[handle_original].content_id()
+            && let Some(link) = self.live_photo_map.get_mut(&id)
+          {
+            let is_image = self.media[handle_original].get_live_photo_component_type()
+              == Some(LivePhotoComponentType::Image);
+            link.remove(handle_original);
+
+            for &handle in &edits {
+              if is_image {
+                link.insert_image(handle, &self.media[handle]);
+              } else {
+                link.insert_video(handle, &self.media[handle]);
+              }
+            }
+          }
+
+          self.remove_media_with_deps(handle_original, "original version")?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+
   /// Removes leftover Live Photos videos. These are video files that were part
   /// of a Live Photo, where the corresponding image no longer exists. It is
   /// assumed this means the image was purposefully deleted, and as such, so
-  /// too should the video.
+  /// too should the video. Videos linked to an image in the catalog are kept.
   pub fn remove_live_photo_leftovers(&mut self) -> Result<(), String> {
     log::info!("Removing videos from deleted Live Photo images.");
 
     let (leftover, good): (HashMap<_, _>, HashMap<_, _>) = self
       .live_photo_map
       .drain()
-      .partition(|(_, l)| l.is_leftover_videos());
+      .partition(|(id, l)| l.is_leftover_videos() && !self.catalog_images.contains_key(id));
 
     self.live_photo_map.extend(good);
 
@@ -55,7 +147,13 @@ impl Organizer {
           .get_entry_mut(media_handle)
           .take()
           .ok_or(format!("Cannot find media handle `{media_handle}` in map."))?;
-        remove_by_path(&self.source, media, self.trash.as_ref())?;
+        remove_by_path(
+          &self.source,
+          media,
+          self.trash.as_ref(),
+          &mut self.report,
+          "Live Photo leftover",
+        )?;
       }
     }
 
@@ -112,7 +210,14 @@ impl Organizer {
           .get_entry_mut(removed)
           .take()
           .ok_or(format!("Cannot find media handle `{removed}` in map."))?;
-        remove_by_path(&self.source, media, self.trash.as_ref())?;
+        let reason = format!("duplicate {}", media.get_codec());
+        remove_by_path(
+          &self.source,
+          media,
+          self.trash.as_ref(),
+          &mut self.report,
+          &reason,
+        )?;
       }
 
       insert(link, handle, &self.media[handle]);
@@ -128,35 +233,170 @@ impl Organizer {
 
     for sidecar in self.sidecars.iter_entries_mut() {
       if let Some(sidecar) = sidecar.take_if(|s| s.is_leftover()) {
-        remove_by_path(&self.source, sidecar, self.trash.as_ref())?;
+        remove_by_path(
+          &self.source,
+          sidecar,
+          self.trash.as_ref(),
+          &mut self.report,
+          "sidecar leftover",
+        )?;
       }
     }
 
     for sidecar in self.dupes.iter_entries_mut() {
       if let Some(sidecar) = sidecar.take_if(|s| s.is_leftover()) {
-        remove_by_path(&self.source, sidecar, self.trash.as_ref())?;
+        remove_by_path(
+          &self.source,
+          sidecar,
+          self.trash.as_ref(),
+          &mut self.report,
+          "duplicate sidecar leftover",
+        )?;
       }
     }
 
     Ok(())
   }
+
+  /// Removes leftover video companion files (e.g. `.THM` or `.SRT`), whose
+  /// video no longer exists.
+  pub fn remove_companion_leftovers(&mut self) -> Result<(), String> {
+    log::info!("Removing companion files missing associated videos.");
+
+    for path in mem::take(&mut self.companions) {
+      remove_by_path(
+        &self.source,
+        path,
+        self.trash.as_ref(),
+        &mut self.report,
+        "companion leftover",
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Organizer {
+  /// Removes the media file at `handle`, alongside its sidecars and companion
+  /// files, because of `reason`.
+  fn remove_media_with_deps(&mut self, handle: Handle<Media>, reason: &str) -> Result<(), String> {
+    let media = self
+      .media
+      .get_entry_mut(handle)
+      .take()
+      .ok_or(format!("Cannot find media handle `{handle}` in map."))?;
+
+    let sidecars = media
+      .get_sidecar()
+      .and_then(|h| self.sidecars.get_entry_mut(h).take())
+      .map(|s| s.as_ref().to_path_buf());
+    let dupes = media
+      .iter_dupes()
+      .filter_map(|h| self.dupes.get_entry_mut(h).take())
+      .map(|d| d.as_ref().to_path_buf());
+
+    let companions = self.video_companions.remove(&handle).unwrap_or_default();
+
+    for path in std::iter::once(media.as_ref().to_path_buf())
+      .chain(sidecars)
+      .chain(dupes.collect::<Vec<_>>())
+      .chain(companions)
+    {
+      remove_by_path(
+        &self.source,
+        path,
+        self.trash.as_ref(),
+        &mut self.report,
+        reason,
+      )?;
+    }
+
+    Ok(())
+  }
 }
 
 /// Remove a file to `trash`, if `Some`, preserving relative path from the
-/// scanned input directory.
-fn remove_by_path(
+/// scanned input directory. Records the removal and its `reason` in `report`.
+pub fn remove_by_path(
   root: impl AsRef<Path>,
   path_relative: impl AsRef<Path>,
   trash: Option<impl AsRef<Path>>,
+  report: &mut Report,
+  reason: &str,
 ) -> Result<(), String> {
+  let reason = reason.to_string();
+
   if let Some(trash) = trash {
-    log::warn!("{}: Moving to trash.", path_relative.as_ref().display());
-    io::remove_file(&root, trash, root.as_ref().join(path_relative))?;
+    log::warn!(
+      "{}: Moving to trash ({reason}).",
+      path_relative.as_ref().display()
+    );
+    io::remove_file(
+      &root,
+      trash,
+      root.as_ref().join(&path_relative),
+      &reason,
+      report.get_run(),
+    )?;
+    report.action(path_relative, Action::Trashed { reason });
+  } else {
+    report.action(path_relative, Action::Dropped { reason });
   }
 
   Ok(())
 }
 
+#[cfg(test)]
+mod test_apply_edit_policy {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn keeps_both() {
+    let d = test_dir!(
+      "IMG_1234.jpg": {},
+      "IMG_E1234.jpg": {},
+    );
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.apply_edit_policy(EditPolicy::Both).unwrap();
+
+    assert_dir!(d, ["IMG_1234.jpg", "IMG_E1234.jpg"]);
+  }
+
+  #[test]
+  fn keeps_original() {
+    let d = test_dir!(
+      "IMG_1234.jpg": {},
+      "IMG_E1234.jpg": {},
+      "IMG_E1234.jpg.xmp": {},
+    );
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.apply_edit_policy(EditPolicy::Original).unwrap();
+
+    assert_dir!(d, ["IMG_1234.jpg"]);
+    assert_trash!(d, ["IMG_E1234.jpg", "IMG_E1234.jpg.xmp"]);
+  }
+
+  #[test]
+  fn keeps_edited_in_live_photo() {
+    let d = test_dir!(
+      "IMG_1234.heic": { "ContentIdentifier": "ID" },
+      "IMG_E1234.heic": {},
+      "IMG_1234.mov": { "ContentIdentifier": "ID", "CompressorID": "hvc1" },
+    );
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.apply_edit_policy(EditPolicy::Edited).unwrap();
+    o.remove_live_photo_leftovers().unwrap();
+
+    assert_dir!(d, ["IMG_E1234.heic", "IMG_1234.mov"]);
+    assert_trash!(d, ["IMG_1234.heic"]);
+  }
+}
+
 #[cfg(test)]
 mod test_remove_live_photo_leftovers {
   use super::*;
@@ -347,3 +587,36 @@ mod test_remove_sidecar_leftovers {
     assert_trash!(d, ["image_01.jpg.xmp"]);
   }
 }
+
+#[cfg(test)]
+mod test_remove_companion_leftovers {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn keeps_paired_companion() {
+    let d = test_dir!(
+      "DJI_0001.mov": { "CompressorID": "avc1" },
+    );
+    fs::write(d.get_path("DJI_0001.SRT"), "1\n").unwrap();
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.remove_companion_leftovers().unwrap();
+
+    assert_dir!(d, ["DJI_0001.mov", "DJI_0001.SRT"]);
+  }
+
+  #[test]
+  fn removes_leftover_companion() {
+    let d = test_dir!();
+    fs::write(d.get_path("DJI_0001.SRT"), "1\n").unwrap();
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.remove_companion_leftovers().unwrap();
+
+    assert_dir!(d, []);
+    assert_trash!(d, ["DJI_0001.SRT"]);
+  }
+}
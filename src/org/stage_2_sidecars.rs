@@ -2,33 +2,68 @@
 
 //! Organizer Stage 2: Automatic sidecar creation.
 
-use super::Organizer;
-use crate::{io, org, prim::SidecarInitial};
+use super::{
+  Organizer,
+  report::{Action, FileRole},
+};
+use crate::{
+  io,
+  org,
+  prim::{Handle, Media, SidecarInitial, SidecarNaming},
+};
 
 impl Organizer {
-  /// Creates a new XMP sidecar for any file without one, and loads it into the
-  /// organizer for future stages.
+  /// Creates a new XMP sidecar for any file without one, named by the scheme
+  /// set by `set_sidecar_naming`, and loads it into the organizer for future
+  /// stages.
   pub fn create_missing_sidecars(&mut self) -> Result<(), String> {
     log::info!("Creating XMP sidecars for media files without.");
 
-    for media in self.media.iter_data_mut() {
-      if !media.is_missing_sidecar() {
-        continue;
-      }
+    let stem_named = self.get_stem_named_media();
+    let missing = self
+      .media
+      .iter_data_indexed()
+      .filter(|(_, media)| media.is_missing_sidecar())
+      .map(|(handle, _)| (handle, self.get_sidecar_naming(handle, &stem_named)))
+      .collect::<Vec<_>>();
 
-      log::debug!("{media}: Creating XMP sidecar.");
-
-      let metadata = io::create_xmp(org::to_abs_path(
-        &self.source,
-        &media.get_metadata().source_file,
-      ))?;
-
-      let path = metadata.as_ref().to_path_buf();
-      self.sidecars.insert(path, SidecarInitial::new(metadata)?);
+    for (handle, naming) in missing {
+      self.create_sidecar(handle, naming)?;
     }
 
     Ok(())
   }
+
+  /// Creates a new XMP sidecar for the media file at `handle`, named by
+  /// `naming`, and loads it into the organizer. Returns its handle.
+  pub fn create_sidecar(
+    &mut self,
+    handle: Handle<Media>,
+    naming: SidecarNaming,
+  ) -> Result<Handle<SidecarInitial>, String> {
+    let media = &self.media[handle];
+
+    log::debug!("{media}: Creating XMP sidecar.");
+
+    let metadata = io::create_xmp(
+      org::to_abs_path(&self.source, &media.get_metadata().source_file),
+      naming,
+    )?;
+
+    let path = metadata.as_ref().to_path_buf();
+
+    self.report.action(media, Action::SidecarCreated {
+      sidecar: path.clone(),
+    });
+    let group = self.report.get_group(media);
+    self
+      .report
+      .set_group(&path, FileRole::Sidecar, group.as_deref());
+
+    self.sidecars.insert(&path, SidecarInitial::new(metadata)?);
+
+    Ok(self.sidecars.find(&path).unwrap())
+  }
 }
 
 #[cfg(test)]
@@ -60,6 +95,20 @@ mod test_create_missing_sidecars {
     assert_dir!(d, ["image.jpg", "image.jpg.xmp"]);
   }
 
+  #[test]
+  fn creates_stem_named_sidecar() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.mov": { "CompressorID": "avc1" },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_sidecar_naming(SidecarNaming::Stem);
+    o.create_missing_sidecars().unwrap();
+
+    assert_dir!(d, ["image.jpg", "image.mov", "image.mov.xmp", "image.xmp"]);
+  }
+
   #[test]
   fn skips_if_sidecar_already_exists() {
     let d = test_dir!(
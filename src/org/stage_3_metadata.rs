@@ -2,15 +2,18 @@
 
 //! Organizer Stage 3: Automatic metadata updates.
 
-use std::ffi::OsStr;
+use std::{ffi::OsStr, path::Path};
 
-use tzf_rs::{Finder, r#gen::tzf::v1::Timezones};
-
-use super::Organizer;
+use super::{
+  Organizer,
+  Report,
+  report::{Action, Finding},
+  rules::Severity,
+};
 use crate::{
   io,
   org,
-  prim::{self, FileCategory, Sidecar},
+  prim::{self, CameraOwner, CopyrightConfig, FileCategory, Media, Metadata, OwnerMatch, Sidecar},
 };
 
 /// Holds which metadata update passes are enabled.
@@ -18,6 +21,7 @@ use crate::{
 #[derive(Default)]
 pub struct MetadataUpdateConfig {
   align_mwg_tags:             bool,
+  camera_owners:              Vec<CameraOwner>,
   set_copyright_from_creator: bool,
   set_location_from_gps:      bool,
   set_time_zone_from_gps:     bool,
@@ -27,6 +31,7 @@ impl MetadataUpdateConfig {
   /// If any update is enabled.
   fn enabled(&self) -> bool {
     self.align_mwg_tags
+      || !self.camera_owners.is_empty()
       || self.set_copyright_from_creator
       || self.set_location_from_gps
       || self.set_time_zone_from_gps
@@ -43,8 +48,17 @@ impl Organizer {
     self.metadata_updates.align_mwg_tags = true;
   }
 
+  /// Automatically writes the `Creator` tag, if not set, from the owner of the
+  /// camera each file was shot with in `owners`. Files from cameras matching
+  /// several owners (e.g. shared cameras) are reported instead.
+  pub fn enable_set_creator_from_camera(&mut self, owners: Vec<CameraOwner>) {
+    log::info!("Enabling automatic creator ({} camera(s)).", owners.len());
+    self.metadata_updates.camera_owners = owners;
+  }
+
   /// Automatically writes the `Copyright` tag from `Creator`, if `Creator` is
-  /// set and `Copyright` not.
+  /// set and `Copyright` not, alongside any other rights tags missing. Values
+  /// follow the policy set by `set_copyright_policy`.
   pub fn enable_set_copyrights_from_creator(&mut self) {
     log::info!("Enabling automatic copyright.");
     self.metadata_updates.set_copyright_from_creator = true;
@@ -74,38 +88,32 @@ impl Organizer {
 
     log::info!("Applying metadata updates.");
 
-    let finder = if self.metadata_updates.set_time_zone_from_gps {
-      Finder::from_pb(
-        Timezones::try_from(
-          include_bytes!("../../third_party/tzf-rel/combined-with-oceans.bin").to_vec(),
-        )
-        .unwrap(),
-      )
-    } else {
-      Finder::new()
-    };
-
     for media in self.media.iter_data_mut() {
-      // Main pass (copyright, location & time zone).
+      // Main pass (creator, copyright, location & time zone).
       {
         let metadata = media
           .get_sidecar()
           .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata());
 
         let mut args = Vec::new();
-
-        if self.metadata_updates.set_copyright_from_creator
-          && metadata.creator.is_some()
-          && metadata.copyright.is_none()
-        {
-          args.push(OsStr::new("-Copyright<Copyright ${Creator}"));
-        }
+        let mut passes = Vec::new();
+
+        let attribution_args = get_attribution_args(
+          &mut self.report,
+          &self.metadata_updates,
+          &self.copyright,
+          media,
+          metadata,
+          &mut passes,
+        );
+        args.extend(attribution_args.iter().map(OsStr::new));
 
         if self.metadata_updates.set_location_from_gps
           && metadata.gps_latitude.is_some()
           && metadata.gps_longitude.is_some()
         {
           args.push(OsStr::new("-geolocate<GPSPosition"));
+          passes.push("location");
         }
 
         let time_zone_args;
@@ -113,11 +121,13 @@ impl Organizer {
         if self.metadata_updates.set_time_zone_from_gps
           && let Some(lat_lon) = metadata.get_lat_lon()
           && let Some((date_time, _)) = metadata.get_date_time_original()
+          && let Some(offset) = prim::get_offset_for_time_zone(
+            &date_time,
+            prim::get_time_zone_name(lat_lon.0, lat_lon.1),
+          )
+          .inspect_err(|err| log::warn!("{metadata}: Not setting time zone ({err})."))
+          .ok()
         {
-          let time_zone = finder.get_tz_name(f64::from(lat_lon.1), f64::from(lat_lon.0));
-
-          let offset = prim::get_offset_for_time_zone(&date_time, time_zone);
-
           let date_time_new = date_time.and_local_timezone(offset).unwrap();
 
           time_zone_args = Vec::from([
@@ -127,6 +137,7 @@ impl Organizer {
 
           args.push(OsStr::new(&time_zone_args[0]));
           args.push(OsStr::new(&time_zone_args[1]));
+          passes.push("time_zone");
         }
 
         if !args.is_empty() {
@@ -139,11 +150,9 @@ impl Organizer {
 
           let metadata = io::read_metadata(&path)?;
 
-          if let Some(sidecar) = media.get_sidecar().map(|h| &mut self.sidecars[h]) {
-            sidecar.update_metadata(metadata.clone());
-          } else {
-            media.update_metadata(metadata);
-          }
+          record_updates(&mut self.report, &path, &passes, &metadata);
+
+          org::update_media_metadata(&mut self.report, &mut self.sidecars, media, metadata);
         }
       }
 
@@ -168,6 +177,10 @@ impl Organizer {
             OsStr::new("-MWG:all<MWG:all"),
             path.as_os_str(),
           ])?;
+
+          self.report.action(&path, Action::MetadataUpdated {
+            pass: "align_mwg_tags".to_string(),
+          });
         }
       }
     }
@@ -176,6 +189,117 @@ impl Organizer {
   }
 }
 
+/// Gets arguments for the creator and copyright passes (if enabled) for
+/// `media`, whose metadata (or its sidecar's) is `metadata`, adding to `passes`
+/// those with updates.
+fn get_attribution_args(
+  report: &mut Report,
+  config: &MetadataUpdateConfig,
+  copyright: &CopyrightConfig,
+  media: &Media,
+  metadata: &Metadata,
+  passes: &mut Vec<&str>,
+) -> Vec<String> {
+  let mut args = Vec::new();
+
+  let owner = if metadata.creator.is_none() {
+    find_camera_owner(report, &config.camera_owners, media)
+  } else {
+    None
+  };
+
+  let with_creator;
+  let metadata_rights = if let Some(owner) = owner {
+    args.push(format!("-Creator={owner}"));
+    passes.push("creator");
+
+    with_creator = Metadata {
+      creator: Some(owner.to_string()),
+      ..metadata.clone()
+    };
+    &with_creator
+  } else {
+    metadata
+  };
+
+  if config.set_copyright_from_creator {
+    let len = args.len();
+
+    args.extend(
+      copyright
+        .get_expected(metadata_rights)
+        .into_iter()
+        .filter(|(tag, _)| metadata.get(tag).is_none())
+        .map(|(tag, value)| prim::get_rights_write_arg(tag, &value)),
+    );
+
+    if args.len() > len {
+      passes.push("copyright");
+    }
+  }
+
+  args
+}
+
+/// Finds the owner of the camera `media` was shot with in `owners`, reporting
+/// cameras matching several owners.
+fn find_camera_owner<'a>(
+  report: &mut Report,
+  owners: &'a [CameraOwner],
+  media: &Media,
+) -> Option<&'a str> {
+  match prim::find_camera_owner(owners, media.get_metadata()) {
+    OwnerMatch::Owner(owner) => Some(owner),
+    OwnerMatch::Ambiguous(matched) => {
+      let message = format!("Camera matches several owners ({}).", matched.join(", "));
+      log::warn!("{media}: {message}");
+      report.finding(media, Finding {
+        rule: "camera_owner".to_string(),
+        severity: Severity::Warning,
+        message,
+      });
+      None
+    }
+    OwnerMatch::None => None,
+  }
+}
+
+/// Records each update pass applied to the file at `path`, alongside the
+/// resulting values of the tags it writes, in `report`.
+fn record_updates(report: &mut Report, path: &Path, passes: &[&str], metadata: &Metadata) {
+  for pass in passes {
+    report.action(path, Action::MetadataUpdated {
+      pass: (*pass).to_string(),
+    });
+
+    let tags = match *pass {
+      "creator" => vec![("Creator", metadata.creator.clone())],
+      "copyright" => ["Copyright", "UsageTerms", "WebStatement", "Marked"]
+        .into_iter()
+        .map(|tag| (tag, metadata.get(tag)))
+        .filter(|(tag, value)| *tag == "Copyright" || value.is_some())
+        .collect(),
+      "location" => vec![
+        ("City", metadata.city.clone()),
+        ("State", metadata.state.clone()),
+        ("Country", metadata.country.clone()),
+      ],
+      "time_zone" => vec![(
+        "DateTimeOriginal",
+        metadata
+          .sub_sec_date_time_original
+          .clone()
+          .or(metadata.date_time_original.clone()),
+      )],
+      _ => Vec::new(),
+    };
+
+    for (tag, value) in tags {
+      report.tag_change(path, tag, value.as_deref());
+    }
+  }
+}
+
 #[cfg(test)]
 mod test_align_mwg_tags {
   use super::*;
@@ -343,6 +467,50 @@ mod test_set_copyright_from_creator {
   }
 }
 
+#[cfg(test)]
+mod test_set_creator_from_camera {
+  use super::*;
+  use crate::testing::*;
+
+  fn owners() -> Vec<CameraOwner> {
+    vec![CameraOwner {
+      owner: "Owner".to_string(),
+      model: Some("R5".to_string()),
+      ..Default::default()
+    }]
+  }
+
+  #[test]
+  fn preserves_existing_creator() {
+    let d = test_dir!(
+      "image.jpg": { "Model": "R5" },
+      "image.jpg.xmp": { "Creator": "Creator" },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.enable_set_creator_from_camera(owners());
+    o.apply_metadata_updates().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Creator", "Creator");
+  }
+
+  #[test]
+  fn sets_creator_and_copyright_if_missing() {
+    let d = test_dir!(
+      "image.jpg": { "Model": "R5" },
+      "image.jpg.xmp": {},
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.enable_set_creator_from_camera(owners());
+    o.enable_set_copyrights_from_creator();
+    o.apply_metadata_updates().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Creator", "Owner");
+    assert_tag!(d, "image.jpg.xmp", "Copyright", "Copyright Owner");
+  }
+}
+
 #[cfg(test)]
 mod test_set_location_from_gps {
   use super::*;
@@ -4,17 +4,71 @@
 
 use std::fmt::Write;
 
-use super::Organizer;
+use super::{Organizer, catalog_links, report::Action};
 use crate::{io, org, prim::Sidecar};
 
 impl Organizer {
   /// Synchronizes metadata from Live Photo images to their corresponding
-  /// videos. This means that any manual changes only need to be made for the
-  /// image, and it can be copied here automatically.
+  /// videos (including those linked in the catalog). This means that any
+  /// manual changes only need to be made for the image, and it can be copied
+  /// here automatically.
   pub fn sync_live_photo_metadata(&mut self) -> Result<(), String> {
     log::info!("Synchronizing metadata across Live Photo components.");
 
-    for l in self.live_photo_map.values_mut() {
+    for (id, l) in &mut self.live_photo_map {
+      if let Some(image_path) = self.catalog_images.get(id) {
+        let Some(handle_video_sidecar) = self.media[l.get_video_best()].get_sidecar() else {
+          continue;
+        };
+        let video_sidecar = &mut self.sidecars[handle_video_sidecar];
+        let from = catalog_links::get_catalog_metadata_source(image_path);
+
+        log::trace!(
+          "{} -> {}: Synchronizing metadata from catalog.",
+          from.display(),
+          video_sidecar
+        );
+
+        let metadata = io::copy_metadata(&from, org::to_abs_path(&self.source, &video_sidecar))?;
+        self
+          .report
+          .metadata_diff(&*video_sidecar, video_sidecar.get_metadata(), &metadata);
+        video_sidecar.update_metadata(metadata);
+
+        self
+          .report
+          .action(&video_sidecar, Action::MetadataSynced { from });
+        continue;
+      }
+
+      if let Some(video_path) = self.catalog_videos.get(id) {
+        let to = catalog_links::get_catalog_metadata_source(video_path);
+        if to == *video_path {
+          log::debug!(
+            "{}: Cannot synchronize to catalogued Live Photo video without sidecar.",
+            video_path.display()
+          );
+          continue;
+        }
+
+        let image = &self.media[l.get_image_best()];
+        let Some(handle_image_sidecar) = image.get_sidecar() else {
+          log::debug!("{image}: Cannot synchronize from Live Photo image without sidecar.");
+          continue;
+        };
+        let from = self.sidecars[handle_image_sidecar].as_ref().to_path_buf();
+
+        log::trace!(
+          "{} -> {}: Synchronizing metadata to catalog.",
+          from.display(),
+          to.display()
+        );
+
+        io::copy_metadata(org::to_abs_path(&self.source, &from), &to)?;
+        self.report.action(&to, Action::MetadataSynced { from });
+        continue;
+      }
+
       if !l.is_pair() {
         log::warn!(
           "Cannot synchronize Live Photo with duplicates:{}",
@@ -52,10 +106,17 @@ impl Organizer {
       );
 
       let metadata = io::copy_metadata(
-        org::to_abs_path(&self.source, image_sidecar_path),
+        org::to_abs_path(&self.source, &image_sidecar_path),
         org::to_abs_path(&self.source, &video_sidecar),
       )?;
+      self
+        .report
+        .metadata_diff(&*video_sidecar, video_sidecar.get_metadata(), &metadata);
       video_sidecar.update_metadata(metadata);
+
+      self.report.action(&video_sidecar, Action::MetadataSynced {
+        from: image_sidecar_path,
+      });
     }
 
     Ok(())
@@ -85,7 +146,14 @@ impl Organizer {
           org::to_abs_path(&self.source, sidecar),
           org::to_abs_path(&self.source, &dupe),
         )?;
+        self
+          .report
+          .metadata_diff(&*dupe, dupe.get_metadata(), &metadata);
         dupe.update_metadata(metadata);
+
+        self.report.action(&dupe, Action::MetadataSynced {
+          from: sidecar.as_ref().to_path_buf(),
+        });
       }
     }
 
@@ -113,7 +181,14 @@ impl Organizer {
         org::to_abs_path(&self.source, sidecar),
         org::to_abs_path(&self.source, &media),
       )?;
+      self
+        .report
+        .metadata_diff(&*media, media.get_metadata(), &metadata);
       media.update_metadata(metadata);
+
+      self.report.action(&media, Action::MetadataSynced {
+        from: sidecar.as_ref().to_path_buf(),
+      });
     }
 
     Ok(())
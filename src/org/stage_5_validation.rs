@@ -2,23 +2,110 @@
 
 //! Organizer Stage 5: Metadata validation.
 
-use super::Organizer;
-use crate::prim::{self, FileMap, Handle, Media, Metadata, Sidecar, SidecarInitial};
+use std::{
+  collections::{HashMap, HashSet},
+  path::{Path, PathBuf},
+  sync::LazyLock,
+};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeDelta};
+use regex::Regex;
+use serde::Deserialize;
+
+use super::{
+  Organizer,
+  report::Finding,
+  rules::{Rule, Severity},
+  stage_6_organization,
+};
+use crate::{
+  io,
+  org,
+  prim::{
+    self,
+    BurstLayout,
+    CopyrightConfig,
+    FileMap,
+    Handle,
+    Integrity,
+    Media,
+    Metadata,
+    Sidecar,
+    SidecarInitial,
+  },
+};
+
+/// `ExifTool`'s copy number (if any), left between the expected stem and
+/// extension when another file had the same time.
+static COPY_NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?:_[a-z]+)?$").unwrap());
+
+/// A single validation check, returning a description of each problem found.
+type Check = fn(&Metadata) -> Vec<String>;
 
 /// Stores which validation checks are enabled.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Default)]
 pub struct ValidationConfig {
-  pub attribution: bool,
-  pub camera:      bool,
-  pub date_time:   bool,
-  pub location:    bool,
+  pub attribution:  bool,
+  pub camera:       bool,
+  pub date_time:    bool,
+  pub location:     bool,
+  pub rules:        Vec<Rule>,
+  /// Catalog root to check file names against, if enabled.
+  pub file_name:    Option<PathBuf>,
+  /// Bounds for plausibility checks, if enabled.
+  pub plausibility: Option<PlausibilityConfig>,
+  /// Whether integrity checks have been run.
+  pub integrity:    bool,
+}
+
+/// Bounds for checking whether dates and GPS coordinates are plausible, rather
+/// than just present.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PlausibilityConfig {
+  /// Dates cameras commonly reset their clocks to, which are unlikely to be
+  /// real capture dates.
+  pub reset_dates:        Vec<NaiveDate>,
+  /// Earliest plausible capture date.
+  pub min_date:           Option<NaiveDate>,
+  /// How many hours into the future a capture date may be, to allow for clock
+  /// drift.
+  pub future_hours:       i64,
+  /// Distance, in degrees, from 0° latitude and longitude within which GPS
+  /// coordinates are assumed to be bogus ("Null Island").
+  pub null_island_radius: f32,
+  /// Whether `OffsetTimeOriginal` must match the time zone at the GPS
+  /// location.
+  pub time_zone:          bool,
+}
+
+impl Default for PlausibilityConfig {
+  fn default() -> Self {
+    Self {
+      reset_dates:        [(1970, 1, 1), (1980, 1, 1), (2000, 1, 1)]
+        .into_iter()
+        .filter_map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d))
+        .collect(),
+      min_date:           None,
+      future_hours:       24,
+      null_island_radius: 0.01,
+      time_zone:          true,
+    }
+  }
 }
 
 impl ValidationConfig {
   /// If any check is enabled.
-  pub fn enabled(&mut self) -> bool {
-    self.attribution || self.camera || self.date_time || self.location
+  pub fn enabled(&self) -> bool {
+    self.attribution
+      || self.camera
+      || self.date_time
+      || self.location
+      || !self.rules.is_empty()
+      || self.file_name.is_some()
+      || self.plausibility.is_some()
+      || self.integrity
   }
 }
 
@@ -49,6 +136,69 @@ impl Organizer {
     self.validation.location = true;
   }
 
+  /// Validates whether dates and GPS coordinates are plausible, within the
+  /// bounds in `config` (e.g. not a camera's reset date, or at 0°, 0°).
+  pub fn enable_plausibility_validation(&mut self, config: PlausibilityConfig) {
+    log::info!("Date and location plausibility validation enabled.");
+    self.validation.plausibility = Some(config);
+  }
+
+  /// Checks that each media file is complete, both structurally (e.g. JPEG EOI
+  /// marker, ISO-BMFF box sizes) and via `ExifTool`'s validation. Files with
+  /// errors will then fail validation. Unlike other checks, this runs
+  /// immediately, as it reads every file in full.
+  pub fn verify_integrity(&mut self) -> Result<(), String> {
+    log::info!("Verifying file integrity.");
+
+    let mut results = HashMap::new();
+
+    for (handle, media) in self.media.iter_data_indexed() {
+      let path = org::to_abs_path(&self.source, media);
+
+      let mut integrity = Integrity::default();
+      if let Err(e) = prim::check_structure(&path, &media.get_metadata().file_type) {
+        integrity.errors.push(e);
+      }
+
+      results.insert(path.canonicalize().unwrap_or(path), (handle, integrity));
+    }
+
+    for output in io::read_validation(results.keys())? {
+      let Some((_, integrity)) = results.get_mut(&output.source_file) else {
+        continue;
+      };
+
+      parse_validation(&output, integrity);
+    }
+
+    for (handle, integrity) in results.into_values() {
+      self.media[handle].set_integrity(integrity);
+    }
+
+    self.validation.integrity = true;
+
+    Ok(())
+  }
+
+  /// Validates whether files are where stage 6 would put them within `catalog`,
+  /// based on their current metadata. Drifted files are warned about, and once
+  /// checked, `move_and_rename_files` will only move drifted files.
+  pub fn enable_file_name_validation(&mut self, catalog: impl AsRef<Path>) {
+    log::info!("File name validation enabled.");
+    self.validation.file_name = Some(catalog.as_ref().to_path_buf());
+  }
+
+  /// Validates against user-defined `rules`, in addition to any built-in
+  /// checks.
+  pub fn add_validation_rules(&mut self, rules: impl IntoIterator<Item = Rule>) {
+    let count = self.validation.rules.len();
+    self.validation.rules.extend(rules);
+    log::info!(
+      "{} custom validation rule(s) enabled.",
+      self.validation.rules.len() - count
+    );
+  }
+
   /// Actually runs validation. This batches all operations enabled via calls to
   /// `enable_*_validation` to reduce the number of calls to `ExifTool`.
   pub fn validate(&mut self) {
@@ -58,164 +208,448 @@ impl Organizer {
 
     log::info!("Validating metadata.");
 
-    self
-      .valid_media
-      .extend(validate(&self.media, &self.sidecars, &self.validation));
+    for (handle_media, findings) in validate(
+      &self.media,
+      &self.sidecars,
+      &self.validation,
+      &self.copyright,
+    ) {
+      if findings.iter().all(|f| f.severity < Severity::Error) {
+        self.valid_media.insert(handle_media);
+      }
+
+      let media = &self.media[handle_media];
+
+      for finding in findings {
+        match finding.severity {
+          Severity::Error | Severity::Warning => log::warn!("{media}: {}", finding.message),
+          Severity::Info => log::debug!("{media}: {}", finding.message),
+        }
+        self.report.finding(media, finding);
+      }
+    }
+
+    if let Some(catalog) = self.validation.file_name.clone() {
+      let drifted = self.validate_file_names(&catalog);
+      self.drifted_media = Some(drifted);
+    }
+  }
+
+  /// Number of media files found in the wrong place by file name validation.
+  pub fn count_drifted_media(&self) -> usize {
+    self.drifted_media.as_ref().map_or(0, HashSet::len)
+  }
+
+  /// Leaves all drifted files in place during `move_and_rename_files`.
+  pub fn skip_drifted_media(&mut self) {
+    self.declined_media = self
+      .drifted_media
+      .replace(HashSet::new())
+      .unwrap_or_default();
+  }
+
+  /// Checks each media file's path within `catalog` against the one derived
+  /// from its metadata source, returning the `Handle`s of those that differ.
+  /// As in stage 6, all Live Photo components are named for the best image,
+  /// and Apple Photos edits for their original.
+  fn validate_file_names(&mut self, catalog: &Path) -> HashSet<Handle<Media>> {
+    let mut name_sources = HashMap::new();
+
+    for link in self.live_photo_map.values() {
+      if link.is_leftover_videos() {
+        continue;
+      }
+
+      let handle_main = link.get_image_best();
+      for handle in link.iter_images().chain(link.iter_videos()) {
+        name_sources.insert(handle, handle_main);
+      }
+    }
+
+    for (&handle_edit, handle_original) in &self.edit_originals {
+      let handle_main = name_sources
+        .get(handle_original)
+        .copied()
+        .unwrap_or(*handle_original);
+      name_sources.insert(handle_edit, handle_main);
+    }
+
+    let mut drifted = HashSet::new();
+
+    for (handle_media, media) in self.media.iter_data_indexed() {
+      let handle_source = name_sources
+        .get(&handle_media)
+        .copied()
+        .unwrap_or(handle_media);
+      let source = &self.media[handle_source];
+      let suffix = if self.edit_originals.contains_key(&handle_media) {
+        prim::EDITED_SUFFIX
+      } else {
+        ""
+      };
+      let metadata = source
+        .get_sidecar()
+        .map_or(source.get_metadata(), |h| self.sidecars[h].get_metadata());
+
+      let path = org::to_abs_path(&self.source, media);
+      let burst = self
+        .bursts
+        .get(&handle_source)
+        .map(|b| (self.burst_layout, b.name.as_str()));
+
+      if let Some(message) = validate_file_name(
+        catalog,
+        &path,
+        media.get_metadata(),
+        metadata,
+        suffix,
+        burst,
+      ) {
+        log::warn!("{media}: {message}");
+        self.report.finding(media, Finding {
+          rule: "file_name".to_string(),
+          severity: Severity::Warning,
+          message,
+        });
+        drifted.insert(handle_media);
+      }
+    }
+
+    drifted
   }
 }
 
 /// Based on supplied `config`, runs validation checks and returns an iterator
-/// over the `Handle`s to valid media files.
-fn validate<'a>(
+/// over the `Handle`s to all media files, alongside findings for any checks
+/// they failed. Built-in checks always have error severity.
+pub fn validate<'a>(
   media: &'a FileMap<Media>,
   sidecars: &'a FileMap<SidecarInitial>,
   config: &'a ValidationConfig,
-) -> impl Iterator<Item = Handle<Media>> + 'a {
+  copyright: &'a CopyrightConfig,
+) -> impl Iterator<Item = (Handle<Media>, Vec<Finding>)> + 'a {
   media
     .iter_data_indexed()
     .map(|(handle_media, media)| {
       (
         handle_media,
+        media.get_metadata().file_type.as_str(),
+        media.get_integrity(),
         media
           .get_sidecar()
           .map_or(media.get_metadata(), |h| sidecars[h].get_metadata()),
       )
     })
-    .filter_map(|(handle_media, metadata)| {
+    .map(|(handle_media, file_type, integrity, metadata)| {
+      let checks: [(&str, bool, Check); 3] = [
+        ("camera", config.camera, validate_camera),
+        ("date_time", config.date_time, validate_date_time),
+        ("location", config.location, validate_location),
+      ];
+
+      let attribution = if config.attribution {
+        validate_attribution(metadata, copyright)
+      } else {
+        Vec::new()
+      };
+      let attribution = attribution.into_iter().map(|message| Finding {
+        rule: "attribution".to_string(),
+        severity: Severity::Error,
+        message,
+      });
+
       // Only run each validation if enabled, but make sure all run even if already
       // invalid.
-      let mut valid = !config.attribution || validate_attribution(metadata);
-      valid = (!config.camera || validate_camera(metadata)) && valid;
-      valid = (!config.date_time || validate_date_time(metadata)) && valid;
-      valid = (!config.location || validate_location(metadata)) && valid;
+      let built_in = checks
+        .into_iter()
+        .filter(|(_, enabled, _)| *enabled)
+        .flat_map(|(rule, _, check)| {
+          check(metadata).into_iter().map(move |message| Finding {
+            rule: rule.to_string(),
+            severity: Severity::Error,
+            message,
+          })
+        });
+
+      let plausibility = config.plausibility.iter().flat_map(|plausibility| {
+        validate_plausibility(metadata, plausibility, Local::now().naive_local())
+          .into_iter()
+          .map(|message| Finding {
+            rule: "plausibility".to_string(),
+            severity: Severity::Error,
+            message,
+          })
+      });
+
+      let integrity = integrity.into_iter().flat_map(|integrity| {
+        let errors = integrity.errors.iter().map(|e| (Severity::Error, e));
+        let warnings = integrity.warnings.iter().map(|w| (Severity::Warning, w));
+
+        errors.chain(warnings).map(|(severity, message)| Finding {
+          rule: "integrity".to_string(),
+          severity,
+          message: message.clone(),
+        })
+      });
+
+      let custom = config.rules.iter().flat_map(|rule| {
+        rule
+          .check(file_type, metadata)
+          .into_iter()
+          .map(|message| Finding {
+            rule: rule.name.clone(),
+            severity: rule.severity,
+            message,
+          })
+      });
 
-      valid.then_some(handle_media)
+      (
+        handle_media,
+        attribution
+          .chain(built_in)
+          .chain(plausibility)
+          .chain(integrity)
+          .chain(custom)
+          .collect(),
+      )
     })
 }
 
-/// Validates attribution tags in `metadata`.
-fn validate_attribution(metadata: &Metadata) -> bool {
-  let creator = metadata.creator.as_ref().ok_or_else(|| {
-    log::warn!("{metadata}: Missing `Creator` tag.");
-  });
-
-  let copyright = metadata.copyright.as_ref().ok_or_else(|| {
-    log::warn!("{metadata}: Missing `Copyright` tag.");
-  });
+/// Adds the problems from `ExifTool`'s validation `output` to `integrity`.
+/// `ExifTool` errors (e.g. a corrupt maker note) make the file fail, while
+/// warnings are only reported.
+fn parse_validation(output: &io::ValidationOutput, integrity: &mut Integrity) {
+  if let Some(error) = &output.error {
+    integrity.errors.push(format!("ExifTool error: {error}"));
+  }
 
-  let Ok(creator) = creator else {
-    return false;
+  let Some(validate) = output.validate.as_deref().filter(|v| *v != "OK") else {
+    return;
   };
 
-  let Ok(copyright) = copyright else {
-    return false;
+  if validate.contains("Error") && output.error.is_none() {
+    integrity
+      .errors
+      .push(format!("ExifTool validation failed ({validate})."));
+  } else if let Some(warning) = &output.warning {
+    integrity
+      .warnings
+      .push(format!("ExifTool validation: {validate} ({warning})."));
+  }
+}
+
+/// Validates that `path` is where stage 6 would move the media file described
+/// by `media`, within `catalog`, with its date and time from `metadata` and
+/// `suffix` after its stem (e.g. for edits), laid out per its `burst`, if
+/// any. Returns a description of the problem, if any. Files without a usable
+/// date are left to `validate_date_time`.
+fn validate_file_name(
+  catalog: &Path,
+  path: &Path,
+  media: &Metadata,
+  metadata: &Metadata,
+  suffix: &str,
+  burst: Option<(BurstLayout, &str)>,
+) -> Option<String> {
+  let stem = stage_6_organization::get_expected_stem(metadata)?;
+  let stem = match burst {
+    Some((layout, name)) => layout.apply(&stem, name),
+    None => stem,
   };
+  let ext = &media.file_type_extension;
+
+  let in_place = path
+    .strip_prefix(catalog)
+    .ok()
+    .and_then(Path::to_str)
+    .and_then(|p| p.strip_prefix(stem.as_str()))
+    .and_then(|p| p.strip_suffix(&format!("{suffix}.{ext}")))
+    .is_some_and(|copy_number| COPY_NUMBER.is_match(copy_number));
+
+  (!in_place)
+    .then(|| format!("File name does not match metadata (expected `{stem}{suffix}.{ext}`)."))
+}
 
-  if *copyright != format!("Copyright {creator}") {
-    log::debug!("{metadata}: Unexpected `Copyright` format (\"{copyright}\").");
+/// Validates that the capture date and GPS coordinates in `metadata` are
+/// plausible per `config`, as of `now` (local time). Missing values are left
+/// to `validate_date_time` and `validate_location`.
+fn validate_plausibility(
+  metadata: &Metadata,
+  config: &PlausibilityConfig,
+  now: NaiveDateTime,
+) -> Vec<String> {
+  let mut problems = Vec::new();
+
+  let date_time_original = metadata.get_date_time_original();
+
+  if let Some((date_time, offset)) = date_time_original {
+    if config.reset_dates.contains(&date_time.date()) {
+      problems.push(format!(
+        "`DateTimeOriginal` is a common camera reset date ({date_time})."
+      ));
+    }
+
+    if let Some(min_date) = config.min_date
+      && date_time.date() < min_date
+    {
+      problems.push(format!(
+        "`DateTimeOriginal` is before {min_date} ({date_time})."
+      ));
+    }
+
+    // Compare in local time, as that's what `now` is in.
+    let date_time_local =
+      offset.map_or(date_time, |o| date_time - o + prim::get_offset_local(&now));
+
+    if date_time_local > now + TimeDelta::hours(config.future_hours) {
+      problems.push(format!(
+        "`DateTimeOriginal` is in the future ({date_time})."
+      ));
+    }
   }
 
-  true
+  if let Some((lat, lon)) = metadata.get_lat_lon() {
+    if lat.abs() < config.null_island_radius && lon.abs() < config.null_island_radius {
+      problems.push(format!("GPS coordinates are near 0°, 0° ({lat}, {lon})."));
+    }
+
+    if config.time_zone
+      && let Some((date_time, Some(offset))) = date_time_original
+    {
+      let time_zone = prim::get_time_zone_name(lat, lon);
+
+      // Where clocks were set back, either offset is valid.
+      match prim::get_offsets_for_time_zone(&date_time, time_zone) {
+        Ok(offsets_expected) if !offsets_expected.contains(&offset) => {
+          let offsets_expected = offsets_expected
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" or ");
+          problems.push(format!(
+            "`DateTimeOriginal` offset ({offset}) does not match time zone at GPS location \
+             ({time_zone}, {offsets_expected})."
+          ));
+        }
+        Ok(_) => {}
+        Err(err) => log::debug!("{metadata}: Not checking time zone ({err})."),
+      }
+    }
+  }
+
+  problems
 }
 
-/// Validates camera tags in `metadata`.
-fn validate_camera(metadata: &Metadata) -> bool {
-  let mut valid = true;
+/// Validates attribution tags in `metadata`, returning any problems found.
+/// Rights tags (e.g. `Copyright`) must match the `copyright` policy.
+fn validate_attribution(metadata: &Metadata, copyright: &CopyrightConfig) -> Vec<String> {
+  let mut problems = Vec::new();
+
+  if metadata.creator.is_none() {
+    problems.push("Missing `Creator` tag.".to_string());
+  }
+
+  if metadata.copyright.is_none() {
+    problems.push("Missing `Copyright` tag.".to_string());
+  }
+
+  let expected = copyright.get_enforced(metadata);
+
+  if let Some(actual) = &metadata.copyright
+    && !expected.iter().any(|(tag, _)| *tag == "Copyright")
+    && let Some((_, value)) = copyright
+      .get_expected(metadata)
+      .into_iter()
+      .find(|(tag, _)| *tag == "Copyright")
+    && *actual != value
+  {
+    log::debug!("{metadata}: Unexpected `Copyright` format (\"{actual}\").");
+  }
+
+  for (tag, value) in prim::find_rights_mismatches(metadata, &expected) {
+    if metadata.get(tag).is_some() {
+      problems.push(format!("Unexpected `{tag}` (expected \"{value}\")."));
+    } else if *tag != "Copyright" {
+      problems.push(format!("Missing `{tag}` tag (expected \"{value}\")."));
+    }
+  }
+
+  problems
+}
+
+/// Validates camera tags in `metadata`, returning any problems found.
+fn validate_camera(metadata: &Metadata) -> Vec<String> {
+  let mut problems = Vec::new();
 
   if metadata.make.is_none() {
-    log::warn!("{metadata}: Missing `Make` tag.");
-    valid = false;
+    problems.push("Missing `Make` tag.".to_string());
   }
 
   if metadata.model.is_none() {
-    log::warn!("{metadata}: Missing `Model` tag.");
-    valid = false;
+    problems.push("Missing `Model` tag.".to_string());
   }
 
-  valid
+  problems
 }
 
-/// Validates date and time tags in `metadata`.
+/// Validates date and time tags in `metadata`, returning any problems found.
 /// This checks that all expected tags are set, as well as their time zones.
-fn validate_date_time(metadata: &Metadata) -> bool {
+fn validate_date_time(metadata: &Metadata) -> Vec<String> {
+  let mut problems = Vec::new();
+
   let date_time_original = metadata
     .date_time_original
     .as_deref()
-    .ok_or_else(|| {
-      log::warn!("{metadata}: Missing `DateTimeOriginal` tag.");
-    })
+    .ok_or("Missing `DateTimeOriginal` tag.".to_string())
     .and_then(|_| {
-      metadata.get_date_time_original().ok_or_else(|| {
-        log::warn!("{metadata}: Unable to parse `DateTimeOriginal` tag.");
-      })
+      metadata
+        .get_date_time_original()
+        .ok_or("Unable to parse `DateTimeOriginal` tag.".to_string())
     })
     .and_then(|(d, t)| {
       t.and_then(|t| d.and_local_timezone(t).single())
-        .ok_or_else(|| {
-          log::warn!("{metadata}: `DateTimeOriginal` tag is missing time zone.");
-        })
-    });
+        .ok_or("`DateTimeOriginal` tag is missing time zone.".to_string())
+    })
+    .map_err(|e| problems.push(e));
 
   let create_date = metadata
     .sub_sec_create_date
     .as_deref()
     .or(metadata.create_date.as_deref())
-    .ok_or_else(|| {
-      log::warn!("{metadata}: Missing `CreateDate` tag.");
-    })
+    .ok_or("Missing `CreateDate` tag.".to_string())
     .and_then(|d| {
-      prim::parse_date_time(d).map_err(|e| {
-        log::warn!("{metadata}: Unable to parse `CreateDate` tag ({e}).");
-      })
+      prim::parse_date_time(d).map_err(|e| format!("Unable to parse `CreateDate` tag ({e})."))
     })
     .and_then(|(d, t)| {
       t.and_then(|t| d.and_local_timezone(t).single())
-        .ok_or_else(|| {
-          log::warn!("{metadata}: `CreateDate` tag is missing time zone.");
-        })
-    });
-
-  let Ok(date_time_original) = date_time_original else {
-    return false;
-  };
-
-  let Ok(create_date) = create_date else {
-    return false;
-  };
-
-  if create_date < date_time_original {
-    log::warn!(
-      "{metadata}: `CreateDate` is before `DateTimeOriginal` ({create_date} < \
-       {date_time_original})."
-    );
-    return false;
+        .ok_or("`CreateDate` tag is missing time zone.".to_string())
+    })
+    .map_err(|e| problems.push(e));
+
+  if let (Ok(date_time_original), Ok(create_date)) = (date_time_original, create_date)
+    && create_date < date_time_original
+  {
+    problems.push(format!(
+      "`CreateDate` is before `DateTimeOriginal` ({create_date} < {date_time_original})."
+    ));
   }
 
-  true
+  problems
 }
 
-/// Validates GPS and location tags in `metadata` are set.
-fn validate_location(metadata: &Metadata) -> bool {
-  let mut valid = true;
-
-  if metadata.gps_position.is_none() {
-    log::warn!("{metadata}: Missing `GPSPosition` tag.");
-    valid = false;
-  }
-  if metadata.city.is_none() {
-    log::warn!("{metadata}: Missing `City` tag.");
-    valid = false;
-  }
-  if metadata.state.is_none() {
-    log::warn!("{metadata}: Missing `State` tag.");
-    valid = false;
-  }
-  if metadata.country.is_none() {
-    log::warn!("{metadata}: Missing `Country` tag.");
-    valid = false;
-  }
-
-  valid
+/// Validates GPS and location tags in `metadata` are set, returning any
+/// problems found.
+fn validate_location(metadata: &Metadata) -> Vec<String> {
+  [
+    ("GPSPosition", metadata.gps_position.is_none()),
+    ("City", metadata.city.is_none()),
+    ("State", metadata.state.is_none()),
+    ("Country", metadata.country.is_none()),
+  ]
+  .into_iter()
+  .filter(|(_, missing)| *missing)
+  .map(|(tag, _)| format!("Missing `{tag}` tag."))
+  .collect()
 }
 
 #[cfg(test)]
@@ -259,12 +693,19 @@ mod test_validation {
     let sidecars = FileMap::new();
 
     let config = ValidationConfig {
-      attribution: true,
-      camera:      true,
-      date_time:   true,
-      location:    true,
+      attribution:  true,
+      camera:       true,
+      date_time:    true,
+      location:     true,
+      rules:        Vec::new(),
+      file_name:    None,
+      plausibility: None,
+      integrity:    false,
     };
-    let valid_handles: Vec<_> = validate(&media, &sidecars, &config).collect();
+    let valid_handles: Vec<_> = validate(&media, &sidecars, &config, &CopyrightConfig::default())
+      .filter(|(_, findings)| findings.is_empty())
+      .map(|(handle, _)| handle)
+      .collect();
 
     assert_eq!(valid_handles, vec![handle_media]);
   }
@@ -318,17 +759,388 @@ mod test_validation {
       .set_media_handle(handle_media);
 
     let config = ValidationConfig {
-      attribution: true,
-      camera:      true,
-      date_time:   true,
-      location:    true,
+      attribution:  true,
+      camera:       true,
+      date_time:    true,
+      location:     true,
+      rules:        Vec::new(),
+      file_name:    None,
+      plausibility: None,
+      integrity:    false,
     };
-    let valid_handles: Vec<_> = validate(&media, &sidecars, &config).collect();
+    let valid_handles: Vec<_> = validate(&media, &sidecars, &config, &CopyrightConfig::default())
+      .filter(|(_, findings)| findings.is_empty())
+      .map(|(handle, _)| handle)
+      .collect();
 
     assert_eq!(valid_handles, vec![handle_media]);
   }
 }
 
+#[cfg(test)]
+mod test_validate_rules {
+  use serde_json::json;
+
+  use super::*;
+  use crate::{io, testing::*};
+
+  #[test]
+  fn reports_custom_rule_severity() {
+    let d = test_dir!(
+      "image.jpg": {
+        "Copyright": "Copyright Seth",
+      },
+    );
+
+    let mut media = FileMap::new();
+    media.insert(
+      "image.jpg",
+      Media::new(io::read_metadata(d.get_path("image.jpg")).unwrap()).unwrap(),
+    );
+
+    let sidecars = FileMap::new();
+
+    let config = ValidationConfig {
+      rules: vec![
+        serde_json::from_value(json!({
+          "name": "copyright_year",
+          "severity": "warning",
+          "type": "matches",
+          "tag": "Copyright",
+          "pattern": r"^Copyright \d{4} .+",
+        }))
+        .unwrap(),
+      ],
+      ..Default::default()
+    };
+    let findings: Vec<_> = validate(&media, &sidecars, &config, &CopyrightConfig::default())
+      .flat_map(|(_, findings)| findings)
+      .collect();
+
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "copyright_year");
+    assert_eq!(findings[0].severity, Severity::Warning);
+  }
+}
+
+#[cfg(test)]
+mod test_parse_validation {
+  use std::path::PathBuf;
+
+  use super::*;
+
+  fn output(validate: &str, warning: Option<&str>, error: Option<&str>) -> io::ValidationOutput {
+    io::ValidationOutput {
+      source_file: PathBuf::from("image.jpg"),
+      validate:    Some(validate.to_string()),
+      warning:     warning.map(str::to_string),
+      error:       error.map(str::to_string),
+    }
+  }
+
+  #[test]
+  fn accepts_ok() {
+    let mut integrity = Integrity::default();
+
+    parse_validation(&output("OK", None, None), &mut integrity);
+
+    assert_eq!(integrity, Integrity::default());
+  }
+
+  #[test]
+  fn reports_warnings() {
+    let mut integrity = Integrity::default();
+
+    parse_validation(
+      &output("1 Warning", Some("Non-standard format"), None),
+      &mut integrity,
+    );
+
+    assert!(integrity.errors.is_empty());
+    assert_eq!(integrity.warnings.len(), 1);
+  }
+
+  #[test]
+  fn fails_errors() {
+    let mut integrity = Integrity::default();
+
+    parse_validation(
+      &output("1 Error", None, Some("Truncated mdat atom")),
+      &mut integrity,
+    );
+
+    assert!(!integrity.errors.is_empty());
+    assert_eq!(integrity.errors.len(), 1);
+  }
+}
+
+#[cfg(test)]
+mod test_validate_file_name {
+  use super::*;
+  use crate::{prim::EDITED_SUFFIX, testing::*};
+
+  #[test]
+  fn accepts_file_in_place() {
+    // Named in local time, as by `io::move_file` (UTC in tests).
+    let media = metadata!(
+      "FileTypeExtension": "jpg",
+    );
+    let metadata = metadata!(
+      "DateTimeOriginal": "2000-01-01T12:30:01.050-08:00",
+    );
+
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/2000/01/000101_203001050.jpg"),
+        &media,
+        &metadata,
+        "",
+        None
+      )
+      .is_none()
+    );
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/2000/01/000101_203001050_b.jpg"),
+        &media,
+        &metadata,
+        "",
+        None
+      )
+      .is_none()
+    );
+  }
+
+  #[test]
+  fn accepts_edit_in_place() {
+    let media = metadata!(
+      "FileTypeExtension": "heic",
+    );
+    let metadata = metadata!(
+      "DateTimeOriginal": "2000-01-01T00:00:00",
+    );
+
+    for path in [
+      "/catalog/2000/01/000101_000000000_E.heic",
+      "/catalog/2000/01/000101_000000000_b_E.heic",
+    ] {
+      assert!(
+        validate_file_name(
+          Path::new("/catalog"),
+          Path::new(path),
+          &media,
+          &metadata,
+          EDITED_SUFFIX,
+          None
+        )
+        .is_none()
+      );
+    }
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/2000/01/000101_000000000.heic"),
+        &media,
+        &metadata,
+        EDITED_SUFFIX,
+        None
+      )
+      .is_some()
+    );
+  }
+
+  #[test]
+  fn accepts_burst_in_place() {
+    let media = metadata!(
+      "FileTypeExtension": "jpg",
+    );
+    let metadata = metadata!(
+      "DateTimeOriginal": "2000-01-01T00:00:00.500",
+    );
+    let burst = Some((BurstLayout::Subfolder, "000101_000000000"));
+
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/2000/01/000101_000000000/000101_000000500.jpg"),
+        &media,
+        &metadata,
+        "",
+        burst
+      )
+      .is_none()
+    );
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/2000/01/000101_000000500.jpg"),
+        &media,
+        &metadata,
+        "",
+        burst
+      )
+      .is_some()
+    );
+  }
+
+  #[test]
+  fn finds_drifted_file() {
+    let media = metadata!(
+      "FileTypeExtension": "jpg",
+    );
+    let metadata = metadata!(
+      "DateTimeOriginal": "2000-01-02T00:00:00",
+    );
+
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/2000/01/000101_000000000.jpg"),
+        &media,
+        &metadata,
+        "",
+        None
+      )
+      .unwrap()
+      .contains("2000/01/000102_000000000.jpg")
+    );
+  }
+
+  #[test]
+  fn skips_file_without_date() {
+    let media = metadata!(
+      "FileTypeExtension": "jpg",
+    );
+
+    assert!(
+      validate_file_name(
+        Path::new("/catalog"),
+        Path::new("/catalog/image.jpg"),
+        &media,
+        &media,
+        "",
+        None
+      )
+      .is_none()
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_validate_plausibility {
+  use super::*;
+  use crate::testing::*;
+
+  fn now() -> NaiveDateTime {
+    make_date_naive(2025, 6, 1, 0, 0, 0, 0)
+  }
+
+  fn config() -> PlausibilityConfig {
+    PlausibilityConfig {
+      time_zone: false,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn accepts_plausible_metadata() {
+    let metadata = metadata!(
+      "DateTimeOriginal": "2020-06-01T12:00:00",
+      "GPSPosition": "47 deg 36' 21.96\" N, 122 deg 19' 58.08\" W",
+    );
+
+    assert!(validate_plausibility(&metadata, &config(), now()).is_empty());
+  }
+
+  #[test]
+  fn finds_reset_date() {
+    let metadata = metadata!(
+      "DateTimeOriginal": "2000-01-01T00:00:05",
+    );
+
+    let problems = validate_plausibility(&metadata, &config(), now());
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("reset date"));
+  }
+
+  #[test]
+  fn finds_date_before_minimum() {
+    let metadata = metadata!(
+      "DateTimeOriginal": "1990-01-01T00:00:00",
+    );
+    let config = PlausibilityConfig {
+      min_date: NaiveDate::from_ymd_opt(1995, 1, 1),
+      ..config()
+    };
+
+    assert_eq!(validate_plausibility(&metadata, &config, now()).len(), 1);
+  }
+
+  #[test]
+  fn finds_future_date() {
+    let metadata = metadata!(
+      "DateTimeOriginal": "2025-06-03T00:00:00",
+    );
+
+    let problems = validate_plausibility(&metadata, &config(), now());
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("future"));
+  }
+
+  #[test]
+  fn finds_null_island() {
+    let metadata = metadata!(
+      "GPSPosition": "0 deg 0' 0.00\" N, 0 deg 0' 0.00\" E",
+    );
+
+    let problems = validate_plausibility(&metadata, &config(), now());
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("0°, 0°"));
+  }
+
+  #[test]
+  fn finds_offset_not_matching_location() {
+    let metadata = metadata!(
+      "SubSecDateTimeOriginal": "2020-06-01T12:00:00.000+00:00",
+      "GPSPosition": "47 deg 36' 21.96\" N, 122 deg 19' 58.08\" W",
+    );
+    let config = PlausibilityConfig {
+      time_zone: true,
+      ..config()
+    };
+
+    let problems = validate_plausibility(&metadata, &config, now());
+
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("America/Los_Angeles"));
+  }
+
+  #[test]
+  fn accepts_either_offset_when_clocks_set_back() {
+    let config = PlausibilityConfig {
+      time_zone: true,
+      ..config()
+    };
+
+    for date_time in [
+      "2024-11-03T01:30:00.000-07:00",
+      "2024-11-03T01:30:00.000-08:00",
+    ] {
+      let mut metadata = metadata!(
+        "GPSPosition": "47 deg 36' 21.96\" N, 122 deg 19' 58.08\" W",
+      );
+      metadata.sub_sec_date_time_original = Some(date_time.to_string());
+
+      assert!(validate_plausibility(&metadata, &config, now()).is_empty());
+    }
+  }
+}
+
 #[cfg(test)]
 mod test_validate_attribution {
   use super::*;
@@ -340,7 +1152,7 @@ mod test_validate_attribution {
       "Creator": "Creator",
     );
 
-    assert!(!validate_attribution(&metadata));
+    assert!(!validate_attribution(&metadata, &CopyrightConfig::default()).is_empty());
   }
 
   #[test]
@@ -349,7 +1161,38 @@ mod test_validate_attribution {
       "Copyright": "Copyright",
     );
 
-    assert!(!validate_attribution(&metadata));
+    assert!(!validate_attribution(&metadata, &CopyrightConfig::default()).is_empty());
+  }
+
+  #[test]
+  fn is_invalid_if_copyright_does_not_match_policy() {
+    let metadata = metadata!(
+      "Creator": "Creator",
+      "Copyright": "Copyright",
+    );
+    let copyright = CopyrightConfig {
+      terms: prim::CopyrightTerms {
+        template: Some("Copyright {creator}".to_string()),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+
+    let problems = validate_attribution(&metadata, &copyright);
+
+    assert_eq!(problems, [
+      "Unexpected `Copyright` (expected \"Copyright Creator\")."
+    ]);
+  }
+
+  #[test]
+  fn passes_custom_copyright_without_template() {
+    let metadata = metadata!(
+      "Creator": "Creator",
+      "Copyright": "© Creator",
+    );
+
+    assert!(validate_attribution(&metadata, &CopyrightConfig::default()).is_empty());
   }
 
   #[test]
@@ -359,7 +1202,7 @@ mod test_validate_attribution {
       "Copyright": "Copyright",
     );
 
-    assert!(validate_attribution(&metadata));
+    assert!(validate_attribution(&metadata, &CopyrightConfig::default()).is_empty());
   }
 }
 
@@ -374,7 +1217,7 @@ mod test_validate_camera {
       "Model": "Model",
     );
 
-    assert!(!validate_camera(&metadata));
+    assert!(!validate_camera(&metadata).is_empty());
   }
 
   #[test]
@@ -383,7 +1226,7 @@ mod test_validate_camera {
       "Make": "Make",
     );
 
-    assert!(!validate_camera(&metadata));
+    assert!(!validate_camera(&metadata).is_empty());
   }
 
   #[test]
@@ -393,7 +1236,7 @@ mod test_validate_camera {
       "Model": "Model",
     );
 
-    assert!(validate_camera(&metadata));
+    assert!(validate_camera(&metadata).is_empty());
   }
 }
 
@@ -409,7 +1252,7 @@ mod test_validate_date_time {
       "CreateDate": "2000-01-01T00:00:00+00:00",
     );
 
-    assert!(!validate_date_time(&metadata));
+    assert!(!validate_date_time(&metadata).is_empty());
   }
 
   #[test]
@@ -418,7 +1261,7 @@ mod test_validate_date_time {
       "DateTimeOriginal": "2000-01-01T00:00:00+00:00",
     );
 
-    assert!(!validate_date_time(&metadata));
+    assert!(!validate_date_time(&metadata).is_empty());
   }
 
   #[test]
@@ -427,7 +1270,7 @@ mod test_validate_date_time {
       "CreateDate": "2000-01-01T00:00:00+00:00",
     );
 
-    assert!(!validate_date_time(&metadata));
+    assert!(!validate_date_time(&metadata).is_empty());
   }
 
   #[test]
@@ -437,7 +1280,7 @@ mod test_validate_date_time {
       "CreateDate": "2000-01-01T00:00:00",
     );
 
-    assert!(!validate_date_time(&metadata));
+    assert!(!validate_date_time(&metadata).is_empty());
   }
 
   #[test]
@@ -447,7 +1290,7 @@ mod test_validate_date_time {
       "CreateDate": "2000-01-01T00:00:00+00:00",
     );
 
-    assert!(!validate_date_time(&metadata));
+    assert!(!validate_date_time(&metadata).is_empty());
   }
 
   #[test]
@@ -460,7 +1303,7 @@ mod test_validate_date_time {
       "OffsetTimeDigitized": "+00:00",
       "SubSecCreateDate": "2000-01-01T00:00:00+00:00",
     );
-    assert!(validate_date_time(&metadata));
+    assert!(validate_date_time(&metadata).is_empty());
   }
 
   #[test]
@@ -469,7 +1312,7 @@ mod test_validate_date_time {
       "DateTimeOriginal": "2000-01-01T00:00:00+00:00",
       "CreateDate": "2000-01-01T00:00:00+00:00",
     );
-    assert!(validate_date_time(&metadata));
+    assert!(validate_date_time(&metadata).is_empty());
   }
 }
 
@@ -486,7 +1329,7 @@ mod test_validate_location {
       "Country": "United States",
     );
 
-    assert!(!validate_location(&metadata));
+    assert!(!validate_location(&metadata).is_empty());
   }
 
   #[test]
@@ -499,7 +1342,7 @@ mod test_validate_location {
       "GPSPosition": "47 deg 36' 21.96\" N, 122 deg 19' 58.08\" W",
     );
 
-    assert!(!validate_location(&metadata));
+    assert!(!validate_location(&metadata).is_empty());
   }
 
   #[test]
@@ -515,7 +1358,7 @@ mod test_validate_location {
       "Country": "United States",
     );
 
-    assert!(validate_location(&metadata));
+    assert!(validate_location(&metadata).is_empty());
   }
 
   #[test]
@@ -529,6 +1372,6 @@ mod test_validate_location {
       "Country": "United States",
     );
 
-    assert!(validate_location(&metadata));
+    assert!(validate_location(&metadata).is_empty());
   }
 }
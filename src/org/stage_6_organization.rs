@@ -3,102 +3,643 @@
 //! Stage 6: Moving and renaming of files.
 
 use std::{
-  ffi::OsString,
+  collections::HashSet,
+  ffi::{OsStr, OsString},
+  iter,
+  mem,
   path::{Path, PathBuf},
 };
 
-use super::Organizer;
+use super::{Organizer, Report, catalog_links, report::Action};
 use crate::{
   io,
   org,
-  prim::{FileMap, Handle, Media, SidecarDupe, SidecarInitial},
+  prim::{
+    self,
+    FileMap,
+    Handle,
+    LivePhotoLinker,
+    Media,
+    Metadata,
+    Sidecar,
+    SidecarDupe,
+    SidecarInitial,
+  },
 };
 
+/// Mirrors the date and time part of `io::DATETIME_WRITE_FORMAT`, without
+/// `ExifTool`'s copy number.
+const EXPECTED_STEM_FORMAT: &str = "%Y/%m/%y%m%d_%H%M%S%3f";
+
 impl Organizer {
   /// Moves loaded files to `dst`, organizing them into subdirectories and
   /// renaming them based on their timestamps.
-  /// Unless `force` is true, this will only touch validated files.
-  pub fn move_and_rename_files(mut self, dst: impl AsRef<Path>, force: bool) -> Result<(), String> {
-    if dst.as_ref().is_relative() {
-      return Err(format!(
-        "{}: Destination path is not absolute.",
-        dst.as_ref().display()
-      ));
-    }
-
-    if !dst.as_ref().exists() {
-      return Err(format!(
-        "{}: Destination path does not exist.",
-        dst.as_ref().display()
-      ));
-    }
+  /// Unless `force` is true, this will only touch validated files. If file
+  /// name validation ran, this will only touch drifted files (and the rest of
+  /// their Live Photo), regardless of `force`.
+  /// Returns the report of everything done during this run.
+  pub fn move_and_rename_files(
+    mut self,
+    dst: impl AsRef<Path>,
+    force: bool,
+  ) -> Result<Report, String> {
+    check_destination(&dst)?;
 
     if !self.validation.enabled() && !force {
       log::warn!("Skipping move and rename: Validation disabled.");
-      return Ok(());
+      return Ok(self.report);
     }
 
     log::info!("Moving and renaming Live Photos.");
 
-    for mut link in self.live_photo_map.into_values() {
+    self.move_catalog_linked_videos(&dst, force)?;
+    self.move_catalog_linked_images(&dst, force)?;
+
+    for link in mem::take(&mut self.live_photo_map).into_values() {
       if link.is_leftover_videos() {
         continue;
       }
 
-      let handle_main = link.get_image_best();
+      self.move_live_photo(link, &dst, force)?;
+    }
 
-      let image_main = take_media(handle_main, &mut self.media);
-      let sidecar_main = take_sidecar(&image_main, &mut self.sidecars);
-      let dupes_main = take_dupes(&image_main, &mut self.dupes);
-      let metadata_source = pick_source(&image_main, sidecar_main.as_ref());
+    log::info!("Moving and renaming all other media files.");
 
-      let should_move = force || self.valid_media.contains(&handle_main);
+    let handles = self
+      .media
+      .iter_data_indexed()
+      .map(|(h, _)| h)
+      .collect::<Vec<_>>();
+
+    for handle in handles {
+      // Edits are moved alongside their original, unless it was removed.
+      if self
+        .edit_originals
+        .get(&handle)
+        .is_some_and(|&h| self.media.get_entry_mut(h).is_some())
+      {
+        continue;
+      }
 
-      for handle in link.drain() {
-        if handle == handle_main {
-          continue;
-        }
+      let Some(media) = self.media.get_entry_mut(handle).take() else {
+        continue;
+      };
+      let sidecar = take_sidecar(&media, &mut self.sidecars);
+      let dupes = take_dupes(&media, &mut self.dupes);
+      let metadata_source = pick_source(&media, sidecar.as_ref());
 
-        let media = take_media(handle, &mut self.media);
-        let sidecar = take_sidecar(&media, &mut self.sidecars);
-        let dupes = take_dupes(&media, &mut self.dupes);
+      let should_move = should_move(
+        [handle],
+        handle,
+        force,
+        &self.valid_media,
+        self.drifted_media.as_ref(),
+      );
+      let reason = skip_reason(
+        [handle].into_iter(),
+        self.drifted_media.as_ref(),
+        &self.declined_media,
+      );
 
-        if should_move {
-          move_media_with_deps(&self.source, &dst, &metadata_source, media, sidecar, dupes)?;
+      if should_move {
+        let to = move_media_with_deps(
+          &self.source,
+          &dst,
+          &self.get_name_format(handle),
+          &metadata_source,
+          media,
+          sidecar,
+          dupes,
+          &mut self.report,
+        )?;
+        self.move_associates(handle, Some(&to), reason)?;
+      } else {
+        if self.drifted_media.is_none() {
+          log::warn!("{media}: Not moving or renaming. File did not pass validation.");
         }
+        skip_media_with_deps(&media, sidecar.as_ref(), &dupes, reason, &mut self.report);
+        self.move_associates(handle, None, reason)?;
       }
+    }
+
+    Ok(self.report)
+  }
+
+  /// Moves all components of a Live Photo (and their associates), named for
+  /// its best image.
+  fn move_live_photo(
+    &mut self,
+    mut link: LivePhotoLinker,
+    dst: impl AsRef<Path>,
+    force: bool,
+  ) -> Result<(), String> {
+    let handle_main = link.get_image_best();
+
+    let image_main = take_media(handle_main, &mut self.media);
+    let sidecar_main = take_sidecar(&image_main, &mut self.sidecars);
+    let dupes_main = take_dupes(&image_main, &mut self.dupes);
+    let metadata_source = pick_source(&image_main, sidecar_main.as_ref());
+
+    let should_move = should_move(
+      link.iter_images().chain(link.iter_videos()),
+      handle_main,
+      force,
+      &self.valid_media,
+      self.drifted_media.as_ref(),
+    );
+    let reason = skip_reason(
+      link.iter_images().chain(link.iter_videos()),
+      self.drifted_media.as_ref(),
+      &self.declined_media,
+    );
+    let format = self.get_name_format(handle_main);
+
+    for handle in link.drain() {
+      if handle == handle_main {
+        continue;
+      }
+
+      let media = take_media(handle, &mut self.media);
+      let sidecar = take_sidecar(&media, &mut self.sidecars);
+      let dupes = take_dupes(&media, &mut self.dupes);
 
       if should_move {
-        move_media_with_deps(
+        let to = move_media_with_deps(
           &self.source,
           &dst,
+          &format,
           &metadata_source,
-          image_main,
-          sidecar_main,
-          dupes_main,
+          media,
+          sidecar,
+          dupes,
+          &mut self.report,
         )?;
+        self.move_associates(handle, Some(&to), reason)?;
       } else {
+        skip_media_with_deps(&media, sidecar.as_ref(), &dupes, reason, &mut self.report);
+        self.move_associates(handle, None, reason)?;
+      }
+    }
+
+    if should_move {
+      let to = move_media_with_deps(
+        &self.source,
+        &dst,
+        &format,
+        &metadata_source,
+        image_main,
+        sidecar_main,
+        dupes_main,
+        &mut self.report,
+      )?;
+      self.move_associates(handle_main, Some(&to), reason)
+    } else {
+      if self.drifted_media.is_none() {
         log::warn!("{image_main}: Not moving or renaming. File did not pass validation.");
       }
+      skip_media_with_deps(
+        &image_main,
+        sidecar_main.as_ref(),
+        &dupes_main,
+        reason,
+        &mut self.report,
+      );
+      self.move_associates(handle_main, None, reason)
     }
+  }
 
-    log::info!("Moving and renaming all other media files.");
+  /// Moves the companion files and Apple Photos edits of the media at
+  /// `handle` next to it, now at `to`, or leaves them in place because of
+  /// `reason` if it was not moved.
+  fn move_associates(
+    &mut self,
+    handle: Handle<Media>,
+    to: Option<&Path>,
+    reason: &str,
+  ) -> Result<(), String> {
+    for path in self.video_companions.remove(&handle).unwrap_or_default() {
+      let Some(to) = to else {
+        self.report.action(&path, Action::NotMoved {
+          reason: reason.to_string(),
+        });
+        continue;
+      };
 
-    for (handle, entry) in self.media.iter_entries_mut_indexed() {
-      let media = entry.take().unwrap();
+      let ext = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_lowercase();
+
+      let moved = io::rename_file(&path, path_next_to(to, "", OsStr::new(&format!(".{ext}"))))?;
+      self.report.action(&path, Action::Moved { to: moved });
+    }
+
+    let Some(mut edit_group) = self.edit_groups.remove(&handle) else {
+      return Ok(());
+    };
+
+    for handle_edit in edit_group.drain_edits() {
+      let Some(media) = self.media.get_entry_mut(handle_edit).take() else {
+        continue;
+      };
       let sidecar = take_sidecar(&media, &mut self.sidecars);
       let dupes = take_dupes(&media, &mut self.dupes);
-      let metadata_source = pick_source(&media, sidecar.as_ref());
 
-      if force || self.valid_media.contains(&handle) {
-        move_media_with_deps(&self.source, &dst, &metadata_source, media, sidecar, dupes)?;
+      match to {
+        Some(to) => {
+          move_media_next_to(
+            &self.source,
+            to,
+            prim::EDITED_SUFFIX,
+            media,
+            sidecar,
+            dupes,
+            &mut self.report,
+          )?;
+        }
+        None => skip_media_with_deps(&media, sidecar.as_ref(), &dupes, reason, &mut self.report),
+      }
+    }
+
+    for path in edit_group.drain_adjustments() {
+      let Some(to) = to else {
+        self.report.action(&path, Action::NotMoved {
+          reason: reason.to_string(),
+        });
+        continue;
+      };
+
+      let stem = path.file_stem().and_then(OsStr::to_str).unwrap_or_default();
+      let (_, suffix) = prim::get_adjustments_original_stem(stem);
+
+      let moved = io::rename_file(&path, path_next_to(to, suffix, OsStr::new(".aae")))?;
+      self.report.action(&path, Action::Moved { to: moved });
+    }
+
+    Ok(())
+  }
+
+  /// Gets the `ExifTool` date format to name the media at `handle` by, laying
+  /// it out per its burst, if any.
+  fn get_name_format(&self, handle: Handle<Media>) -> String {
+    match self.bursts.get(&handle) {
+      Some(burst) => self
+        .burst_layout
+        .apply(io::DATETIME_WRITE_FORMAT, &burst.name),
+      None => io::DATETIME_WRITE_FORMAT.to_string(),
+    }
+  }
+
+  /// Moves Live Photo videos linked to an image in the catalog (see
+  /// `link_catalog`) next to that image, named to match it. Any duplicate
+  /// videos are moved to `dst`, named from the image with a copy number.
+  fn move_catalog_linked_videos(
+    &mut self,
+    dst: impl AsRef<Path>,
+    force: bool,
+  ) -> Result<(), String> {
+    for (id, image) in mem::take(&mut self.catalog_images) {
+      let Some(mut link) = self.live_photo_map.remove(&id) else {
+        continue;
+      };
+      let handle_main = link.get_video_best();
+      let ext = &self.media[handle_main].get_metadata().file_type_extension;
+
+      let skip = if !should_move(
+        link.iter_videos(),
+        handle_main,
+        force,
+        &self.valid_media,
+        self.drifted_media.as_ref(),
+      ) {
+        Some(skip_reason(
+          link.iter_videos(),
+          self.drifted_media.as_ref(),
+          &self.declined_media,
+        ))
+      } else if path_next_to(&image, "", OsStr::new(&format!(".{ext}"))).exists() {
+        log::debug!(
+          "{}: Not moving, as its image already has a video in the catalog.",
+          self.media[handle_main]
+        );
+        Some("Duplicate of a Live Photo video already in the catalog.")
       } else {
-        log::warn!("{media}: Not moving or renaming. File did not pass validation.");
+        None
+      };
+
+      // The best video is moved first, so it takes the image's name.
+      let metadata_source = catalog_links::get_catalog_metadata_source(&image);
+      let handles = iter::once(handle_main)
+        .chain(link.drain().filter(|&h| h != handle_main))
+        .collect::<Vec<_>>();
+
+      for handle in handles {
+        let video = take_media(handle, &mut self.media);
+        let sidecar = take_sidecar(&video, &mut self.sidecars);
+        let dupes = take_dupes(&video, &mut self.dupes);
+
+        let to = match skip {
+          Some(reason) => {
+            skip_media_with_deps(&video, sidecar.as_ref(), &dupes, reason, &mut self.report);
+            None
+          }
+          None if handle == handle_main => Some(move_media_next_to(
+            &self.source,
+            &image,
+            "",
+            video,
+            sidecar,
+            dupes,
+            &mut self.report,
+          )?),
+          None => Some(move_media_with_deps(
+            &self.source,
+            &dst,
+            io::DATETIME_WRITE_FORMAT,
+            &metadata_source,
+            video,
+            sidecar,
+            dupes,
+            &mut self.report,
+          )?),
+        };
+        self.move_associates(handle, to.as_deref(), skip.unwrap_or_default())?;
       }
     }
 
     Ok(())
   }
+
+  /// Moves Live Photo images linked to a video in the catalog (see
+  /// `link_catalog`) next to that video, named to match it. Any duplicate
+  /// images are moved to `dst`, named from the best image with a copy number.
+  fn move_catalog_linked_images(
+    &mut self,
+    dst: impl AsRef<Path>,
+    force: bool,
+  ) -> Result<(), String> {
+    for (id, video) in mem::take(&mut self.catalog_videos) {
+      let Some(mut link) = self.live_photo_map.remove(&id) else {
+        continue;
+      };
+      let handle_main = link.get_image_best();
+      let ext = &self.media[handle_main].get_metadata().file_type_extension;
+
+      let skip = if path_next_to(&video, "", OsStr::new(&format!(".{ext}"))).exists() {
+        log::debug!(
+          "{}: Not moving, as its video already has an image in the catalog.",
+          self.media[handle_main]
+        );
+        Some("Duplicate of a Live Photo image already in the catalog.")
+      } else if !should_move(
+        link.iter_images(),
+        handle_main,
+        force,
+        &self.valid_media,
+        self.drifted_media.as_ref(),
+      ) {
+        Some(skip_reason(
+          link.iter_images(),
+          self.drifted_media.as_ref(),
+          &self.declined_media,
+        ))
+      } else {
+        None
+      };
+
+      // The best image is moved first, so it takes the video's name, and the
+      // rest are named from it.
+      let mut to_main = None;
+      let handles = iter::once(handle_main)
+        .chain(link.drain().filter(|&h| h != handle_main))
+        .collect::<Vec<_>>();
+
+      for handle in handles {
+        let image = take_media(handle, &mut self.media);
+        let sidecar = take_sidecar(&image, &mut self.sidecars);
+        let dupes = take_dupes(&image, &mut self.dupes);
+
+        let to = match (skip, &to_main) {
+          (Some(reason), _) => {
+            skip_media_with_deps(&image, sidecar.as_ref(), &dupes, reason, &mut self.report);
+            None
+          }
+          (None, None) => Some(move_media_next_to(
+            &self.source,
+            &video,
+            "",
+            image,
+            sidecar,
+            dupes,
+            &mut self.report,
+          )?),
+          (None, Some(to_main)) => Some(move_media_with_deps(
+            &self.source,
+            &dst,
+            io::DATETIME_WRITE_FORMAT,
+            catalog_links::get_catalog_metadata_source(to_main),
+            image,
+            sidecar,
+            dupes,
+            &mut self.report,
+          )?),
+        };
+        if handle == handle_main {
+          to_main.clone_from(&to);
+        }
+        self.move_associates(handle, to.as_deref(), skip.unwrap_or_default())?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Moves `media` and its sidecars next to `image` (e.g. a Live Photo video to
+/// its image already in the catalog), named to match it plus `suffix`.
+/// Returns the new path to `media`.
+fn move_media_next_to(
+  dir_src: impl AsRef<Path>,
+  image: impl AsRef<Path>,
+  suffix: &str,
+  media: Media,
+  sidecar: Option<SidecarInitial>,
+  dupes: impl IntoIterator<Item = SidecarDupe>,
+  report: &mut Report,
+) -> Result<PathBuf, String> {
+  log::trace!("{media}: Moving next to {}.", image.as_ref().display());
+
+  let media_file_ext = media.get_metadata().file_type_extension.clone();
+
+  let to = io::rename_file(
+    org::to_abs_path(&dir_src, &media),
+    path_next_to(&image, suffix, OsStr::new(&format!(".{media_file_ext}"))),
+  )?;
+  report.action(media, Action::Moved { to: to.clone() });
+
+  move_sidecars_next_to(dir_src, &to, &media_file_ext, sidecar, dupes, report)?;
+
+  Ok(to)
+}
+
+/// Moves `sidecar` and `dupes` next to their media file, already moved to
+/// `media_path`, so they are named to match it exactly (including any copy
+/// number `ExifTool` added).
+fn move_sidecars_next_to(
+  dir_src: impl AsRef<Path>,
+  media_path: impl AsRef<Path>,
+  media_file_ext: &str,
+  sidecar: Option<SidecarInitial>,
+  dupes: impl IntoIterator<Item = SidecarDupe>,
+  report: &mut Report,
+) -> Result<(), String> {
+  let with_ending = |ending: &OsStr| path_next_to(&media_path, "", ending);
+
+  for dupe in dupes {
+    let to = io::rename_file(
+      org::to_abs_path(&dir_src, &dupe),
+      with_ending(&get_dupe_ending(&dupe, media_file_ext)),
+    )?;
+    report.action(dupe, Action::Moved { to });
+  }
+
+  if let Some(sidecar) = sidecar {
+    let to = io::rename_file(
+      org::to_abs_path(&dir_src, &sidecar),
+      with_ending(OsStr::new(&sidecar.get_naming().get_ending(media_file_ext))),
+    )?;
+    report.action(sidecar, Action::Moved { to });
+  }
+
+  Ok(())
+}
+
+/// Gets the ending of `dupe` for a media file with extension `media_file_ext`
+/// (e.g. `_01.jpg.xmp`).
+fn get_dupe_ending(dupe: &SidecarDupe, media_file_ext: &str) -> OsString {
+  let mut ending = OsString::from("_");
+  ending.push(dupe.get_dupe_number());
+  ending.push(format!(".{media_file_ext}.xmp"));
+  ending
+}
+
+/// Gets a path for the media file just moved to `media`, at which none of
+/// `endings` (of its sidecars) are taken. `ExifTool` only checks the media
+/// file's own name, so e.g. a stem-named sidecar (`.xmp`) can collide with
+/// that of another media file with a different extension. Collisions are
+/// resolved by advancing the copy number (`_b`, `_c`, ...) as `ExifTool` would.
+fn pick_free_media_path(media: &Path, endings: &[OsString]) -> Result<PathBuf, String> {
+  let stem = media.with_extension("").display().to_string();
+  let ending = OsString::from(format!(
+    ".{}",
+    media.extension().unwrap_or_default().display()
+  ));
+
+  let (base, copy) = match stem.rsplit_once('_') {
+    Some((base, copy))
+      if base.ends_with(|c: char| c.is_ascii_digit())
+        && copy.len() == 1
+        && copy.chars().all(|c| c.is_ascii_lowercase()) =>
+    {
+      (base, copy.chars().next().unwrap())
+    }
+    _ => (stem.as_str(), 'a'),
+  };
+
+  for copy in copy..='z' {
+    let candidate = match copy {
+      'a' => base.to_string(),
+      c => format!("{base}_{c}"),
+    };
+    let path = path_next_to(&candidate, "", &ending);
+
+    if (path == media || !path.exists())
+      && endings
+        .iter()
+        .all(|e| !path_next_to(&candidate, "", e).exists())
+    {
+      return Ok(path);
+    }
+  }
+
+  Err(format!(
+    "{}: No free name for file and its sidecars.",
+    media.display()
+  ))
+}
+
+/// Checks that `dst` is an absolute path to an existing directory.
+fn check_destination(dst: impl AsRef<Path>) -> Result<(), String> {
+  if dst.as_ref().is_relative() {
+    return Err(format!(
+      "{}: Destination path is not absolute.",
+      dst.as_ref().display()
+    ));
+  }
+
+  if !dst.as_ref().exists() {
+    return Err(format!(
+      "{}: Destination path does not exist.",
+      dst.as_ref().display()
+    ));
+  }
+
+  Ok(())
+}
+
+/// Gets the expected path (relative to the catalog, without extension) for a
+/// file with `metadata`, as `io::move_file` would name it. Like `ExifTool`,
+/// this prefers `DateTimeOriginal` over `CreateDate`, and sub-second precision
+/// where available, converted to local time. Returns `None` if there is no
+/// usable date.
+pub fn get_expected_stem(metadata: &Metadata) -> Option<String> {
+  let (date_time, offset) = [
+    &metadata.sub_sec_date_time_original,
+    &metadata.date_time_original,
+    &metadata.sub_sec_create_date,
+    &metadata.create_date,
+  ]
+  .into_iter()
+  .find_map(|d| prim::parse_date_time(d.as_deref()?).ok())?;
+
+  Some(
+    prim::to_local_time(&date_time, offset)
+      .format(EXPECTED_STEM_FORMAT)
+      .to_string(),
+  )
+}
+
+/// Whether a group of media files should be moved, given its main file
+/// (`handle_main`). If file name validation ran, this is only if any file in
+/// the group drifted. Otherwise, this is if forced or the main file is valid.
+fn should_move(
+  group: impl IntoIterator<Item = Handle<Media>>,
+  handle_main: Handle<Media>,
+  force: bool,
+  valid_media: &HashSet<Handle<Media>>,
+  drifted_media: Option<&HashSet<Handle<Media>>>,
+) -> bool {
+  match drifted_media {
+    Some(drifted) => group.into_iter().any(|h| drifted.contains(&h)),
+    None => force || valid_media.contains(&handle_main),
+  }
+}
+
+/// Why a group of media files was not moved, based on whether file name
+/// validation ran, and if so, whether moving the group was declined.
+fn skip_reason(
+  mut group: impl Iterator<Item = Handle<Media>>,
+  drifted_media: Option<&HashSet<Handle<Media>>>,
+  declined_media: &HashSet<Handle<Media>>,
+) -> &'static str {
+  if group.any(|h| declined_media.contains(&h)) {
+    "Moving and renaming was declined."
+  } else if drifted_media.is_some() {
+    "File name already matches metadata."
+  } else {
+    "File did not pass validation."
+  }
 }
 
 fn take_media(handle: Handle<Media>, media_map: &mut FileMap<Media>) -> Media {
@@ -128,56 +669,96 @@ fn pick_source(media: &Media, sidecar: Option<&SidecarInitial>) -> PathBuf {
     .to_path_buf()
 }
 
+/// Gets the path next to `file`, with the same stem plus `suffix`, and
+/// `ending` (e.g. `.mov.xmp`).
+fn path_next_to(file: impl AsRef<Path>, suffix: &str, ending: &OsStr) -> PathBuf {
+  let mut path = file.as_ref().with_extension("").into_os_string();
+  path.push(suffix);
+  path.push(ending);
+  PathBuf::from(path)
+}
+
+/// Records `media` and its sidecars as left in place in `report`, because of
+/// `reason`.
+fn skip_media_with_deps(
+  media: &Media,
+  sidecar: Option<&SidecarInitial>,
+  dupes: &[SidecarDupe],
+  reason: &str,
+  report: &mut Report,
+) {
+  for path in std::iter::once(media.as_ref())
+    .chain(sidecar.map(AsRef::as_ref))
+    .chain(dupes.iter().map(AsRef::as_ref))
+  {
+    report.action(path, Action::NotMoved {
+      reason: reason.to_string(),
+    });
+  }
+}
+
+/// Moves `media` and its sidecars to `dir_dst`, named by `format` (see
+/// `io::move_file`) from the metadata in `metadata_source`.
+/// Returns the new path to `media`.
+#[allow(clippy::too_many_arguments)]
 fn move_media_with_deps(
   dir_src: impl AsRef<Path>,
   dir_dst: impl AsRef<Path>,
+  format: &str,
   metadata_source: impl AsRef<Path>,
   media: Media,
   sidecar: Option<SidecarInitial>,
   dupes: impl IntoIterator<Item = SidecarDupe>,
-) -> Result<(), String> {
+  report: &mut Report,
+) -> Result<PathBuf, String> {
   log::trace!("{media}: Moving and renaming.");
 
   let media_file_ext = media.get_metadata().file_type_extension.clone();
 
-  for dupe in dupes {
-    let mut dupe_ending = OsString::from("_");
-    dupe_ending.push(dupe.get_dupe_number());
-    dupe_ending.push(".");
-    dupe_ending.push(&media_file_ext);
-    dupe_ending.push(".xmp");
-
-    io::move_file(
-      org::to_abs_path(&dir_src, dupe),
-      Some(&org::to_abs_path(&dir_src, &metadata_source)),
-      &dir_dst,
-      dupe_ending,
-    )?;
-  }
-
-  io::move_file(
-    org::to_abs_path(&dir_src, media),
+  // Sidecars are moved after, next to the media file, as `ExifTool` would
+  // otherwise pick copy numbers for them separately.
+  let to_media = io::move_file(
+    org::to_abs_path(&dir_src, &media),
     Some(&org::to_abs_path(&dir_src, &metadata_source)),
     &dir_dst,
+    format,
     format!(".{media_file_ext}"),
   )?;
 
-  if let Some(sidecar) = sidecar {
-    io::move_file(
-      org::to_abs_path(&dir_src, sidecar),
-      Some(&org::to_abs_path(dir_src, metadata_source)),
-      dir_dst,
-      format!(".{media_file_ext}.xmp"),
-    )?;
-  }
+  let dupes = dupes.into_iter().collect::<Vec<_>>();
+  let endings = dupes
+    .iter()
+    .map(|d| get_dupe_ending(d, &media_file_ext))
+    .chain(
+      sidecar
+        .as_ref()
+        .map(|s| OsString::from(s.get_naming().get_ending(&media_file_ext))),
+    )
+    .collect::<Vec<_>>();
 
-  Ok(())
+  let free = pick_free_media_path(&to_media, &endings)?;
+  let to_media = if free == to_media {
+    to_media
+  } else {
+    io::rename_file(&to_media, free)?
+  };
+
+  report.action(media, Action::Moved {
+    to: to_media.clone(),
+  });
+
+  move_sidecars_next_to(dir_src, &to_media, &media_file_ext, sidecar, dupes, report)?;
+
+  Ok(to_media)
 }
 
 #[cfg(test)]
 mod test_move_and_rename_files {
   use super::*;
-  use crate::testing::*;
+  use crate::{
+    prim::{Burst, BurstLayout},
+    testing::*,
+  };
 
   #[test]
   fn errors_if_destination_path_does_not_exist() {
@@ -219,6 +800,26 @@ mod test_move_and_rename_files {
     ]);
   }
 
+  #[test]
+  fn names_stem_sidecars_with_their_media() {
+    let d = test_dir!(
+      "a.heic": {},
+      "a.xmp": { "DateTimeOriginal": "2000-01-01T00:00:00" },
+      "b.jpg": {},
+      "b.xmp": { "DateTimeOriginal": "2000-01-01T00:00:00" },
+    );
+
+    let o = Organizer::import(d.root()).unwrap();
+    o.move_and_rename_files(d.root(), true).unwrap();
+
+    assert_dir!(d, [
+      "2000/01/000101_000000000.heic",
+      "2000/01/000101_000000000.xmp",
+      "2000/01/000101_000000000_b.jpg",
+      "2000/01/000101_000000000_b.xmp",
+    ]);
+  }
+
   #[test]
   fn moves_file_and_dupe_as_group() {
     let d = test_dir!(
@@ -235,6 +836,75 @@ mod test_move_and_rename_files {
     ]);
   }
 
+  #[test]
+  fn moves_edit_group_as_one() {
+    let d = test_dir!(
+      "IMG_1234.jpg": { "DateTimeOriginal": "2000-01-01T00:00:00" },
+      "IMG_E1234.jpg": { "DateTimeOriginal": "2025-01-01T00:00:00" },
+      "IMG_E1234.jpg.xmp": {},
+    );
+    std::fs::write(d.get_path("IMG_1234.aae"), "<plist></plist>").unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+    o.move_and_rename_files(d.root(), true).unwrap();
+
+    assert_dir!(d, [
+      "2000/01/000101_000000000.jpg",
+      "2000/01/000101_000000000_E.jpg",
+      "2000/01/000101_000000000_E.jpg.xmp",
+      "2000/01/000101_000000000.aae",
+    ]);
+  }
+
+  #[test]
+  fn moves_burst_into_subfolder() {
+    let d = test_dir!(
+      "image1.jpg": {
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+      },
+      "image2.jpg": {
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+        "SubSecTimeOriginal": "500",
+      },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    let burst = Burst {
+      id:   "ID".to_string(),
+      name: "000101_000000000".to_string(),
+    };
+    for file in ["image1.jpg", "image2.jpg"] {
+      let handle = o.media.find(d.get_path(file)).unwrap();
+      o.bursts.insert(handle, burst.clone());
+    }
+    o.burst_layout = BurstLayout::Subfolder;
+    o.move_and_rename_files(d.root(), true).unwrap();
+
+    assert_dir!(d, [
+      "2000/01/000101_000000000/000101_000000000.jpg",
+      "2000/01/000101_000000000/000101_000000500.jpg",
+    ]);
+  }
+
+  #[test]
+  fn moves_companions_with_video() {
+    let d = test_dir!(
+      "DJI_0001.mov": {
+        "CompressorID": "avc1",
+        "CreateDate": "2000-01-01T00:00:00",
+      },
+    );
+    std::fs::write(d.get_path("DJI_0001.SRT"), "1\n").unwrap();
+
+    let o = Organizer::import(d.root()).unwrap();
+    o.move_and_rename_files(d.root(), true).unwrap();
+
+    assert_dir!(d, [
+      "2000/01/000101_000000000.mov",
+      "2000/01/000101_000000000.srt",
+    ]);
+  }
+
   #[test]
   fn moves_groups_at_same_time_separately() {
     let d = test_dir!(
@@ -499,4 +1169,138 @@ mod test_move_and_rename_files {
 
     assert_dir!(d, ["image.heic", "image.mov",]);
   }
+
+  #[test]
+  fn moves_only_drifted_files_after_file_name_validation() {
+    let d = test_dir!(
+      "2000/01/000101_000000000.jpg": { "DateTimeOriginal": "2000-01-01T00:00:00" },
+      "2000/01/000101_000000000.jpg.xmp": { "DateTimeOriginal": "2000-01-01T00:00:00" },
+      "2000/01/000102_000000000.jpg": { "DateTimeOriginal": "2000-01-01T00:00:00" },
+      "2000/01/000102_000000000.jpg.xmp": { "DateTimeOriginal": "2000-01-03T00:00:00" },
+    );
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.enable_file_name_validation(d.root());
+    o.validate();
+    assert_eq!(o.count_drifted_media(), 1);
+    o.move_and_rename_files(d.root(), true).unwrap();
+
+    assert_dir!(d, [
+      "2000/01/000101_000000000.jpg",
+      "2000/01/000101_000000000.jpg.xmp",
+      "2000/01/000103_000000000.jpg",
+      "2000/01/000103_000000000.jpg.xmp",
+    ]);
+  }
+}
+
+#[cfg(test)]
+mod test_pick_free_media_path {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn advances_copy_number_past_taken_sidecars() {
+    let d = test_dir!();
+    for file in [
+      "000101_000000000.xmp",
+      "000101_000000000_b.jpg",
+      "000101_000000000_b.xmp",
+    ] {
+      fs::write(d.get_path(file), "").unwrap();
+    }
+
+    assert_eq!(
+      pick_free_media_path(&d.get_path("000101_000000000_b.jpg"), &[OsString::from(
+        ".xmp"
+      )])
+      .unwrap(),
+      d.get_path("000101_000000000_c.jpg")
+    );
+  }
+
+  #[test]
+  fn keeps_path_if_sidecars_free() {
+    let d = test_dir!();
+    fs::write(d.get_path("000101_000000000.jpg"), "").unwrap();
+
+    assert_eq!(
+      pick_free_media_path(&d.get_path("000101_000000000.jpg"), &[OsString::from(
+        ".jpg.xmp"
+      )])
+      .unwrap(),
+      d.get_path("000101_000000000.jpg")
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_get_expected_stem {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn prefers_date_time_original() {
+    let metadata = metadata!(
+      "CreateDate": "2000-01-02T00:00:00",
+      "DateTimeOriginal": "2000-01-01T12:30:01",
+      "SubSecDateTimeOriginal": "2000-01-01T12:30:01.050",
+    );
+
+    assert_eq!(
+      get_expected_stem(&metadata).unwrap(),
+      "2000/01/000101_123001050"
+    );
+  }
+
+  #[test]
+  fn converts_offset_as_exiftool_does() {
+    let pst = metadata!(
+      "DateTimeOriginal": "2000-01-01T12:30:01-08:00",
+    );
+    let utc = metadata!(
+      "DateTimeOriginal": "2000-01-01T20:30:01+00:00",
+    );
+
+    assert_eq!(get_expected_stem(&pst), get_expected_stem(&utc));
+  }
+
+  #[test]
+  fn falls_back_to_create_date() {
+    let metadata = metadata!(
+      "CreateDate": "2000-01-02T00:00:00",
+    );
+
+    assert_eq!(
+      get_expected_stem(&metadata).unwrap(),
+      "2000/01/000102_000000000"
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_skip_reason {
+  use super::*;
+
+  #[test]
+  fn reports_declined_moves() {
+    let (a, b) = (Handle::from(0), Handle::from(1));
+    let declined = HashSet::from([a]);
+    let drifted = HashSet::new();
+
+    assert_eq!(
+      skip_reason([a].into_iter(), Some(&drifted), &declined),
+      "Moving and renaming was declined."
+    );
+    assert_eq!(
+      skip_reason([b].into_iter(), Some(&drifted), &declined),
+      "File name already matches metadata."
+    );
+    assert_eq!(
+      skip_reason([b].into_iter(), None, &HashSet::new()),
+      "File did not pass validation."
+    );
+  }
 }
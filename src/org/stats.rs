@@ -0,0 +1,262 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Statistics on a catalog's composition and health.
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fs,
+};
+
+use serde::Serialize;
+
+use super::{Organizer, rules::Severity, stage_5_validation};
+use crate::prim::{Camera, Sidecar};
+
+/// Key for media files missing the tag counted by.
+const UNKNOWN: &str = "(unknown)";
+
+/// Number and total size of media files.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Count {
+  pub files: usize,
+  pub bytes: u64,
+}
+
+impl Count {
+  /// Adds a file of `bytes`.
+  fn add(&mut self, bytes: u64) {
+    self.files += 1;
+    self.bytes += bytes;
+  }
+}
+
+/// Health of Live Photos, by `ContentIdentifier`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LivePhotoStats {
+  /// Exactly one image and one video, or either linked to its other half
+  /// already in the catalog.
+  pub pairs:         usize,
+  pub missing_video: usize,
+  pub missing_image: usize,
+  /// Multiple images or videos.
+  pub duplicates:    usize,
+}
+
+/// Coverage of media files by sidecars.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SidecarStats {
+  pub with_sidecar:    usize,
+  pub without_sidecar: usize,
+  /// darktable duplicate sidecars.
+  pub dupes:           usize,
+  /// Sidecars (initial or duplicate) without a media file.
+  pub orphaned:        usize,
+}
+
+/// Results of metadata validation.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationStats {
+  /// Media files without error-severity findings.
+  pub passed:  usize,
+  pub failed:  usize,
+  /// Media files with findings, by rule.
+  pub by_rule: BTreeMap<String, usize>,
+}
+
+/// Composition of a catalog's media files, using metadata from their sidecars
+/// where present.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CatalogStats {
+  pub total:        Count,
+  pub by_year:      BTreeMap<String, Count>,
+  pub by_month:     BTreeMap<String, Count>,
+  pub by_file_type: BTreeMap<String, Count>,
+  pub by_codec:     BTreeMap<String, Count>,
+  pub by_camera:    BTreeMap<String, Count>,
+  pub by_creator:   BTreeMap<String, Count>,
+  pub by_country:   BTreeMap<String, Count>,
+  /// Keyed by `City, Country`.
+  pub by_city:      BTreeMap<String, Count>,
+  pub live_photos:  LivePhotoStats,
+  pub sidecars:     SidecarStats,
+  /// Only present if validation is enabled.
+  pub validation:   Option<ValidationStats>,
+}
+
+impl Organizer {
+  /// Computes statistics on all loaded files, without modifying any. Sizes are
+  /// of media files only.
+  pub fn stats(&self) -> CatalogStats {
+    let mut stats = CatalogStats::default();
+
+    for (handle, media) in self.media.iter_data_indexed() {
+      let metadata = self.get_media_metadata(handle);
+      let bytes = fs::metadata(self.get_media_path(handle)).map_or(0, |m| m.len());
+      let date = metadata.get_date_time_original().map(|(d, _)| d);
+      let country = metadata.country.as_deref().unwrap_or(UNKNOWN);
+
+      for (counts, key) in [
+        (&mut stats.by_year, date.map(|d| d.format("%Y").to_string())),
+        (
+          &mut stats.by_month,
+          date.map(|d| d.format("%Y-%m").to_string()),
+        ),
+        (
+          &mut stats.by_file_type,
+          Some(media.get_metadata().file_type.clone()),
+        ),
+        (&mut stats.by_codec, Some(media.get_codec().to_string())),
+        (
+          &mut stats.by_camera,
+          Some(Camera::from_metadata(metadata).to_string()),
+        ),
+        (&mut stats.by_creator, metadata.creator.clone()),
+        (&mut stats.by_country, metadata.country.clone()),
+        (
+          &mut stats.by_city,
+          metadata.city.as_ref().map(|c| format!("{c}, {country}")),
+        ),
+      ] {
+        counts
+          .entry(key.unwrap_or(UNKNOWN.to_string()))
+          .or_default()
+          .add(bytes);
+      }
+
+      stats.total.add(bytes);
+
+      if media.get_sidecar().is_some() {
+        stats.sidecars.with_sidecar += 1;
+      } else {
+        stats.sidecars.without_sidecar += 1;
+      }
+    }
+
+    stats.sidecars.dupes = self.dupes.iter_data().count();
+    stats.sidecars.orphaned = self
+      .sidecars
+      .iter_data()
+      .filter(|s| s.is_leftover())
+      .count()
+      + self.dupes.iter_data().filter(|s| s.is_leftover()).count();
+
+    stats.live_photos = self.get_live_photo_stats();
+    stats.validation = self
+      .validation
+      .enabled()
+      .then(|| self.get_validation_stats());
+
+    stats
+  }
+
+  /// Counts Live Photos by health.
+  fn get_live_photo_stats(&self) -> LivePhotoStats {
+    let mut stats = LivePhotoStats::default();
+
+    for (id, link) in &self.live_photo_map {
+      if link.has_duplicate_images() || link.has_duplicate_videos() {
+        stats.duplicates += 1;
+      } else if link.is_missing_video() && !self.catalog_videos.contains_key(id) {
+        stats.missing_video += 1;
+      } else if link.is_leftover_videos() && !self.catalog_images.contains_key(id) {
+        stats.missing_image += 1;
+      } else {
+        stats.pairs += 1;
+      }
+    }
+
+    stats
+  }
+
+  /// Validates all media files, without recording findings in the report.
+  fn get_validation_stats(&self) -> ValidationStats {
+    let mut stats = ValidationStats::default();
+
+    for (_, findings) in stage_5_validation::validate(
+      &self.media,
+      &self.sidecars,
+      &self.validation,
+      &self.copyright,
+    ) {
+      if findings.iter().all(|f| f.severity < Severity::Error) {
+        stats.passed += 1;
+      } else {
+        stats.failed += 1;
+      }
+
+      for rule in findings
+        .into_iter()
+        .map(|f| f.rule)
+        .collect::<BTreeSet<_>>()
+      {
+        *stats.by_rule.entry(rule).or_default() += 1;
+      }
+    }
+
+    stats
+  }
+}
+
+#[cfg(test)]
+mod test_stats {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn counts_by_tag() {
+    let d = test_dir!(
+      "a.jpg": {
+        "DateTimeOriginal": "2023:05:01 12:00:00",
+        "Make": "Canon",
+        "Model": "R5",
+        "City": "Kyoto",
+        "Country": "Japan",
+      },
+      "a.jpg.xmp": {
+        "DateTimeOriginal": "2023:05:01 12:00:00",
+        "Creator": "Creator",
+        "City": "Kyoto",
+        "Country": "Japan",
+      },
+      "b.jpg": { "DateTimeOriginal": "2023:06:01 12:00:00" },
+      "orphan.jpg.xmp": {},
+    );
+
+    let o = Organizer::import(d.root()).unwrap();
+    let stats = o.stats();
+
+    assert_eq!(stats.total.files, 2);
+    assert_eq!(stats.by_year["2023"].files, 2);
+    assert_eq!(stats.by_month["2023-05"].files, 1);
+    assert_eq!(stats.by_file_type["JPEG"].files, 2);
+    assert_eq!(stats.by_creator["Creator"].files, 1);
+    assert_eq!(stats.by_creator[UNKNOWN].files, 1);
+    assert_eq!(stats.by_city["Kyoto, Japan"].files, 1);
+    assert_eq!(stats.sidecars, SidecarStats {
+      with_sidecar:    1,
+      without_sidecar: 1,
+      dupes:           0,
+      orphaned:        1,
+    });
+    assert_eq!(stats.validation, None);
+  }
+
+  #[test]
+  fn counts_live_photo_health() {
+    let d = test_dir!(
+      "a.jpg": { "ContentIdentifier": "A" },
+      "a.mov": { "ContentIdentifier": "A", "CompressorID": "hvc1" },
+      "b.jpg": { "ContentIdentifier": "B" },
+      "c.mov": { "ContentIdentifier": "C", "CompressorID": "hvc1" },
+    );
+
+    let o = Organizer::import(d.root()).unwrap();
+
+    assert_eq!(o.stats().live_photos, LivePhotoStats {
+      pairs:         1,
+      missing_video: 1,
+      missing_image: 1,
+      duplicates:    0,
+    });
+  }
+}
@@ -0,0 +1,182 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Merging of Google Takeout JSON sidecars into XMP sidecars.
+
+use std::{ffi::OsStr, mem};
+
+use super::{Organizer, report::Action, stage_1_cleanup::remove_by_path};
+use crate::{
+  io,
+  org,
+  prim::{self, Sidecar, Takeout},
+};
+
+impl Organizer {
+  /// Merges each Google Takeout JSON sidecar's date and time, GPS coordinates
+  /// and description into its media file's XMP sidecar, where not already set
+  /// (Takeout strips these from the media files themselves). Merged JSON files
+  /// are then removed, while those matching no media file are left in place.
+  /// Must run after sidecar creation.
+  pub fn merge_takeout_metadata(&mut self) -> Result<(), String> {
+    if self.takeout.is_empty() {
+      return Ok(());
+    }
+
+    log::info!("Merging Google Takeout metadata.");
+
+    for json in mem::take(&mut self.takeout) {
+      let takeout = match io::read_json::<Takeout>(&json) {
+        Ok(takeout) => takeout,
+        Err(e) => {
+          log::warn!("{e}");
+          continue;
+        }
+      };
+
+      let media_paths = self
+        .media
+        .iter_data()
+        .map(|m| org::to_abs_path(&self.source, m))
+        .filter(|p| p.parent() == json.parent())
+        .collect::<Vec<_>>();
+
+      let Some(media_path) = prim::find_takeout_media(
+        &json,
+        takeout.title.as_deref(),
+        media_paths.iter().map(AsRef::as_ref),
+      ) else {
+        log::warn!(
+          "{}: No media file found for Takeout metadata.",
+          json.display()
+        );
+        continue;
+      };
+
+      let media = &self.media[self.media.find(media_path).unwrap()];
+      let Some(sidecar) = media.get_sidecar().map(|h| &mut self.sidecars[h]) else {
+        log::warn!("{media}: Cannot merge Takeout metadata without sidecar.");
+        continue;
+      };
+
+      let metadata = sidecar.get_metadata();
+      let mut args = Vec::new();
+      let mut tags = Vec::new();
+
+      if metadata.date_time_original.is_none()
+        && let Some(date_time) = takeout.get_date_time()
+      {
+        args.push(format!("-DateTimeOriginal={}", date_time.to_rfc3339()));
+        tags.push("DateTimeOriginal");
+      }
+
+      if metadata.gps_latitude.is_none()
+        && let Some((lat, lon)) = takeout.get_lat_lon()
+      {
+        args.push(format!("-GPSLatitude={lat}"));
+        args.push(format!("-GPSLongitude={lon}"));
+        tags.extend(["GPSLatitude", "GPSLongitude"]);
+      }
+
+      if metadata.get("Description").is_none()
+        && let Some(description) = takeout.get_description()
+      {
+        args.push(format!("-Description={description}"));
+        tags.push("Description");
+      }
+
+      if !args.is_empty() {
+        log::trace!("{sidecar}: Merging Takeout metadata.");
+
+        let path = org::to_abs_path(&self.source, &sidecar);
+        io::run_exiftool(
+          Some(&self.source),
+          args.iter().map(OsStr::new).chain([path.as_os_str()]),
+        )?;
+
+        let metadata = io::read_metadata(&path)?;
+
+        self.report.action(&path, Action::MetadataUpdated {
+          pass: "takeout".to_string(),
+        });
+        for tag in tags {
+          self
+            .report
+            .tag_change(&path, tag, metadata.get(tag).as_deref());
+        }
+
+        self
+          .report
+          .metadata_diff(&path, sidecar.get_metadata(), &metadata);
+        sidecar.update_metadata(metadata);
+      }
+
+      remove_by_path(
+        &self.source,
+        &json,
+        self.trash.as_ref(),
+        &mut self.report,
+        "Takeout metadata merged",
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test_merge_takeout_metadata {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  const TAKEOUT: &str = r#"{
+    "title": "image.jpg",
+    "description": "Description",
+    "photoTakenTime": { "timestamp": "946684800" },
+    "geoData": { "latitude": 47.6, "longitude": -122.3 }
+  }"#;
+
+  #[test]
+  fn writes_missing_tags_to_sidecar() {
+    let d = test_dir!(
+      "image.jpg": {},
+    );
+    fs::write(d.get_path("image.jpg.json"), TAKEOUT).unwrap();
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.create_missing_sidecars().unwrap();
+    o.merge_takeout_metadata().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Description", "Description");
+    assert_trash!(d, ["image.jpg.json"]);
+  }
+
+  #[test]
+  fn keeps_existing_tags() {
+    let d = test_dir!(
+      "image.jpg": {},
+      "image.jpg.xmp": { "Description": "Existing" },
+    );
+    fs::write(d.get_path("image.jpg.json"), TAKEOUT).unwrap();
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.merge_takeout_metadata().unwrap();
+
+    assert_tag!(d, "image.jpg.xmp", "Description", "Existing");
+  }
+
+  #[test]
+  fn keeps_unmatched_json() {
+    let d = test_dir!(
+      "image.jpg": {},
+    );
+    fs::write(d.get_path("metadata.json"), r#"{ "title": "Album" }"#).unwrap();
+
+    let mut o = Organizer::load_catalog(d.root(), d.some_trash()).unwrap();
+    o.create_missing_sidecars().unwrap();
+    o.merge_takeout_metadata().unwrap();
+
+    assert_dir!(d, ["image.jpg", "image.jpg.xmp", "metadata.json"]);
+  }
+}
@@ -0,0 +1,228 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Normalization of video capture dates, which video formats store
+//! inconsistently (UTC or local time, or in embedded streams).
+
+use std::ffi::OsStr;
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use super::{Organizer, report::Action};
+use crate::{
+  io,
+  org,
+  prim::{self, Metadata, Sidecar},
+};
+
+/// Settings for reading video capture dates.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct VideoConfig {
+  /// Camera makes (`Make` tag) that store local time in `QuickTime` dates,
+  /// despite the specification requiring UTC.
+  pub local_time_makes: Vec<String>,
+}
+
+impl Default for VideoConfig {
+  fn default() -> Self {
+    Self {
+      local_time_makes: vec!["GoPro".to_string()],
+    }
+  }
+}
+
+impl Organizer {
+  /// Sets `DateTimeOriginal` for videos without it, from the date their format
+  /// does store. For `QuickTime`-based videos (e.g. MOV and MP4), this is
+  /// `CreateDate`, converted from UTC unless the camera's make is listed in
+  /// `config` as storing local time. For AVCHD (`.MTS`), this is the
+  /// `DateTimeOriginal` embedded in the H.264 stream. Dates are converted to
+  /// the time zone at the video's location, if known. Must run after sidecar
+  /// creation.
+  pub fn set_video_dates(&mut self, config: &VideoConfig) -> Result<(), String> {
+    log::info!("Setting video capture dates.");
+
+    for media in self.media.iter_data_mut() {
+      let args: &[&str] = if media.is_quicktime() {
+        let local_time = media.get_metadata().make.as_ref().is_some_and(|make| {
+          config
+            .local_time_makes
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(make))
+        });
+
+        if local_time {
+          &[]
+        } else {
+          &["-api", "QuickTimeUTC"]
+        }
+      } else if media.get_metadata().file_type == "M2TS" {
+        &["-ee"]
+      } else {
+        continue;
+      };
+
+      let metadata = media
+        .get_sidecar()
+        .map_or(media.get_metadata(), |h| self.sidecars[h].get_metadata());
+
+      if metadata.date_time_original.is_some() {
+        continue;
+      }
+
+      let video = io::read_metadata_with_args(org::to_abs_path(&self.source, &*media), args)?;
+
+      let Some(date_time) = get_video_date_time(&video, metadata) else {
+        log::warn!("{media}: No capture date found in video.");
+        continue;
+      };
+
+      log::trace!("{media}: Setting capture date from video.");
+
+      let path = org::to_abs_path(&self.source, &metadata.source_file);
+      let arg = format!("-DateTimeOriginal={date_time}");
+      io::run_exiftool(Some(&self.source), [OsStr::new(&arg), path.as_os_str()])?;
+
+      let metadata = io::read_metadata(&path)?;
+
+      self.report.action(&path, Action::MetadataUpdated {
+        pass: "video_date".to_string(),
+      });
+      self.report.tag_change(
+        &path,
+        "DateTimeOriginal",
+        metadata.date_time_original.as_deref(),
+      );
+
+      org::update_media_metadata(&mut self.report, &mut self.sidecars, media, metadata);
+    }
+
+    Ok(())
+  }
+}
+
+/// Gets the capture date from `video` (read with format-specific options),
+/// formatted for `ExifTool`. Dates with a time zone are converted to the one at
+/// the location in `metadata`, if any, while local dates are left without.
+fn get_video_date_time(video: &Metadata, metadata: &Metadata) -> Option<String> {
+  let (date_time, offset) = [
+    &video.sub_sec_date_time_original,
+    &video.date_time_original,
+    &video.sub_sec_create_date,
+    &video.create_date,
+  ]
+  .into_iter()
+  .find_map(|d| prim::parse_date_time(d.as_deref()?).ok())?;
+
+  let Some(offset) = offset else {
+    return Some(date_time.format("%Y-%m-%dT%H:%M:%S%.f").to_string());
+  };
+
+  Some(to_local_time_zone(date_time, offset, metadata).to_rfc3339())
+}
+
+/// Converts `date_time` at `offset` to the time zone at the location in
+/// `metadata`, if it has one.
+fn to_local_time_zone(
+  date_time: NaiveDateTime,
+  offset: FixedOffset,
+  metadata: &Metadata,
+) -> DateTime<FixedOffset> {
+  let date_time = offset.from_local_datetime(&date_time).unwrap();
+
+  metadata
+    .get_lat_lon()
+    .and_then(|(lat, lon)| prim::get_time_zone_name(lat, lon).parse::<Tz>().ok())
+    .map_or(date_time, |tz| date_time.with_timezone(&tz).fixed_offset())
+}
+
+#[cfg(test)]
+mod test_get_video_date_time {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn keeps_local_date() {
+    let video = metadata!(
+      "CreateDate": "2000-01-01T00:00:00",
+    );
+
+    assert_eq!(
+      get_video_date_time(&video, &metadata!()).as_deref(),
+      Some("2000-01-01T00:00:00")
+    );
+  }
+
+  #[test]
+  fn prefers_date_time_original() {
+    let video = metadata!(
+      "CreateDate": "2000-01-01T00:00:00+00:00",
+      "DateTimeOriginal": "2000-01-02T00:00:00+09:00",
+    );
+
+    assert_eq!(
+      get_video_date_time(&video, &metadata!()).as_deref(),
+      Some("2000-01-02T00:00:00+09:00")
+    );
+  }
+
+  #[test]
+  fn converts_to_time_zone_at_location() {
+    let video = metadata!(
+      "CreateDate": "2000-01-01T08:00:00+00:00",
+    );
+    let metadata = metadata!(
+      "GPSPosition": "47 deg 36' 21.96\" N, 122 deg 19' 58.08\" W",
+    );
+
+    assert_eq!(
+      get_video_date_time(&video, &metadata).as_deref(),
+      Some("2000-01-01T00:00:00-08:00")
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_set_video_dates {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn keeps_existing_date_time_original() {
+    let d = test_dir!(
+      "video.mov": {
+        "CompressorID": "hvc1",
+        "CreateDate": "2000-01-01T00:00:00",
+        "DateTimeOriginal": "2000-01-02T00:00:00+00:00",
+      },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_video_dates(&VideoConfig::default()).unwrap();
+
+    assert_tag!(
+      d,
+      "video.mov",
+      "DateTimeOriginal",
+      "2000-01-02T00:00:00+00:00"
+    );
+  }
+
+  #[test]
+  fn keeps_local_time_for_listed_makes() {
+    let d = test_dir!(
+      "video.mov": {
+        "CompressorID": "hvc1",
+        "CreateDate": "2000-01-01T00:00:00",
+        "Make": "GoPro",
+      },
+    );
+
+    let mut o = Organizer::import(d.root()).unwrap();
+    o.set_video_dates(&VideoConfig::default()).unwrap();
+
+    assert_tag!(d, "video.mov", "DateTimeOriginal", "2000-01-01T00:00:00");
+  }
+}
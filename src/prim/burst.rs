@@ -0,0 +1,320 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Bursts and bracketed exposures, which are sequences of images shot in quick
+//! succession.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use serde::Deserialize;
+
+use super::Metadata;
+
+/// Prefix for the keyword (`XMP-dc:Subject`) recording burst membership.
+pub const BURST_KEYWORD_PREFIX: &str = "burst:";
+
+/// Format for a burst's name, from the capture time of its first image in local
+/// time. This mirrors the file name part of `io::DATETIME_WRITE_FORMAT`.
+pub const BURST_NAME_FORMAT: &str = "%y%m%d_%H%M%S%3f";
+
+/// Values camera makers use for a single shot, rather than a sequence.
+const SINGLE_SHOT_VALUES: [&str; 3] = ["0", "Off", "Single"];
+
+/// How bursts are laid out in the catalog.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurstLayout {
+  /// Named like any other image.
+  #[default]
+  None,
+  /// Placed in a subfolder named for the burst.
+  Subfolder,
+  /// Named with the burst's name as prefix.
+  Prefix,
+}
+
+impl BurstLayout {
+  /// Applies this layout for the burst `name` to `path` (e.g.
+  /// `2000/01/000101_000000000`), which may also be a date format.
+  pub fn apply(self, path: &str, name: &str) -> String {
+    let (dir, file) = path.rsplit_once('/').unwrap_or(("", path));
+    let dir = if dir.is_empty() {
+      String::new()
+    } else {
+      format!("{dir}/")
+    };
+
+    match self {
+      BurstLayout::None => path.to_string(),
+      BurstLayout::Subfolder => format!("{dir}{name}/{file}"),
+      BurstLayout::Prefix => format!("{dir}{name}_{file}"),
+    }
+  }
+}
+
+/// A burst an image belongs to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Burst {
+  /// Identifies the burst in metadata: Apple's `BurstUUID` if present, else
+  /// the name.
+  pub id:   String,
+  /// Names the burst in the catalog, from its first image.
+  pub name: String,
+}
+
+impl Burst {
+  /// Gets the keyword recording membership of this burst.
+  pub fn get_keyword(&self) -> String {
+    format!("{BURST_KEYWORD_PREFIX}{}", self.id)
+  }
+}
+
+/// Finds bursts and bracketed exposures among `images`: those sharing Apple's
+/// `BurstUUID`, or marked by the camera as part of a sequence and shot by the
+/// same camera within `max_gap` of each other. Members are in capture order.
+/// Capture times are converted to local time, as `ExifTool` does when naming
+/// files, so burst names match those of their images.
+pub fn find_bursts<'a, T>(
+  images: impl IntoIterator<Item = (T, &'a Metadata)>,
+  max_gap: TimeDelta,
+) -> Vec<(Burst, Vec<T>)> {
+  let mut apple = HashMap::<String, Vec<(T, NaiveDateTime)>>::new();
+  let mut sequences = HashMap::<(Option<&str>, Option<&str>), Vec<(T, NaiveDateTime)>>::new();
+
+  for (image, metadata) in images {
+    let Some(date_time) = metadata
+      .get_date_time_original()
+      .map(|(date_time, offset)| super::to_local_time(&date_time, offset))
+    else {
+      continue;
+    };
+
+    if let Some(uuid) = get_burst_uuid(metadata) {
+      apple.entry(uuid).or_default().push((image, date_time));
+    } else if is_in_sequence(metadata) {
+      sequences
+        .entry((metadata.make.as_deref(), metadata.model.as_deref()))
+        .or_default()
+        .push((image, date_time));
+    }
+  }
+
+  apple
+    .into_iter()
+    .filter_map(|(uuid, mut members)| {
+      members.sort_by_key(|(_, date_time)| *date_time);
+      (members.len() > 1).then_some((Some(uuid), members))
+    })
+    .chain(
+      sequences
+        .into_values()
+        .flat_map(|members| group_by_gap(members, max_gap))
+        .map(|members| (None, members)),
+    )
+    .map(|(uuid, members)| {
+      let name = members[0].1.format(BURST_NAME_FORMAT).to_string();
+      let burst = Burst {
+        id: uuid.unwrap_or_else(|| name.clone()),
+        name,
+      };
+      (burst, members.into_iter().map(|(m, _)| m).collect())
+    })
+    .collect()
+}
+
+/// Gets the burst identifier Apple devices write, if any.
+fn get_burst_uuid(metadata: &Metadata) -> Option<String> {
+  metadata.get("BurstUUID").filter(|u| !u.is_empty())
+}
+
+/// Whether the camera marked `metadata` as part of a continuous shooting
+/// sequence or exposure bracket.
+fn is_in_sequence(metadata: &Metadata) -> bool {
+  ["SequenceNumber", "BracketMode", "AEBBracketValue"]
+    .into_iter()
+    .filter_map(|tag| metadata.get(tag))
+    .any(|v| !SINGLE_SHOT_VALUES.contains(&v.as_str()))
+}
+
+/// Splits `members` into sequences with at most `max_gap` between consecutive
+/// capture times, in order. Sequences of one are left out.
+fn group_by_gap<T>(
+  members: impl IntoIterator<Item = (T, NaiveDateTime)>,
+  max_gap: TimeDelta,
+) -> Vec<Vec<(T, NaiveDateTime)>> {
+  let mut members = members.into_iter().collect::<Vec<_>>();
+  members.sort_by_key(|(_, date_time)| *date_time);
+
+  let mut groups = Vec::<Vec<(T, NaiveDateTime)>>::new();
+  for member in members {
+    match groups.last_mut() {
+      Some(group) if member.1 - group.last().unwrap().1 <= max_gap => group.push(member),
+      _ => groups.push(vec![member]),
+    }
+  }
+
+  groups.retain(|g| g.len() > 1);
+  groups
+}
+
+#[cfg(test)]
+mod test_apply {
+  use super::*;
+
+  #[test]
+  fn applies_layout() {
+    let path = "2000/01/000101_000000500";
+    let name = "000101_000000000";
+
+    assert_eq!(BurstLayout::None.apply(path, name), path);
+    assert_eq!(
+      BurstLayout::Subfolder.apply(path, name),
+      "2000/01/000101_000000000/000101_000000500"
+    );
+    assert_eq!(
+      BurstLayout::Prefix.apply(path, name),
+      "2000/01/000101_000000000_000101_000000500"
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_find_bursts {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn groups_apple_bursts() {
+    let images = [
+      metadata!(
+        "BurstUUID": "UUID",
+        "DateTimeOriginal": "2000-01-01T00:00:00.500",
+      ),
+      metadata!(
+        "BurstUUID": "UUID",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+      ),
+      metadata!(
+        "DateTimeOriginal": "2000-01-01T00:00:00.500",
+      ),
+    ];
+
+    let bursts = find_bursts(images.iter().enumerate(), TimeDelta::seconds(1));
+
+    assert_eq!(bursts, [(
+      Burst {
+        id:   "UUID".to_string(),
+        name: "000101_000000000".to_string(),
+      },
+      vec![1, 0]
+    )]);
+  }
+
+  #[test]
+  fn names_bursts_in_local_time() {
+    let pst = [
+      metadata!(
+        "BurstUUID": "UUID",
+        "DateTimeOriginal": "2000-01-01T12:00:00-08:00",
+      ),
+      metadata!(
+        "BurstUUID": "UUID",
+        "DateTimeOriginal": "2000-01-01T12:00:01-08:00",
+      ),
+    ];
+    let utc = [
+      metadata!(
+        "BurstUUID": "UUID",
+        "DateTimeOriginal": "2000-01-01T20:00:00+00:00",
+      ),
+      metadata!(
+        "BurstUUID": "UUID",
+        "DateTimeOriginal": "2000-01-01T20:00:01+00:00",
+      ),
+    ];
+
+    assert_eq!(
+      find_bursts(pst.iter().enumerate(), TimeDelta::seconds(1)),
+      find_bursts(utc.iter().enumerate(), TimeDelta::seconds(1))
+    );
+  }
+
+  #[test]
+  fn groups_sequences_by_camera() {
+    let images = [
+      metadata!(
+        "BracketMode": "AEB",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+        "Model": "A",
+      ),
+      metadata!(
+        "BracketMode": "AEB",
+        "DateTimeOriginal": "2000-01-01T00:00:01",
+        "Model": "A",
+      ),
+      metadata!(
+        "BracketMode": "AEB",
+        "DateTimeOriginal": "2000-01-01T00:00:00",
+        "Model": "B",
+      ),
+    ];
+
+    let bursts = find_bursts(images.iter().enumerate(), TimeDelta::seconds(1));
+
+    assert_eq!(bursts, [(
+      Burst {
+        id:   "000101_000000000".to_string(),
+        name: "000101_000000000".to_string(),
+      },
+      vec![0, 1]
+    )]);
+  }
+}
+
+#[cfg(test)]
+mod test_is_in_sequence {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn detects_sequences_and_brackets() {
+    assert!(is_in_sequence(&metadata!("SequenceNumber": 2)));
+    assert!(is_in_sequence(&metadata!("BracketMode": "AEB")));
+    assert!(is_in_sequence(&metadata!("AEBBracketValue": "+1")));
+  }
+
+  #[test]
+  fn ignores_single_shots() {
+    assert!(!is_in_sequence(&metadata!()));
+    assert!(!is_in_sequence(&metadata!("SequenceNumber": 0)));
+    assert!(!is_in_sequence(&metadata!("SequenceNumber": "Single")));
+    assert!(!is_in_sequence(&metadata!("BracketMode": "Off")));
+  }
+}
+
+#[cfg(test)]
+mod test_group_by_gap {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn splits_on_gaps() {
+    let groups = group_by_gap(
+      [
+        ("c", make_date_naive(2000, 1, 1, 0, 0, 1, 0)),
+        ("a", make_date_naive(2000, 1, 1, 0, 0, 0, 0)),
+        ("b", make_date_naive(2000, 1, 1, 0, 0, 0, 500)),
+        ("d", make_date_naive(2000, 1, 1, 0, 0, 5, 0)),
+      ],
+      TimeDelta::seconds(1),
+    );
+
+    assert_eq!(
+      groups
+        .iter()
+        .map(|g| g.iter().map(|(m, _)| *m).collect::<Vec<_>>())
+        .collect::<Vec<_>>(),
+      [["a", "b", "c"]]
+    );
+  }
+}
@@ -0,0 +1,179 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Cameras, as identified by their metadata, and who owns them.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::Deserialize;
+
+use super::Metadata;
+
+/// Maps cameras to their owner. Every criterion set must match a file's
+/// metadata (ignoring case) for it to be attributed to `owner`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CameraOwner {
+  /// Value for `Creator`. Required, unlike the criteria.
+  pub owner:         String,
+  pub make:          Option<String>,
+  pub model:         Option<String>,
+  pub serial_number: Option<String>,
+  /// For phones, the device (e.g. `iPhone 15 Pro`).
+  pub host_computer: Option<String>,
+  pub software:      Option<String>,
+}
+
+impl CameraOwner {
+  /// Gets the tags this entry matches on and their values.
+  fn criteria(&self) -> impl Iterator<Item = (&'static str, &str)> {
+    [
+      ("Make", &self.make),
+      ("Model", &self.model),
+      ("SerialNumber", &self.serial_number),
+      ("HostComputer", &self.host_computer),
+      ("Software", &self.software),
+    ]
+    .into_iter()
+    .filter_map(|(tag, value)| Some((tag, value.as_deref()?)))
+  }
+
+  /// Whether every criterion matches `metadata`. Entries without criteria
+  /// match nothing.
+  fn matches(&self, metadata: &Metadata) -> bool {
+    self.criteria().next().is_some()
+      && self.criteria().all(|(tag, value)| {
+        metadata
+          .get(tag)
+          .is_some_and(|v| v.eq_ignore_ascii_case(value))
+      })
+  }
+}
+
+/// Who owns the camera a file was shot with.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OwnerMatch<'a> {
+  None,
+  Owner(&'a str),
+  /// Equally specific entries (e.g. a shared camera) name different owners.
+  Ambiguous(Vec<&'a str>),
+}
+
+/// Finds the owner of the camera `metadata` was shot with in `owners`. Where
+/// several entries match, the most specific (i.e. with the most criteria) win.
+pub fn find_camera_owner<'a>(owners: &'a [CameraOwner], metadata: &Metadata) -> OwnerMatch<'a> {
+  let matches = owners
+    .iter()
+    .filter(|o| o.matches(metadata))
+    .map(|o| (o.criteria().count(), o.owner.as_str()))
+    .collect::<Vec<_>>();
+
+  let Some(specificity) = matches.iter().map(|(s, _)| *s).max() else {
+    return OwnerMatch::None;
+  };
+
+  let mut best = matches
+    .into_iter()
+    .filter(|(s, _)| *s == specificity)
+    .map(|(_, o)| o)
+    .collect::<Vec<_>>();
+  best.sort_unstable();
+  best.dedup();
+
+  match best.as_slice() {
+    [owner] => OwnerMatch::Owner(owner),
+    _ => OwnerMatch::Ambiguous(best),
+  }
+}
+
+/// Identifies a camera by its metadata, for listing those in a catalog.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Camera {
+  pub make:          Option<String>,
+  pub model:         Option<String>,
+  pub serial_number: Option<String>,
+  pub host_computer: Option<String>,
+}
+
+impl Camera {
+  /// Gets the camera `metadata` was shot with.
+  pub fn from_metadata(metadata: &Metadata) -> Self {
+    Self {
+      make:          metadata.make.clone(),
+      model:         metadata.model.clone(),
+      serial_number: metadata.get("SerialNumber"),
+      host_computer: metadata.get("HostComputer"),
+    }
+  }
+}
+
+impl Display for Camera {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} {}",
+      self.make.as_deref().unwrap_or("(unknown make)"),
+      self.model.as_deref().unwrap_or("(unknown model)")
+    )?;
+
+    if let Some(serial_number) = &self.serial_number {
+      write!(f, ", serial {serial_number}")?;
+    }
+    if let Some(host_computer) = &self.host_computer {
+      write!(f, ", host {host_computer}")?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test_find_camera_owner {
+  use super::*;
+  use crate::testing::*;
+
+  fn owner(owner: &str, model: &str, serial_number: Option<&str>) -> CameraOwner {
+    CameraOwner {
+      owner: owner.to_string(),
+      model: Some(model.to_string()),
+      serial_number: serial_number.map(str::to_string),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn prefers_most_specific() {
+    let owners = [owner("A", "R5", None), owner("B", "R5", Some("1"))];
+
+    assert_eq!(
+      find_camera_owner(&owners, &metadata!("Model": "r5", "SerialNumber": "1")),
+      OwnerMatch::Owner("B")
+    );
+    assert_eq!(
+      find_camera_owner(&owners, &metadata!("Model": "R5", "SerialNumber": "2")),
+      OwnerMatch::Owner("A")
+    );
+    assert_eq!(
+      find_camera_owner(&owners, &metadata!("Model": "R6")),
+      OwnerMatch::None
+    );
+  }
+
+  #[test]
+  fn reports_shared_cameras() {
+    let owners = [owner("A", "R5", None), owner("B", "R5", None)];
+
+    assert_eq!(
+      find_camera_owner(&owners, &metadata!("Model": "R5")),
+      OwnerMatch::Ambiguous(vec!["A", "B"])
+    );
+  }
+
+  #[test]
+  fn ignores_entries_without_criteria() {
+    let owners = [CameraOwner {
+      owner: "A".to_string(),
+      ..Default::default()
+    }];
+
+    assert_eq!(find_camera_owner(&owners, &metadata!()), OwnerMatch::None);
+  }
+}
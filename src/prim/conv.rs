@@ -1,6 +1,20 @@
-use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Offset, TimeZone};
+use std::sync::LazyLock;
+
+use chrono::{DateTime, FixedOffset, Local, LocalResult, NaiveDateTime, Offset, TimeZone};
 use chrono_tz::Tz;
 use regex::Regex;
+use tzf_rs::{Finder, r#gen::tzf::v1::Timezones};
+
+/// Time zone boundaries, including over the oceans. This is large, so is only
+/// loaded on first use.
+static TIME_ZONE_FINDER: LazyLock<Finder> = LazyLock::new(|| {
+  Finder::from_pb(
+    Timezones::try_from(
+      include_bytes!("../../third_party/tzf-rel/combined-with-oceans.bin").to_vec(),
+    )
+    .unwrap(),
+  )
+});
 
 /// Converts degrees, minutes and seconds to latitude and longitude.
 pub fn dms_to_lat_lon(deg: f32, min: f32, sec: f32) -> f32 {
@@ -8,14 +22,44 @@ pub fn dms_to_lat_lon(deg: f32, min: f32, sec: f32) -> f32 {
 }
 
 /// Determines the time zone offset at a given date and time, within the named
-/// time zone.
-pub fn get_offset_for_time_zone(date_time: &NaiveDateTime, time_zone: &str) -> FixedOffset {
-  time_zone
+/// time zone. Where the time is ambiguous (i.e. clocks were set back), this is
+/// the earlier offset. Errors if the time zone is unknown, or the time does not
+/// exist (i.e. clocks were set forward).
+pub fn get_offset_for_time_zone(
+  date_time: &NaiveDateTime,
+  time_zone: &str,
+) -> Result<FixedOffset, String> {
+  get_offsets_for_time_zone(date_time, time_zone).map(|offsets| offsets[0])
+}
+
+/// Determines the possible time zone offsets at a given date and time, within
+/// the named time zone: one, or where the time is ambiguous, both (earlier
+/// first). Errors as `get_offset_for_time_zone`.
+pub fn get_offsets_for_time_zone(
+  date_time: &NaiveDateTime,
+  time_zone: &str,
+) -> Result<Vec<FixedOffset>, String> {
+  let offsets = match time_zone
     .parse::<Tz>()
-    .unwrap()
+    .map_err(|e| format!("Unable to parse time zone `{time_zone}` ({e})."))?
     .offset_from_local_datetime(date_time)
-    .unwrap()
-    .fix()
+  {
+    LocalResult::Single(o) => vec![o.fix()],
+    LocalResult::Ambiguous(earlier, later) => vec![earlier.fix(), later.fix()],
+    LocalResult::None => {
+      return Err(format!(
+        "`{date_time}` does not exist in time zone `{time_zone}`."
+      ));
+    }
+  };
+
+  Ok(offsets)
+}
+
+/// Gets the name of the time zone (e.g. `America/Los_Angeles`) at a latitude
+/// and longitude.
+pub fn get_time_zone_name(lat: f32, lon: f32) -> &'static str {
+  TIME_ZONE_FINDER.get_tz_name(f64::from(lon), f64::from(lat))
 }
 
 /// Gets the `FixedOffset` for the computer's time zone at a given date & time.
@@ -23,6 +67,15 @@ pub fn get_offset_local(date_time: &NaiveDateTime) -> FixedOffset {
   *Local.from_local_datetime(date_time).unwrap().offset()
 }
 
+/// Converts a date & time with an optional offset to the computer's time zone,
+/// as `ExifTool` does when formatting dates (e.g. when renaming files). Without
+/// an offset, the date & time is already local.
+pub fn to_local_time(date_time: &NaiveDateTime, offset: Option<FixedOffset>) -> NaiveDateTime {
+  offset.map_or(*date_time, |o| {
+    Local.from_utc_datetime(&(*date_time - o)).naive_local()
+  })
+}
+
 /// Converts a date & time string to a `NaiveDateTime` and an optional
 /// `FixedOffset`. Assumes RFC3339 format, but optionally without a time zone
 /// offset.
@@ -63,7 +116,7 @@ mod test_get_offset_for_time_zone {
       .and_then(|d| d.and_hms_opt(1, 59, 59))
       .unwrap();
 
-    let offset = get_offset_for_time_zone(&date, "America/Los_Angeles");
+    let offset = get_offset_for_time_zone(&date, "America/Los_Angeles").unwrap();
 
     assert_eq!(offset, FixedOffset::east_opt(-8 * 3600).unwrap());
   }
@@ -74,10 +127,79 @@ mod test_get_offset_for_time_zone {
       .and_then(|d| d.and_hms_opt(3, 0, 0))
       .unwrap();
 
-    let offset = get_offset_for_time_zone(&date, "America/Los_Angeles");
+    let offset = get_offset_for_time_zone(&date, "America/Los_Angeles").unwrap();
+
+    assert_eq!(offset, FixedOffset::east_opt(-7 * 3600).unwrap());
+  }
+
+  #[test]
+  fn returns_earlier_offset_if_ambiguous() {
+    let date = NaiveDate::from_ymd_opt(2025, 11, 2)
+      .and_then(|d| d.and_hms_opt(1, 30, 0))
+      .unwrap();
+
+    let offset = get_offset_for_time_zone(&date, "America/Los_Angeles").unwrap();
 
     assert_eq!(offset, FixedOffset::east_opt(-7 * 3600).unwrap());
   }
+
+  #[test]
+  fn returns_both_offsets_if_ambiguous() {
+    let date = NaiveDate::from_ymd_opt(2025, 11, 2)
+      .and_then(|d| d.and_hms_opt(1, 30, 0))
+      .unwrap();
+
+    let offsets = get_offsets_for_time_zone(&date, "America/Los_Angeles").unwrap();
+
+    assert_eq!(offsets, [
+      FixedOffset::east_opt(-7 * 3600).unwrap(),
+      FixedOffset::east_opt(-8 * 3600).unwrap(),
+    ]);
+  }
+
+  #[test]
+  fn errors_if_time_does_not_exist() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 9)
+      .and_then(|d| d.and_hms_opt(2, 30, 0))
+      .unwrap();
+
+    assert!(get_offset_for_time_zone(&date, "America/Los_Angeles").is_err());
+  }
+
+  #[test]
+  fn errors_if_time_zone_unknown() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 9)
+      .and_then(|d| d.and_hms_opt(12, 0, 0))
+      .unwrap();
+
+    assert!(get_offset_for_time_zone(&date, "Nowhere/Nothing").is_err());
+  }
+}
+
+#[cfg(test)]
+mod test_to_local_time {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn converts_offset_to_local() {
+    let date_time = make_date_naive(2000, 1, 1, 12, 30, 1, 50);
+    let offset = get_offset_local(&date_time);
+    let pst = FixedOffset::east_opt(-8 * 3600).unwrap();
+
+    assert_eq!(to_local_time(&date_time, Some(offset)), date_time);
+    assert_eq!(
+      to_local_time(&(date_time - offset + pst), Some(pst)),
+      date_time
+    );
+  }
+
+  #[test]
+  fn keeps_time_without_offset() {
+    let date_time = make_date_naive(2000, 1, 1, 12, 30, 1, 50);
+
+    assert_eq!(to_local_time(&date_time, None), date_time);
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,272 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Copyright policy, deriving rights tags from each file's creator and capture
+//! year.
+
+use std::collections::HashMap;
+
+use chrono::Datelike;
+use serde::Deserialize;
+
+use super::Metadata;
+
+/// Template for `Copyright` if none is configured.
+const DEFAULT_TEMPLATE: &str = "Copyright {creator}";
+
+/// Rights tags set by a copyright policy, besides `Copyright`, all within the
+/// XMP rights management schema.
+const XMP_RIGHTS_TAGS: [&str; 3] = ["UsageTerms", "WebStatement", "Marked"];
+
+/// Rights to set for files, all optional. Unset values fall back to the
+/// defaults (see `CopyrightConfig`).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CopyrightTerms {
+  /// Template for `Copyright`, in which `{creator}` and `{year}` (of capture)
+  /// are replaced.
+  pub template:      Option<String>,
+  /// Value for `XMP-xmpRights:UsageTerms` (e.g. a license).
+  pub usage_terms:   Option<String>,
+  /// Value for `XMP-xmpRights:WebStatement` (e.g. a license URL).
+  pub web_statement: Option<String>,
+  /// Value for `XMP-xmpRights:Marked` (i.e. whether rights-managed).
+  pub marked:        Option<bool>,
+}
+
+/// Copyright policy: default terms, overridden per creator (`Creator` tag).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CopyrightConfig {
+  #[serde(flatten)]
+  pub terms:    CopyrightTerms,
+  pub creators: HashMap<String, CopyrightTerms>,
+}
+
+impl CopyrightConfig {
+  /// Gets the rights tags and values expected for `metadata`, from its
+  /// `Creator` and capture year. This is empty without `Creator`, and leaves
+  /// out `Copyright` if its template needs the year but there is no capture
+  /// date.
+  pub fn get_expected(&self, metadata: &Metadata) -> Vec<(&'static str, String)> {
+    let Some(creator) = metadata.creator.as_deref() else {
+      return Vec::new();
+    };
+
+    let terms = self.creators.get(creator);
+    let year = metadata
+      .get_date_time_original()
+      .map(|(date_time, _)| date_time.year().to_string());
+
+    let template = terms
+      .and_then(|t| t.template.as_deref())
+      .or(self.terms.template.as_deref())
+      .unwrap_or(DEFAULT_TEMPLATE);
+    let usage_terms = terms
+      .and_then(|t| t.usage_terms.clone())
+      .or(self.terms.usage_terms.clone());
+    let web_statement = terms
+      .and_then(|t| t.web_statement.clone())
+      .or(self.terms.web_statement.clone());
+    let marked = terms.and_then(|t| t.marked).or(self.terms.marked);
+
+    let copyright = render(template, creator, year.as_deref()).map(|c| ("Copyright", c));
+    let rights = XMP_RIGHTS_TAGS.into_iter().zip([
+      usage_terms,
+      web_statement,
+      marked.map(|m| if m { "True" } else { "False" }.to_string()),
+    ]);
+
+    copyright
+      .into_iter()
+      .chain(rights.filter_map(|(tag, value)| Some((tag, value?))))
+      .collect()
+  }
+
+  /// Gets the rights tags and values enforced on existing tags for `metadata`
+  /// (see `get_expected`). This leaves out `Copyright` unless a template is
+  /// configured for its `Creator`, so custom copyrights are kept by default.
+  pub fn get_enforced(&self, metadata: &Metadata) -> Vec<(&'static str, String)> {
+    let has_template = self.terms.template.is_some()
+      || metadata
+        .creator
+        .as_deref()
+        .and_then(|c| self.creators.get(c))
+        .is_some_and(|t| t.template.is_some());
+
+    self
+      .get_expected(metadata)
+      .into_iter()
+      .filter(|(tag, _)| has_template || *tag != "Copyright")
+      .collect()
+  }
+}
+
+/// Gets which of `expected` (see `CopyrightConfig::get_expected`) differ from,
+/// or are missing in, `metadata`.
+pub fn find_rights_mismatches<'a>(
+  metadata: &Metadata,
+  expected: &'a [(&'static str, String)],
+) -> Vec<&'a (&'static str, String)> {
+  expected
+    .iter()
+    .filter(|(tag, value)| {
+      !metadata
+        .get(tag)
+        .is_some_and(|v| v == *value || (*tag == "Marked" && v.eq_ignore_ascii_case(value)))
+    })
+    .collect()
+}
+
+/// Gets the `ExifTool` argument to write `value` to the rights `tag`.
+pub fn get_rights_write_arg(tag: &str, value: &str) -> String {
+  if XMP_RIGHTS_TAGS.contains(&tag) {
+    format!("-XMP-xmpRights:{tag}={value}")
+  } else {
+    format!("-{tag}={value}")
+  }
+}
+
+/// Fills in `template` with `creator` and `year`, unless it needs the year but
+/// there is none.
+fn render(template: &str, creator: &str, year: Option<&str>) -> Option<String> {
+  let rendered = template.replace("{creator}", creator);
+
+  if rendered.contains("{year}") {
+    Some(rendered.replace("{year}", year?))
+  } else {
+    Some(rendered)
+  }
+}
+
+#[cfg(test)]
+mod test_get_expected {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn defaults_to_creator() {
+    let metadata = metadata!(
+      "Creator": "Creator",
+    );
+
+    assert_eq!(CopyrightConfig::default().get_expected(&metadata), [(
+      "Copyright",
+      "Copyright Creator".to_string()
+    )]);
+  }
+
+  #[test]
+  fn applies_creator_overrides() {
+    let config = serde_json::from_value::<CopyrightConfig>(serde_json::json!({
+      "template": "© {year} {creator}",
+      "usage_terms": "All rights reserved.",
+      "marked": true,
+      "creators": {
+        "Other": { "template": "© {year} Studio", "marked": false },
+      },
+    }))
+    .unwrap();
+
+    let metadata = metadata!(
+      "Creator": "Creator",
+      "DateTimeOriginal": "2000-01-01T00:00:00",
+    );
+    assert_eq!(config.get_expected(&metadata), [
+      ("Copyright", "© 2000 Creator".to_string()),
+      ("UsageTerms", "All rights reserved.".to_string()),
+      ("Marked", "True".to_string()),
+    ]);
+
+    let metadata = metadata!(
+      "Creator": "Other",
+      "DateTimeOriginal": "2000-01-01T00:00:00",
+    );
+    assert_eq!(config.get_expected(&metadata), [
+      ("Copyright", "© 2000 Studio".to_string()),
+      ("UsageTerms", "All rights reserved.".to_string()),
+      ("Marked", "False".to_string()),
+    ]);
+  }
+
+  #[test]
+  fn skips_copyright_without_year() {
+    let config = serde_json::from_value::<CopyrightConfig>(serde_json::json!({
+      "template": "© {year} {creator}",
+    }))
+    .unwrap();
+
+    let metadata = metadata!(
+      "Creator": "Creator",
+    );
+
+    assert!(config.get_expected(&metadata).is_empty());
+  }
+
+  #[test]
+  fn is_empty_without_creator() {
+    assert!(
+      CopyrightConfig::default()
+        .get_expected(&metadata!())
+        .is_empty()
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_get_enforced {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn skips_copyright_without_template() {
+    let config = serde_json::from_value::<CopyrightConfig>(serde_json::json!({
+      "usage_terms": "All rights reserved.",
+      "creators": {
+        "Other": { "template": "© {creator}" },
+      },
+    }))
+    .unwrap();
+
+    let metadata = metadata!(
+      "Creator": "Creator",
+    );
+    assert_eq!(config.get_enforced(&metadata), [(
+      "UsageTerms",
+      "All rights reserved.".to_string()
+    )]);
+
+    let metadata = metadata!(
+      "Creator": "Other",
+    );
+    assert_eq!(config.get_enforced(&metadata), [
+      ("Copyright", "© Other".to_string()),
+      ("UsageTerms", "All rights reserved.".to_string()),
+    ]);
+  }
+}
+
+#[cfg(test)]
+mod test_find_rights_mismatches {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn finds_differing_and_missing_tags() {
+    let metadata = metadata!(
+      "Copyright": "Copyright Other",
+      "Marked": true,
+      "WebStatement": "https://example.com",
+    );
+    let expected = [
+      ("Copyright", "Copyright Creator".to_string()),
+      ("UsageTerms", "All rights reserved.".to_string()),
+      ("WebStatement", "https://example.com".to_string()),
+      ("Marked", "True".to_string()),
+    ];
+
+    assert_eq!(find_rights_mismatches(&metadata, &expected), [
+      &expected[0],
+      &expected[1]
+    ]);
+  }
+}
@@ -0,0 +1,142 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Types for Apple Photos edit groups, which consist of an original media file,
+//! its edited renders (e.g. `IMG_E1234.HEIC`) and adjustments (`.AAE` files).
+
+use std::{path::PathBuf, sync::LazyLock};
+
+use regex::Regex;
+
+use super::file_map::Handle;
+use crate::prim::Media;
+
+/// Suffix for an edited render's stem, once named for its original.
+pub const EDITED_SUFFIX: &str = "_E";
+
+/// Suffix for the stem of adjustments to the original (`IMG_O1234.AAE`), once
+/// named for it.
+pub const ADJUSTMENTS_ORIGINAL_SUFFIX: &str = "_O";
+
+/// Matches the stem of an edited render, as exported (`IMG_E1234`) or named
+/// for its original in the catalog (`000101_000000000_E`).
+static EDITED_STEM: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"^(?:(.+_)E(\d+)|(.+)_E)$").unwrap());
+
+/// Matches the stem of adjustments to the original, as exported (`IMG_O1234`)
+/// or named for it in the catalog (`000101_000000000_O`).
+static ADJUSTMENTS_ORIGINAL_STEM: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"^(?:(.+_)O(\d+)|(.+)_O)$").unwrap());
+
+/// Stores the edited renders and adjustments of an original media file.
+#[derive(Default)]
+pub struct EditGroup {
+  edits:       Vec<Handle<Media>>,
+  adjustments: Vec<PathBuf>,
+}
+
+impl EditGroup {
+  /// Extract all edited render `Handle`s.
+  pub fn drain_edits(&mut self) -> impl Iterator<Item = Handle<Media>> + '_ {
+    self.edits.drain(..)
+  }
+
+  /// Extract all adjustments files.
+  pub fn drain_adjustments(&mut self) -> impl Iterator<Item = PathBuf> + '_ {
+    self.adjustments.drain(..)
+  }
+
+  /// Whether this has any edited renders.
+  pub fn has_edits(&self) -> bool {
+    !self.edits.is_empty()
+  }
+
+  /// Link edited render via `Handle`.
+  pub fn insert_edit(&mut self, handle: Handle<Media>) {
+    self.edits.push(handle);
+  }
+
+  /// Link adjustments file by path.
+  pub fn insert_adjustments(&mut self, path: PathBuf) {
+    self.adjustments.push(path);
+  }
+
+  /// Iterate over all adjustments files, without removing them.
+  pub fn iter_adjustments(&self) -> impl Iterator<Item = &PathBuf> {
+    self.adjustments.iter()
+  }
+}
+
+/// Gets the stem of the original an edited render's `stem` belongs to, if it
+/// is one.
+pub fn get_edited_original_stem(stem: &str) -> Option<String> {
+  parse_stem(&EDITED_STEM, stem)
+}
+
+/// Gets the stem of the original an adjustments file's `stem` belongs to,
+/// alongside the suffix to keep when naming it for the original.
+pub fn get_adjustments_original_stem(stem: &str) -> (String, &'static str) {
+  match parse_stem(&ADJUSTMENTS_ORIGINAL_STEM, stem) {
+    Some(original) => (original, ADJUSTMENTS_ORIGINAL_SUFFIX),
+    None => (stem.to_string(), ""),
+  }
+}
+
+/// Joins the exported (prefix and number) or catalog (stem) captures of `re`.
+fn parse_stem(re: &Regex, stem: &str) -> Option<String> {
+  let caps = re.captures(stem)?;
+
+  match (caps.get(1), caps.get(2), caps.get(3)) {
+    (Some(prefix), Some(number), _) => Some(format!("{}{}", prefix.as_str(), number.as_str())),
+    (_, _, Some(stem)) => Some(stem.as_str().to_string()),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod test_get_edited_original_stem {
+  use super::*;
+
+  #[test]
+  fn parses_exported_and_catalog_names() {
+    assert_eq!(
+      get_edited_original_stem("IMG_E1234").as_deref(),
+      Some("IMG_1234")
+    );
+    assert_eq!(
+      get_edited_original_stem("000101_000000000_E").as_deref(),
+      Some("000101_000000000")
+    );
+    assert_eq!(
+      get_edited_original_stem("000101_000000000_a_E").as_deref(),
+      Some("000101_000000000_a")
+    );
+  }
+
+  #[test]
+  fn ignores_originals() {
+    assert_eq!(get_edited_original_stem("IMG_1234"), None);
+    assert_eq!(get_edited_original_stem("000101_000000000"), None);
+    assert_eq!(get_edited_original_stem("EDIT"), None);
+  }
+}
+
+#[cfg(test)]
+mod test_get_adjustments_original_stem {
+  use super::*;
+
+  #[test]
+  fn parses_exported_and_catalog_names() {
+    assert_eq!(
+      get_adjustments_original_stem("IMG_1234"),
+      ("IMG_1234".to_string(), "")
+    );
+    assert_eq!(
+      get_adjustments_original_stem("IMG_O1234"),
+      ("IMG_1234".to_string(), "_O")
+    );
+    assert_eq!(
+      get_adjustments_original_stem("000101_000000000_O"),
+      ("000101_000000000".to_string(), "_O")
+    );
+  }
+}
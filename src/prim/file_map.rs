@@ -151,18 +151,6 @@ impl<T> FileMap<T> {
   pub fn iter_entries_mut(&mut self) -> impl Iterator<Item = &mut Option<T>> {
     self.data.iter_mut().filter(|o| o.is_some())
   }
-
-  /// Mutably iterates over all existing entries, alongside their handles,
-  /// allowing the caller to remove them. Note: The caller should *not* use
-  /// this to overwrite the entry with a different file.
-  pub fn iter_entries_mut_indexed(&mut self) -> impl Iterator<Item = (Handle<T>, &mut Option<T>)> {
-    self
-      .data
-      .iter_mut()
-      .enumerate()
-      .filter(|(_, o)| o.is_some())
-      .map(|(i, o)| (i.into(), o))
-  }
 }
 
 impl<T> Default for FileMap<T> {
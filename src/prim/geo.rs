@@ -0,0 +1,316 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Export of media file locations as `GeoJSON` or KML, optionally clustered,
+//! with per-day tracks.
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::Write,
+  path::PathBuf,
+};
+
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime};
+use serde_json::{Value, json};
+
+/// Mean radius of the Earth, in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Location of a media file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoPoint {
+  pub path: PathBuf,
+  pub lat:  f32,
+  pub lon:  f32,
+  /// `DateTimeOriginal`, if set.
+  pub time: Option<(NaiveDateTime, Option<FixedOffset>)>,
+  pub city: Option<String>,
+}
+
+/// A feature to export.
+#[derive(Debug, PartialEq)]
+pub enum GeoFeature<'a> {
+  /// A media file, or with clustering, several within the cluster radius (at
+  /// their mean location).
+  Point {
+    lat:    f64,
+    lon:    f64,
+    points: Vec<&'a GeoPoint>,
+  },
+  /// Media files taken on one day, ordered by time.
+  Track {
+    date:   NaiveDate,
+    points: Vec<&'a GeoPoint>,
+  },
+}
+
+/// Gets the features to export for `points`. If `cluster_radius` (in meters)
+/// is `Some`, points within it of the first point of a cluster are merged into
+/// it. If `tracks`, adds a track for each day with multiple timed points.
+pub fn get_geo_features(
+  points: &[GeoPoint],
+  cluster_radius: Option<f64>,
+  tracks: bool,
+) -> Vec<GeoFeature<'_>> {
+  let mut features = match cluster_radius {
+    Some(radius) => cluster(points, radius),
+    None => points
+      .iter()
+      .map(|p| GeoFeature::Point {
+        lat:    f64::from(p.lat),
+        lon:    f64::from(p.lon),
+        points: vec![p],
+      })
+      .collect(),
+  };
+
+  if tracks {
+    let mut days = BTreeMap::<_, Vec<_>>::new();
+    for point in points {
+      if let Some((time, _)) = point.time {
+        days.entry(time.date()).or_default().push(point);
+      }
+    }
+
+    features.extend(days.into_iter().filter(|(_, points)| points.len() > 1).map(
+      |(date, mut points)| {
+        points.sort_by_key(|p| p.time.map(|(t, _)| t));
+        GeoFeature::Track { date, points }
+      },
+    ));
+  }
+
+  features
+}
+
+/// Writes `features` as a `GeoJSON` feature collection.
+pub fn to_geojson(features: &[GeoFeature]) -> Value {
+  let features = features
+    .iter()
+    .map(|feature| match feature {
+      GeoFeature::Point { lat, lon, points } => json!({
+        "type": "Feature",
+        "geometry": { "type": "Point", "coordinates": [lon, lat] },
+        "properties": get_point_properties(points),
+      }),
+      GeoFeature::Track { date, points } => json!({
+        "type": "Feature",
+        "geometry": {
+          "type": "LineString",
+          "coordinates": points.iter().map(|p| [p.lon, p.lat]).collect::<Vec<_>>(),
+        },
+        "properties": { "date": date.to_string(), "count": points.len() },
+      }),
+    })
+    .collect::<Vec<_>>();
+
+  json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Writes `features` as a KML document.
+pub fn to_kml(features: &[GeoFeature]) -> String {
+  let mut kml = String::from(concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n",
+    "<Document>\n",
+  ));
+
+  for feature in features {
+    match feature {
+      GeoFeature::Point { lat, lon, points } => {
+        let (name, description) = match points.as_slice() {
+          [point] => (
+            point.path.display().to_string(),
+            point.city.clone().unwrap_or_default(),
+          ),
+          _ => (
+            format!("{} files", points.len()),
+            points
+              .iter()
+              .map(|p| p.path.display().to_string())
+              .collect::<Vec<_>>()
+              .join("\n"),
+          ),
+        };
+
+        let time = match points.as_slice() {
+          [point] => point.time.map_or(String::new(), |t| {
+            format!("<TimeStamp><when>{}</when></TimeStamp>", format_time(t))
+          }),
+          _ => String::new(),
+        };
+
+        let _ = writeln!(
+          kml,
+          "<Placemark><name>{}</name><description>{}</description>{time}",
+          escape_xml(&name),
+          escape_xml(&description),
+        );
+        let _ = writeln!(
+          kml,
+          "<Point><coordinates>{lon},{lat}</coordinates></Point></Placemark>"
+        );
+      }
+      GeoFeature::Track { date, points } => {
+        let coordinates = points
+          .iter()
+          .map(|p| format!("{},{}", p.lon, p.lat))
+          .collect::<Vec<_>>()
+          .join(" ");
+
+        let _ = writeln!(kml, "<Placemark><name>{date}</name><LineString>");
+        let _ = writeln!(
+          kml,
+          "<coordinates>{coordinates}</coordinates></LineString></Placemark>"
+        );
+      }
+    }
+  }
+
+  kml.push_str("</Document>\n</kml>\n");
+  kml
+}
+
+/// Greedily clusters `points`, each joining the first cluster whose first
+/// point is within `radius` meters.
+fn cluster(points: &[GeoPoint], radius: f64) -> Vec<GeoFeature<'_>> {
+  let mut clusters = Vec::<Vec<&GeoPoint>>::new();
+
+  for point in points {
+    match clusters
+      .iter_mut()
+      .find(|c| get_distance(c[0], point) <= radius)
+    {
+      Some(cluster) => cluster.push(point),
+      None => clusters.push(vec![point]),
+    }
+  }
+
+  clusters
+    .into_iter()
+    .map(|points| {
+      #[allow(clippy::cast_precision_loss)]
+      let count = points.len() as f64;
+      GeoFeature::Point {
+        lat: points.iter().map(|p| f64::from(p.lat)).sum::<f64>() / count,
+        lon: points.iter().map(|p| f64::from(p.lon)).sum::<f64>() / count,
+        points,
+      }
+    })
+    .collect()
+}
+
+/// Gets the great-circle distance between `a` and `b`, in meters.
+fn get_distance(a: &GeoPoint, b: &GeoPoint) -> f64 {
+  let (lat_a, lat_b) = (f64::from(a.lat).to_radians(), f64::from(b.lat).to_radians());
+  let d_lat = lat_b - lat_a;
+  let d_lon = f64::from(b.lon - a.lon).to_radians();
+
+  let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+  2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+/// Gets the `GeoJSON` properties of a point feature of `points`: its path, time
+/// and city if a single file, else all paths, the time range and cities.
+fn get_point_properties(points: &[&GeoPoint]) -> Value {
+  if let [point] = points {
+    return json!({
+      "path": point.path,
+      "time": point.time.map(format_time),
+      "city": point.city,
+    });
+  }
+
+  let times = points.iter().filter_map(|p| p.time).collect::<Vec<_>>();
+
+  json!({
+    "count": points.len(),
+    "paths": points.iter().map(|p| &p.path).collect::<Vec<_>>(),
+    "from": times.iter().min_by_key(|(t, _)| *t).copied().map(format_time),
+    "to": times.iter().max_by_key(|(t, _)| *t).copied().map(format_time),
+    "cities": points.iter().filter_map(|p| p.city.as_deref()).collect::<BTreeSet<_>>(),
+  })
+}
+
+/// Formats `time` as ISO 8601, with its offset if known.
+fn format_time((time, offset): (NaiveDateTime, Option<FixedOffset>)) -> String {
+  match offset {
+    Some(offset) => format!("{}{offset}", time.format("%Y-%m-%dT%H:%M:%S")),
+    None => time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+  }
+}
+
+/// Escapes `text` for use in XML.
+fn escape_xml(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test_get_geo_features {
+  use super::*;
+
+  fn point(path: &str, lat: f32, lon: f32, time: &str) -> GeoPoint {
+    GeoPoint {
+      path: PathBuf::from(path),
+      lat,
+      lon,
+      time: Some((time.parse().unwrap(), None)),
+      city: None,
+    }
+  }
+
+  #[test]
+  fn clusters_nearby_points() {
+    let points = [
+      point("a.jpg", 35.0, 135.0, "2023-05-01T12:00:00"),
+      point("b.jpg", 35.0001, 135.0, "2023-05-01T12:01:00"),
+      point("c.jpg", 36.0, 135.0, "2023-05-01T12:02:00"),
+    ];
+
+    let features = get_geo_features(&points, Some(100.0), false);
+
+    assert_eq!(features.len(), 2);
+    assert!(matches!(&features[0], GeoFeature::Point { points, .. } if points.len() == 2));
+  }
+
+  #[test]
+  fn orders_tracks_by_time() {
+    let points = [
+      point("b.jpg", 36.0, 135.0, "2023-05-01T13:00:00"),
+      point("a.jpg", 35.0, 135.0, "2023-05-01T12:00:00"),
+      point("c.jpg", 37.0, 135.0, "2023-05-02T12:00:00"),
+    ];
+
+    let features = get_geo_features(&points, None, true);
+
+    assert_eq!(features.len(), 4);
+    assert_eq!(features[3], GeoFeature::Track {
+      date:   NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+      points: vec![&points[1], &points[0]],
+    });
+  }
+}
+
+#[cfg(test)]
+mod test_to_kml {
+  use super::*;
+
+  #[test]
+  fn escapes_text() {
+    let points = [GeoPoint {
+      path: PathBuf::from("a&b.jpg"),
+      lat:  35.0,
+      lon:  135.0,
+      time: None,
+      city: Some("Kyoto".to_string()),
+    }];
+
+    let kml = to_kml(&get_geo_features(&points, None, false));
+
+    assert!(kml.contains("<name>a&amp;b.jpg</name><description>Kyoto</description>"));
+    assert!(kml.contains("<coordinates>135,35</coordinates>"));
+  }
+}
@@ -0,0 +1,297 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Structural checks that media files are complete (e.g. not truncated by a
+//! failing SD card).
+
+use std::{
+  fs::File,
+  io::{BufReader, Read, Seek, SeekFrom},
+  path::Path,
+};
+
+/// Box types (from ISO/IEC 14496-12 and `QuickTime`) whose payloads are only
+/// child boxes, which are checked recursively.
+const CONTAINER_BOXES: [&[u8; 4]; 7] = [
+  b"moov", b"trak", b"mdia", b"minf", b"stbl", b"edts", b"dinf",
+];
+
+/// Problems found when checking a media file's integrity.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Integrity {
+  /// Problems meaning the file is likely damaged.
+  pub errors:   Vec<String>,
+  /// Problems which may not affect the file (e.g. non-standard metadata).
+  pub warnings: Vec<String>,
+}
+
+/// Checks the structure of the file at `path`, based on its `ExifTool`
+/// `FileType`. File types without a structural check always pass.
+pub fn check_structure(path: impl AsRef<Path>, file_type: &str) -> Result<(), String> {
+  let check = match file_type {
+    "JPEG" => check_jpeg,
+    "HEIC" | "HEIF" | "AVIF" | "MOV" | "MP4" | "M4V" => check_iso_bmff,
+    _ => return Ok(()),
+  };
+
+  let file = File::open(&path).map_err(|e| format!("Failed to open file ({e})."))?;
+
+  check(&mut BufReader::new(file))
+}
+
+/// Checks that a JPEG has all of its segments, and that its image data ends
+/// with an End of Image (EOI) marker. Data after the EOI (e.g. a Motion Photo
+/// video) is ignored.
+fn check_jpeg<R: Read + Seek>(reader: &mut R) -> Result<(), String> {
+  let mut marker = [0u8; 2];
+
+  reader
+    .read_exact(&mut marker)
+    .map_err(|_| "Missing JPEG Start of Image marker.")?;
+  if marker != [0xff, 0xd8] {
+    return Err("Missing JPEG Start of Image marker.".to_string());
+  }
+
+  // Skip over all segments before the image data, which have lengths.
+  loop {
+    reader
+      .read_exact(&mut marker)
+      .map_err(|_| "JPEG is truncated before image data.")?;
+    if marker[0] != 0xff {
+      return Err("Invalid JPEG segment marker.".to_string());
+    }
+
+    let mut length = [0u8; 2];
+    reader
+      .read_exact(&mut length)
+      .map_err(|_| "JPEG is truncated before image data.")?;
+    let length = u16::from_be_bytes(length);
+
+    skip(reader, u64::from(length).saturating_sub(2))
+      .map_err(|()| format!("JPEG segment 0x{:02X} is truncated.", marker[1]))?;
+
+    // Start of Scan, after which is the entropy-coded image data.
+    if marker[1] == 0xda {
+      break;
+    }
+  }
+
+  // Within image data, 0xFF bytes are followed by 0x00 or a restart marker, so
+  // the first 0xFFD9 is the EOI.
+  let mut previous = 0u8;
+  let mut buffer = [0u8; 4096];
+  loop {
+    let count = reader
+      .read(&mut buffer)
+      .map_err(|e| format!("Failed to read JPEG ({e})."))?;
+    if count == 0 {
+      break;
+    }
+
+    for &byte in &buffer[..count] {
+      if previous == 0xff && byte == 0xd9 {
+        return Ok(());
+      }
+      previous = byte;
+    }
+  }
+
+  Err("JPEG is missing End of Image marker (likely truncated).".to_string())
+}
+
+/// Checks that every box in an ISO Base Media File (e.g. HEIC, MOV, MP4) fits
+/// within the file, recursing into container boxes.
+fn check_iso_bmff<R: Read + Seek>(reader: &mut R) -> Result<(), String> {
+  let len = reader
+    .seek(SeekFrom::End(0))
+    .map_err(|e| format!("Failed to read file ({e})."))?;
+  reader
+    .seek(SeekFrom::Start(0))
+    .map_err(|e| format!("Failed to read file ({e})."))?;
+
+  if len == 0 {
+    return Err("File is empty.".to_string());
+  }
+
+  check_boxes(reader, 0, len)
+}
+
+/// Checks the boxes between `start` and `end` in `reader`.
+fn check_boxes<R: Read + Seek>(reader: &mut R, start: u64, end: u64) -> Result<(), String> {
+  let mut pos = start;
+
+  while pos < end {
+    if end - pos < 8 {
+      return Err(format!("Incomplete box header at byte {pos}."));
+    }
+
+    reader
+      .seek(SeekFrom::Start(pos))
+      .map_err(|e| format!("Failed to read file ({e})."))?;
+
+    let mut header = [0u8; 8];
+    reader
+      .read_exact(&mut header)
+      .map_err(|e| format!("Failed to read box header at byte {pos} ({e})."))?;
+
+    let box_type = [header[4], header[5], header[6], header[7]];
+    let name = String::from_utf8_lossy(&box_type);
+
+    let (header_len, size) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]])
+    {
+      // Box extends to the end of its parent.
+      0 => (8, end - pos),
+      // Size is in the following 64 bits.
+      1 => {
+        let mut size = [0u8; 8];
+        reader
+          .read_exact(&mut size)
+          .map_err(|_| format!("Box `{name}` is truncated."))?;
+        (16, u64::from_be_bytes(size))
+      }
+      size => (8, u64::from(size)),
+    };
+
+    if size < header_len {
+      return Err(format!("Box `{name}` has invalid size ({size})."));
+    }
+
+    if pos + size > end {
+      return Err(format!(
+        "Box `{name}` extends {} byte(s) past its parent (likely truncated).",
+        pos + size - end
+      ));
+    }
+
+    if CONTAINER_BOXES.contains(&&box_type) {
+      check_boxes(reader, pos + header_len, pos + size)?;
+    }
+
+    pos += size;
+  }
+
+  Ok(())
+}
+
+/// Skips `count` bytes in `reader`, failing if there are not enough.
+fn skip<R: Read + Seek>(reader: &mut R, count: u64) -> Result<(), ()> {
+  let pos = reader.stream_position().map_err(|_| ())?;
+  let len = reader.seek(SeekFrom::End(0)).map_err(|_| ())?;
+
+  if pos + count > len {
+    return Err(());
+  }
+
+  reader.seek(SeekFrom::Start(pos + count)).map_err(|_| ())?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_check_jpeg {
+  use std::io::Cursor;
+
+  use super::*;
+
+  /// A minimal JPEG: SOI, an APP0 segment, SOS, image data and EOI.
+  fn jpeg() -> Vec<u8> {
+    let mut bytes = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00];
+    bytes.extend([0xff, 0xda, 0x00, 0x03, 0x00]);
+    bytes.extend([0x12, 0xff, 0x00, 0x34, 0xff, 0xd0, 0x56]);
+    bytes.extend([0xff, 0xd9]);
+    bytes
+  }
+
+  #[test]
+  fn accepts_complete_jpeg() {
+    assert!(check_jpeg(&mut Cursor::new(jpeg())).is_ok());
+  }
+
+  #[test]
+  fn accepts_data_after_eoi() {
+    let mut bytes = jpeg();
+    bytes.extend(b"trailing video");
+
+    assert!(check_jpeg(&mut Cursor::new(bytes)).is_ok());
+  }
+
+  #[test]
+  fn errors_if_missing_eoi() {
+    let mut bytes = jpeg();
+    bytes.truncate(bytes.len() - 2);
+
+    assert!(
+      check_jpeg(&mut Cursor::new(bytes))
+        .unwrap_err()
+        .contains("End of Image")
+    );
+  }
+
+  #[test]
+  fn errors_if_segment_truncated() {
+    let bytes = vec![0xff, 0xd8, 0xff, 0xe1, 0x10, 0x00, 0x00];
+
+    assert!(
+      check_jpeg(&mut Cursor::new(bytes))
+        .unwrap_err()
+        .contains("truncated")
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_check_iso_bmff {
+  use std::io::Cursor;
+
+  use super::*;
+
+  fn make_box(box_type: [u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut bytes = u32::try_from(payload.len() + 8)
+      .unwrap()
+      .to_be_bytes()
+      .to_vec();
+    bytes.extend(&box_type);
+    bytes.extend(payload);
+    bytes
+  }
+
+  fn mov() -> Vec<u8> {
+    let mut bytes = make_box(*b"ftyp", b"qt  ");
+    bytes.extend(make_box(
+      *b"moov",
+      &make_box(*b"trak", &make_box(*b"tkhd", &[0; 4])),
+    ));
+    bytes.extend(make_box(*b"mdat", &[0; 16]));
+    bytes
+  }
+
+  #[test]
+  fn accepts_complete_file() {
+    assert!(check_iso_bmff(&mut Cursor::new(mov())).is_ok());
+  }
+
+  #[test]
+  fn errors_if_truncated() {
+    let mut bytes = mov();
+    bytes.truncate(bytes.len() - 4);
+
+    assert!(
+      check_iso_bmff(&mut Cursor::new(bytes))
+        .unwrap_err()
+        .contains("Box `mdat` extends 4 byte(s)")
+    );
+  }
+
+  #[test]
+  fn errors_if_child_box_overflows_parent() {
+    let mut bytes = make_box(*b"ftyp", b"qt  ");
+    let mut trak = make_box(*b"trak", &[0; 4]);
+    trak[3] = 0xff;
+    bytes.extend(make_box(*b"moov", &trak));
+
+    assert!(
+      check_iso_bmff(&mut Cursor::new(bytes))
+        .unwrap_err()
+        .contains("Box `trak`")
+    );
+  }
+}
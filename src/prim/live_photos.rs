@@ -63,6 +63,16 @@ impl LivePhotoLinker {
     self.videos.len() > 1
   }
 
+  /// Iterate over all image `Handles`, without removing them.
+  pub fn iter_images(&self) -> impl Iterator<Item = Handle<Media>> + '_ {
+    self.images.iter().map(LivePhotoLinkMetadata::handle)
+  }
+
+  /// Iterate over all video `Handles`, without removing them.
+  pub fn iter_videos(&self) -> impl Iterator<Item = Handle<Media>> + '_ {
+    self.videos.iter().map(LivePhotoLinkMetadata::handle)
+  }
+
   /// Link image via `Handle`.
   pub fn insert_image(&mut self, handle: Handle<Media>, image: &Media) {
     self.images.push(LivePhotoLinkMetadata::new(handle, image));
@@ -73,6 +83,12 @@ impl LivePhotoLinker {
     self.videos.push(LivePhotoLinkMetadata::new(handle, video));
   }
 
+  /// Unlink `handle`, whether an image or video.
+  pub fn remove(&mut self, handle: Handle<Media>) {
+    self.images.retain(|i| i.handle() != handle);
+    self.videos.retain(|v| v.handle() != handle);
+  }
+
   /// Returns whether this `ContentIdentifier` has exactly one image and one
   /// video. If this is true, then this Live Photo is good and does not need
   /// deduplication.
@@ -80,6 +96,12 @@ impl LivePhotoLinker {
     self.images.len() == 1 && self.videos.len() == 1
   }
 
+  /// Returns whether this `ContentIdentifier` has images but no linked videos.
+  /// This generally means the video was lost when copying the Live Photo.
+  pub fn is_missing_video(&self) -> bool {
+    !self.images.is_empty() && self.videos.is_empty()
+  }
+
   /// Returns whether this `ContentIdentifier` has no linked images. If so, the
   /// linked video is likely leftover from a deleted Live Photo image, and
   /// should be deleted.
@@ -217,3 +239,26 @@ mod test_live_photo_link_metadata {
     assert_eq!(to_sorted_vec(dupes), [0, 1, 2]);
   }
 }
+
+#[cfg(test)]
+mod test_remove {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn unlinks_handle() {
+    let image = Media::new(metadata!(
+      "FileType": "HEIC",
+      "ContentIdentifier": "ID",
+    ))
+    .unwrap();
+
+    let mut link = LivePhotoLinker::default();
+    link.insert_image(0.into(), &image);
+    link.insert_image(1.into(), &image);
+
+    link.remove(0.into());
+
+    assert_eq!(link.iter_images().collect::<Vec<_>>(), [1.into()]);
+  }
+}
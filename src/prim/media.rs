@@ -13,7 +13,7 @@ use std::{
 
 use chrono::{DateTime, FixedOffset};
 
-use super::{Handle, LivePhotoID, Metadata, SidecarDupe, SidecarInitial};
+use super::{Handle, Integrity, LivePhotoID, Metadata, SidecarDupe, SidecarInitial};
 use crate::prim::FileCategory;
 
 static LIVE_PHOTO_IMAGE_EXTS: LazyLock<HashSet<&'static str>> =
@@ -21,6 +21,21 @@ static LIVE_PHOTO_IMAGE_EXTS: LazyLock<HashSet<&'static str>> =
 static LIVE_PHOTO_VIDEO_EXTS: LazyLock<HashSet<&'static str>> =
   LazyLock::new(|| HashSet::from(["MOV"]));
 
+/// Video file types based on the `QuickTime` container, whose dates are meant
+/// to be stored in UTC.
+static QUICKTIME_TYPES: LazyLock<HashSet<&'static str>> =
+  LazyLock::new(|| HashSet::from(["3G2", "3GP", "M4V", "MOV", "MP4"]));
+
+/// All other video file types.
+static OTHER_VIDEO_TYPES: LazyLock<HashSet<&'static str>> =
+  LazyLock::new(|| HashSet::from(["AVI", "M2TS", "MKV", "WEBM"]));
+
+/// File types of camera RAW formats.
+const RAW_TYPES: [&str; 20] = [
+  "3FR", "ARW", "CR2", "CR3", "CRW", "DCR", "DNG", "ERF", "IIQ", "KDC", "MEF", "MOS", "MRW", "NEF",
+  "NRW", "ORF", "PEF", "RAF", "RW2", "SRW",
+];
+
 /// Live Photos are comprised of an image file and a video.
 #[derive(PartialEq)]
 pub enum LivePhotoComponentType {
@@ -34,30 +49,52 @@ pub enum LivePhotoComponentType {
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Codec {
+  AV1,
   AVC,
+  H263,
   HEIC,
   HEVC,
   JPEG,
+  ProRes,
+  VP9,
   Other,
 }
 
 impl Codec {
   fn rank(self) -> u8 {
     match self {
-      Codec::HEIC | Codec::HEVC => u8::MAX,
-      Codec::JPEG | Codec::AVC => u8::MAX - 1,
+      Codec::HEIC | Codec::HEVC | Codec::AV1 => u8::MAX,
+      Codec::JPEG | Codec::AVC | Codec::VP9 | Codec::ProRes => u8::MAX - 1,
+      Codec::H263 => 1,
       Codec::Other => 0,
     }
   }
+
+  /// Maps a `QuickTime` `CompressorID` (i.e. sample description four-cc).
+  fn from_compressor_id(id: &str) -> Self {
+    match id {
+      "av01" => Codec::AV1,
+      "avc1" | "avc3" => Codec::AVC,
+      "h263" | "s263" => Codec::H263,
+      "hev1" | "hvc1" => Codec::HEVC,
+      "ap4h" | "ap4x" | "apch" | "apcn" | "apco" | "apcs" => Codec::ProRes,
+      "vp09" => Codec::VP9,
+      _ => Codec::Other,
+    }
+  }
 }
 
 impl Display for Codec {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
     match self {
+      Codec::AV1 => write!(f, "AV1"),
       Codec::AVC => write!(f, "AVC"),
+      Codec::H263 => write!(f, "H.263"),
       Codec::HEIC => write!(f, "HEIC"),
       Codec::HEVC => write!(f, "HEVC"),
       Codec::JPEG => write!(f, "JPEG"),
+      Codec::ProRes => write!(f, "ProRes"),
+      Codec::VP9 => write!(f, "VP9"),
       Codec::Other => write!(f, "Other"),
     }
   }
@@ -78,9 +115,10 @@ impl PartialOrd for Codec {
 /// Represents a single media file loaded from disk, including its metadata and,
 /// optionally, handles to associated sidecars.
 pub struct Media {
-  metadata: Metadata,
-  sidecar:  Option<Handle<SidecarInitial>>,
-  dupes:    HashSet<Handle<SidecarDupe>>,
+  metadata:  Metadata,
+  sidecar:   Option<Handle<SidecarInitial>>,
+  dupes:     HashSet<Handle<SidecarDupe>>,
+  integrity: Option<Integrity>,
 }
 
 impl Media {
@@ -90,6 +128,7 @@ impl Media {
       metadata,
       sidecar: None,
       dupes: HashSet::new(),
+      integrity: None,
     };
 
     if media.metadata.get_file_category() != FileCategory::Media {
@@ -150,11 +189,25 @@ impl Media {
     match self.metadata.file_type.as_str() {
       "JPEG" => Codec::JPEG,
       "HEIC" => Codec::HEIC,
-      "MOV" => match self.metadata.compressor_id.as_deref() {
-        Some("avc1") => Codec::AVC,
-        Some("hev1" | "hvc1") => Codec::HEVC,
+      // AVCHD only supports H.264.
+      "M2TS" => Codec::AVC,
+      "AVI" => match self.metadata.get("VideoCodec").as_deref() {
+        Some("avc1" | "H264" | "h264" | "X264" | "x264") => Codec::AVC,
+        Some("H263" | "h263") => Codec::H263,
+        _ => Codec::Other,
+      },
+      "MKV" | "WEBM" => match self.metadata.get("CodecID").as_deref() {
+        Some("V_AV1") => Codec::AV1,
+        Some("V_MPEG4/ISO/AVC") => Codec::AVC,
+        Some("V_MPEGH/ISO/HEVC") => Codec::HEVC,
+        Some("V_VP9") => Codec::VP9,
         _ => Codec::Other,
       },
+      t if QUICKTIME_TYPES.contains(t) => self
+        .metadata
+        .compressor_id
+        .as_deref()
+        .map_or(Codec::Other, Codec::from_compressor_id),
       _ => Codec::Other,
     }
   }
@@ -175,6 +228,11 @@ impl Media {
     }
   }
 
+  /// Returns the results of integrity checks, if they have been run.
+  pub fn get_integrity(&self) -> Option<&Integrity> {
+    self.integrity.as_ref()
+  }
+
   /// Returns loaded metadata.
   pub fn get_metadata(&self) -> &Metadata {
     &self.metadata
@@ -200,6 +258,22 @@ impl Media {
       .unwrap()
   }
 
+  /// Whether this is a video in a `QuickTime`-based container (e.g. MOV or
+  /// MP4).
+  pub fn is_quicktime(&self) -> bool {
+    QUICKTIME_TYPES.contains(self.metadata.file_type.as_str())
+  }
+
+  /// Whether this is a video file, of any known type.
+  pub fn is_video(&self) -> bool {
+    self.is_quicktime() || OTHER_VIDEO_TYPES.contains(self.metadata.file_type.as_str())
+  }
+
+  /// Whether this is a camera RAW file.
+  pub fn is_raw(&self) -> bool {
+    RAW_TYPES.contains(&self.metadata.file_type.as_str())
+  }
+
   /// Returns the `Handle` to the initial (primary) sidecar, if it exists.
   pub fn get_sidecar(&self) -> Option<Handle<SidecarInitial>> {
     self.sidecar
@@ -216,6 +290,11 @@ impl Media {
     self.dupes.iter().copied()
   }
 
+  /// Stores the results of integrity checks for this file.
+  pub fn set_integrity(&mut self, integrity: Integrity) {
+    self.integrity = Some(integrity);
+  }
+
   /// Link a sidecar to this media file by `Handle`.
   pub fn set_sidecar(&mut self, sidecar: Handle<SidecarInitial>) {
     assert!(self.sidecar.is_none());
@@ -281,6 +360,18 @@ mod test_new {
   }
 }
 
+#[cfg(test)]
+mod test_is_raw {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn identifies_raw_types() {
+    assert!(Media::new(metadata!("FileType": "CR2")).unwrap().is_raw());
+    assert!(!Media::new(metadata!("FileType": "JPEG")).unwrap().is_raw());
+  }
+}
+
 #[cfg(test)]
 mod test_get_live_photo_component_type {
   use super::*;
@@ -446,6 +537,75 @@ mod test_get_codec {
 
     assert_eq!(media.get_codec(), Codec::JPEG);
   }
+
+  #[test]
+  fn identifies_quicktime_codecs() {
+    let media = Media::new(metadata!(
+      "SourceFile": "test.mp4",
+      "FileType": "MP4",
+      "CompressorID": "av01",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::AV1);
+
+    let media = Media::new(metadata!(
+      "SourceFile": "test.mp4",
+      "FileType": "MP4",
+      "CompressorID": "avc1",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::AVC);
+
+    let media = Media::new(metadata!(
+      "SourceFile": "test.3gp",
+      "FileType": "3GP",
+      "CompressorID": "s263",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::H263);
+
+    let media = Media::new(metadata!(
+      "SourceFile": "test.mov",
+      "FileType": "MOV",
+      "CompressorID": "apcn",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::ProRes);
+
+    let media = Media::new(metadata!(
+      "SourceFile": "test.mp4",
+      "FileType": "MP4",
+      "CompressorID": "vp09",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::VP9);
+  }
+
+  #[test]
+  fn identifies_other_video_codecs() {
+    let media = Media::new(metadata!(
+      "SourceFile": "test.mts",
+      "FileType": "M2TS",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::AVC);
+
+    let media = Media::new(metadata!(
+      "SourceFile": "test.avi",
+      "FileType": "AVI",
+      "VideoCodec": "H264",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::AVC);
+
+    let media = Media::new(metadata!(
+      "SourceFile": "test.webm",
+      "FileType": "WEBM",
+      "CodecID": "V_VP9",
+    ))
+    .unwrap();
+    assert_eq!(media.get_codec(), Codec::VP9);
+  }
 }
 
 #[cfg(test)]
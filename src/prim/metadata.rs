@@ -4,6 +4,7 @@
 
 use core::fmt;
 use std::{
+  collections::HashMap,
   ffi::OsStr,
   fmt::{Display, Formatter},
   path::{Path, PathBuf},
@@ -12,15 +13,60 @@ use std::{
 use chrono::{FixedOffset, NaiveDateTime};
 use regex::Regex;
 use serde::Deserialize;
+use serde_json::Value;
+
+use super::SidecarNaming;
+
+/// Extensions of files cameras write alongside videos: DJI telemetry subtitles
+/// (`.SRT`), low-resolution proxies (`GoPro` `.LRV`, DJI `.LRF`) and thumbnails
+/// (`.THM`).
+const COMPANION_EXTS: [&str; 4] = ["srt", "lrv", "lrf", "thm"];
+
+/// Gets the value of a tag with a dedicated field in `Metadata`.
+type GetKnownTag = fn(&Metadata) -> Option<&String>;
+
+/// Tags with dedicated fields in `Metadata` (besides `SourceFile`), by their
+/// `ExifTool` name, alongside how to get them.
+const KNOWN_TAGS: [(&str, GetKnownTag); 21] = [
+  ("FileType", |m| Some(&m.file_type)),
+  ("FileTypeExtension", |m| Some(&m.file_type_extension)),
+  ("FileModifyDate", |m| Some(&m.file_modify_date)),
+  ("CompressorID", |m| m.compressor_id.as_ref()),
+  ("ContentIdentifier", |m| m.content_identifier.as_ref()),
+  ("Creator", |m| m.creator.as_ref()),
+  ("Copyright", |m| m.copyright.as_ref()),
+  ("Make", |m| m.make.as_ref()),
+  ("Model", |m| m.model.as_ref()),
+  ("ModifyDate", |m| m.modify_date.as_ref()),
+  ("SubSecModifyDate", |m| m.sub_sec_modify_date.as_ref()),
+  ("CreateDate", |m| m.create_date.as_ref()),
+  ("SubSecCreateDate", |m| m.sub_sec_create_date.as_ref()),
+  ("DateTimeOriginal", |m| m.date_time_original.as_ref()),
+  ("SubSecDateTimeOriginal", |m| {
+    m.sub_sec_date_time_original.as_ref()
+  }),
+  ("GPSLatitude", |m| m.gps_latitude.as_ref()),
+  ("GPSLongitude", |m| m.gps_longitude.as_ref()),
+  ("GPSPosition", |m| m.gps_position.as_ref()),
+  ("City", |m| m.city.as_ref()),
+  ("State", |m| m.state.as_ref()),
+  ("Country", |m| m.country.as_ref()),
+];
 
 /// Represents whether a file is a media file or sidecar, and if a sidecar,
-/// whether the initial (i.e. base or primary) sidecar or a duplicate from
-/// darktable.
+/// whether the initial (i.e. base or primary) sidecar, a duplicate from
+/// darktable, a JSON sidecar from Google Takeout, Apple Photos adjustments, or
+/// a video's companion file. Other files (e.g. JSON not from Takeout) are left
+/// alone.
 #[derive(Debug, PartialEq, Eq)]
 pub enum FileCategory {
   Media,
   SidecarInitial,
   SidecarDupe,
+  Takeout,
+  Adjustments,
+  Companion,
+  Other,
 }
 
 /// Holds the parsed components of a file name, used to determine file type and
@@ -113,9 +159,63 @@ pub struct Metadata {
   pub city:    Option<String>,
   pub state:   Option<String>,
   pub country: Option<String>,
+
+  // All other tags read by ExifTool, for user-defined validation rules.
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
 }
 
 impl Metadata {
+  /// Gets the value of `tag` by its `ExifTool` name, whether or not it has a
+  /// dedicated field. Non-string values are converted to their JSON form.
+  pub fn get(&self, tag: &str) -> Option<String> {
+    if tag == "SourceFile" {
+      return self.source_file.to_str().map(str::to_string);
+    }
+
+    if let Some((_, get_known)) = KNOWN_TAGS.iter().find(|(t, _)| *t == tag) {
+      return get_known(self).cloned();
+    }
+
+    self.extra.get(tag).map(|v| match v {
+      Value::String(s) => s.clone(),
+      v => v.to_string(),
+    })
+  }
+
+  /// Gets the values of `tag` if it is a list (e.g. `Subject`).
+  pub fn get_list(&self, tag: &str) -> Option<Vec<String>> {
+    match self.extra.get(tag)? {
+      Value::Array(a) => Some(
+        a.iter()
+          .map(|v| match v {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+          })
+          .collect(),
+      ),
+      _ => None,
+    }
+  }
+
+  /// Iterates over all tags that have values, alongside their values (see
+  /// `get`).
+  pub fn iter_tags(&self) -> impl Iterator<Item = (&str, String)> {
+    std::iter::once("SourceFile")
+      .chain(KNOWN_TAGS.iter().map(|(tag, _)| *tag))
+      .chain(self.extra.keys().map(String::as_str))
+      .filter_map(|tag| Some((tag, self.get(tag)?)))
+  }
+
+  /// Whether `keyword` is among the keywords (`Subject`).
+  pub fn has_keyword(&self, keyword: &str) -> bool {
+    match self.extra.get("Subject") {
+      Some(Value::String(s)) => s == keyword,
+      Some(Value::Array(a)) => a.iter().any(|v| v.as_str() == Some(keyword)),
+      _ => false,
+    }
+  }
+
   pub fn get_date_time_original(&self) -> Option<(NaiveDateTime, Option<FixedOffset>)> {
     let date_time_original = self
       .sub_sec_date_time_original
@@ -128,14 +228,30 @@ impl Metadata {
   /// Get the type of file this metadata represents.
   pub fn get_file_category(&self) -> FileCategory {
     if self.file_type == "XMP" {
-      if self
-        .parse_file_name()
-        .is_some_and(|f| f.dupe_number.is_some())
+      if SidecarNaming::of(&self.source_file) == SidecarNaming::FullName
+        && self
+          .parse_file_name()
+          .is_some_and(|f| f.dupe_number.is_some())
       {
         FileCategory::SidecarDupe
       } else {
         FileCategory::SidecarInitial
       }
+    } else if self
+      .source_file
+      .extension()
+      .and_then(OsStr::to_str)
+      .is_some_and(|e| COMPANION_EXTS.iter().any(|c| e.eq_ignore_ascii_case(c)))
+    {
+      FileCategory::Companion
+    } else if self.file_type == "JSON" {
+      if self.get("PhotoTakenTimeTimestamp").is_some() {
+        FileCategory::Takeout
+      } else {
+        FileCategory::Other
+      }
+    } else if self.file_type_extension.eq_ignore_ascii_case("aae") {
+      FileCategory::Adjustments
     } else {
       assert!(self.file_type != "-", "FileType is not set.");
       FileCategory::Media
@@ -241,6 +357,70 @@ impl Display for Metadata {
   }
 }
 
+#[cfg(test)]
+mod test_get {
+  use crate::testing::*;
+
+  #[test]
+  fn gets_known_and_extra_tags() {
+    let metadata = metadata!(
+      "Copyright": "Copyright Seth",
+      "Rating": 5,
+      "Software": "darktable",
+    );
+
+    assert_eq!(metadata.get("Copyright").as_deref(), Some("Copyright Seth"));
+    assert_eq!(metadata.get("Rating").as_deref(), Some("5"));
+    assert_eq!(metadata.get("Software").as_deref(), Some("darktable"));
+    assert!(metadata.get("Make").is_none());
+    assert!(metadata.get("Label").is_none());
+  }
+}
+
+#[cfg(test)]
+mod test_get_list {
+  use serde_json::json;
+
+  use crate::testing::*;
+
+  #[test]
+  fn gets_only_lists() {
+    let mut metadata = metadata!(
+      "Creator": "Creator",
+      "Label": "Red",
+    );
+    metadata
+      .extra
+      .insert("Subject".to_string(), json!(["a", "b"]));
+
+    assert_eq!(
+      metadata.get_list("Subject"),
+      Some(vec!["a".to_string(), "b".to_string()])
+    );
+    assert!(metadata.get_list("Label").is_none());
+    assert!(metadata.get_list("Creator").is_none());
+  }
+}
+
+#[cfg(test)]
+mod test_iter_tags {
+  use crate::testing::*;
+
+  #[test]
+  fn iterates_known_and_extra_tags() {
+    let metadata = metadata!(
+      "Creator": "Creator",
+      "Rating": 5,
+    );
+
+    let tags = metadata.iter_tags().collect::<Vec<_>>();
+
+    assert!(tags.contains(&("Creator", "Creator".to_string())));
+    assert!(tags.contains(&("Rating", "5".to_string())));
+    assert!(!tags.iter().any(|(tag, _)| *tag == "Make"));
+  }
+}
+
 #[cfg(test)]
 mod test_get_date_time_original {
   use crate::testing::*;
@@ -314,6 +494,65 @@ mod test_get_file_category {
 
     assert_eq!(metadata.get_file_category(), FileCategory::SidecarInitial);
   }
+
+  #[test]
+  fn identifies_stem_named_sidecar() {
+    let metadata = metadata!(
+      "SourceFile": "image_01.xmp",
+      "FileType": "XMP",
+    );
+
+    assert_eq!(metadata.get_file_category(), FileCategory::SidecarInitial);
+  }
+
+  #[test]
+  fn identifies_takeout() {
+    let metadata = metadata!(
+      "SourceFile": "image.jpg.json",
+      "FileType": "JSON",
+      "PhotoTakenTimeTimestamp": "946684800",
+    );
+
+    assert_eq!(metadata.get_file_category(), FileCategory::Takeout);
+  }
+
+  #[test]
+  fn identifies_other_json() {
+    let metadata = metadata!(
+      "SourceFile": "data.json",
+      "FileType": "JSON",
+    );
+
+    assert_eq!(metadata.get_file_category(), FileCategory::Other);
+  }
+
+  #[test]
+  fn identifies_adjustments() {
+    let metadata = metadata!(
+      "SourceFile": "IMG_1234.AAE",
+      "FileType": "AAE",
+      "FileTypeExtension": "aae",
+    );
+
+    assert_eq!(metadata.get_file_category(), FileCategory::Adjustments);
+  }
+
+  #[test]
+  fn identifies_companions() {
+    let metadata = metadata!(
+      "SourceFile": "GL010123.LRV",
+      "FileType": "MP4",
+      "FileTypeExtension": "mp4",
+    );
+    assert_eq!(metadata.get_file_category(), FileCategory::Companion);
+
+    let metadata = metadata!(
+      "SourceFile": "DJI_0001.srt",
+      "FileType": "TXT",
+      "FileTypeExtension": "txt",
+    );
+    assert_eq!(metadata.get_file_category(), FileCategory::Companion);
+  }
 }
 
 #[cfg(test)]
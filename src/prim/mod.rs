@@ -3,23 +3,41 @@
 //! Primitive types for representing multimedia files and their metadata, and
 //! the relationships between them.
 
+mod burst;
+mod camera;
 mod conv;
+mod copyright;
+mod edit_group;
 mod file_map;
+mod geo;
+mod integrity;
 mod live_photos;
 mod media;
 mod metadata;
 mod sidecar_dupe;
 mod sidecar_initial;
+mod sidecar_naming;
+mod tag_diff;
+mod takeout;
 
 use std::path::PathBuf;
 
+pub use burst::*;
+pub use camera::*;
 pub use conv::*;
+pub use copyright::*;
+pub use edit_group::*;
 pub use file_map::*;
+pub use geo::*;
+pub use integrity::*;
 pub use live_photos::*;
 pub use media::*;
 pub use metadata::*;
 pub use sidecar_dupe::*;
 pub use sidecar_initial::*;
+pub use sidecar_naming::*;
+pub use tag_diff::*;
+pub use takeout::*;
 
 /// Provides a shared interface to both "initial" and "duplicate" sidecars.
 /// <https://docs.darktable.org/usermanual/development/en/overview/sidecar-files/sidecar/>.
@@ -28,7 +46,9 @@ pub trait Sidecar {
   fn get_media_handle(&self) -> Option<Handle<Media>>;
 
   /// Gets the path to the source file for this sidecar.
-  /// This does *not* guarantee the file exists.
+  /// This does *not* guarantee the file exists. Stem-named sidecars (e.g.
+  /// `image.xmp`) do not identify their media file's extension, so are instead
+  /// matched by stem (see `pick_stem_sidecar_media`).
   fn get_media_path(&self) -> PathBuf {
     let parsed_file_name = self.get_metadata().parse_file_name().unwrap();
     PathBuf::from(parsed_file_name.parent_and_stem).with_extension(parsed_file_name.base_ext)
@@ -37,6 +57,11 @@ pub trait Sidecar {
   /// Get the metadata read from this sidecar file.
   fn get_metadata(&self) -> &Metadata;
 
+  /// Gets the scheme this sidecar is named by.
+  fn get_naming(&self) -> SidecarNaming {
+    SidecarNaming::of(&self.get_metadata().source_file)
+  }
+
   /// This file is a "leftover" sidecar file if it no longer has an associated
   /// media file. This generally means that the media file was deleted, and as
   /// such this sidecar should be, too.
@@ -23,13 +23,6 @@ pub struct SidecarDupe {
 impl SidecarDupe {
   /// Create a new duplicate sidecar from the given metadata.
   pub fn new(metadata: Metadata) -> Result<Self, String> {
-    if metadata.get_file_category() != FileCategory::SidecarDupe {
-      return Err(format!(
-        "{metadata}: Invalid sidecar duplicate file type ({}).",
-        metadata.file_type
-      ));
-    }
-
     let parsed_name = metadata.parse_file_name();
 
     if parsed_name.is_none_or(|p| p.base_ext.eq_ignore_ascii_case("xmp")) {
@@ -38,6 +31,13 @@ impl SidecarDupe {
       ));
     }
 
+    if metadata.get_file_category() != FileCategory::SidecarDupe {
+      return Err(format!(
+        "{metadata}: Invalid sidecar duplicate file type ({}).",
+        metadata.file_type
+      ));
+    }
+
     Ok(Self {
       metadata,
       media: None,
@@ -9,7 +9,7 @@ use std::{
   path::Path,
 };
 
-use super::{Handle, Media, Metadata, Sidecar};
+use super::{Handle, Media, Metadata, Sidecar, SidecarNaming};
 use crate::prim::FileCategory;
 
 /// Holds metadata from an XMP sidecar file on disk, and an optional handle to
@@ -17,6 +17,7 @@ use crate::prim::FileCategory;
 pub struct SidecarInitial {
   metadata: Metadata,
   media:    Option<Handle<Media>>,
+  naming:   SidecarNaming,
 }
 
 impl SidecarInitial {
@@ -31,15 +32,25 @@ impl SidecarInitial {
 
     let parsed_name = metadata.parse_file_name();
 
-    if parsed_name.is_none_or(|p| p.base_ext.eq_ignore_ascii_case("xmp")) {
+    if parsed_name.is_none_or(|p| {
+      p.base_ext.eq_ignore_ascii_case("xmp")
+        && SidecarNaming::of(&metadata.source_file) != SidecarNaming::Stem
+    }) {
       return Err(format!("{metadata}: Invalid sidecar file extension."));
     }
 
     Ok(Self {
+      naming: SidecarNaming::of(&metadata.source_file),
       metadata,
       media: None,
     })
   }
+
+  /// Marks this sidecar as stem-named, where its name looked full-name only
+  /// because its stem has a dot (e.g. `2023.01.01.xmp`).
+  pub fn set_stem_named(&mut self) {
+    self.naming = SidecarNaming::Stem;
+  }
 }
 
 impl AsRef<Path> for SidecarInitial {
@@ -63,6 +74,10 @@ impl Sidecar for SidecarInitial {
     &self.metadata
   }
 
+  fn get_naming(&self) -> SidecarNaming {
+    self.naming
+  }
+
   fn set_media_handle(&mut self, media: Handle<Media>) {
     assert!(self.media.is_none());
     self.media = Some(media);
@@ -79,12 +94,22 @@ mod test_new {
   use crate::testing::*;
 
   #[test]
-  fn errors_if_extension_only_xmp() {
+  fn accepts_stem_named() {
     let metadata = metadata!(
       "SourceFile": "image.xmp",
       "FileType": "XMP",
     );
 
+    assert!(SidecarInitial::new(metadata).is_ok());
+  }
+
+  #[test]
+  fn errors_if_extension_only_xmp() {
+    let metadata = metadata!(
+      "SourceFile": "image.xmp.xmp",
+      "FileType": "XMP",
+    );
+
     assert_err!(
       SidecarInitial::new(metadata),
       "Invalid sidecar file extension."
@@ -0,0 +1,155 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Naming schemes for XMP sidecars, which differ between editors.
+
+use std::{
+  ffi::OsStr,
+  path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use super::Media;
+
+/// How sidecars are named after their media file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarNaming {
+  /// The media file's name plus `.xmp` (e.g. `image.jpg.xmp`), as written by
+  /// darktable and digiKam. Duplicates are only supported with this scheme.
+  #[default]
+  #[serde(alias = "darktable", alias = "digikam")]
+  FullName,
+  /// The media file's stem plus `.xmp` (e.g. `image.xmp`), as written by
+  /// Lightroom and Capture One.
+  #[serde(alias = "lightroom", alias = "capture_one")]
+  Stem,
+}
+
+impl SidecarNaming {
+  /// Gets the scheme the sidecar at `path` appears to be named by. Stem-named
+  /// sidecars with a dot in their stem (e.g. `2023.01.01.xmp`) appear
+  /// full-name, so are only known once linked by stem.
+  pub fn of(path: impl AsRef<Path>) -> Self {
+    let path = path.as_ref();
+    let is_xmp = path
+      .extension()
+      .is_some_and(|e| e.eq_ignore_ascii_case("xmp"));
+    let has_media_ext = path
+      .file_stem()
+      .map(Path::new)
+      .and_then(Path::extension)
+      .is_some();
+
+    if is_xmp && !has_media_ext {
+      SidecarNaming::Stem
+    } else {
+      SidecarNaming::FullName
+    }
+  }
+
+  /// Gets the ending of a sidecar for a media file with extension `media_ext`
+  /// (e.g. `.jpg.xmp`).
+  pub fn get_ending(self, media_ext: &str) -> String {
+    match self {
+      SidecarNaming::FullName => format!(".{media_ext}.xmp"),
+      SidecarNaming::Stem => ".xmp".to_string(),
+    }
+  }
+
+  /// Gets the path of the sidecar for the media file at `media`.
+  pub fn get_path(self, media: impl AsRef<Path>) -> PathBuf {
+    let media = media.as_ref();
+    let ext = media.extension().and_then(OsStr::to_str).unwrap_or("");
+
+    let mut path = media.with_extension("").into_os_string();
+    path.push(self.get_ending(ext));
+    PathBuf::from(path)
+  }
+}
+
+/// Picks which of `candidates` (media files sharing a stem) a stem-named
+/// sidecar belongs to: the only one, else a RAW file (as Lightroom and Capture
+/// One only write sidecars for these), else an image, else the first.
+pub fn pick_stem_sidecar_media<'a, T>(
+  candidates: impl IntoIterator<Item = (T, &'a Media)>,
+) -> Option<T> {
+  let mut candidates = candidates.into_iter().collect::<Vec<_>>();
+
+  let index = candidates
+    .iter()
+    .position(|(_, m)| m.is_raw())
+    .or_else(|| candidates.iter().position(|(_, m)| !m.is_video()))
+    .unwrap_or(0);
+
+  (index < candidates.len()).then(|| candidates.swap_remove(index).0)
+}
+
+#[cfg(test)]
+mod test_of {
+  use super::*;
+
+  #[test]
+  fn identifies_schemes() {
+    assert_eq!(
+      SidecarNaming::of("dir/image.jpg.xmp"),
+      SidecarNaming::FullName
+    );
+    assert_eq!(
+      SidecarNaming::of("dir/image_01.jpg.xmp"),
+      SidecarNaming::FullName
+    );
+    assert_eq!(SidecarNaming::of("dir/image.xmp"), SidecarNaming::Stem);
+    assert_eq!(SidecarNaming::of("dir/image_01.XMP"), SidecarNaming::Stem);
+  }
+}
+
+#[cfg(test)]
+mod test_get_path {
+  use super::*;
+
+  #[test]
+  fn names_by_scheme() {
+    assert_eq!(
+      SidecarNaming::FullName.get_path("dir/image.jpg"),
+      PathBuf::from("dir/image.jpg.xmp")
+    );
+    assert_eq!(
+      SidecarNaming::Stem.get_path("dir/image.jpg"),
+      PathBuf::from("dir/image.xmp")
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_pick_stem_sidecar_media {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn prefers_raw() {
+    let jpg = Media::new(metadata!("FileType": "JPEG")).unwrap();
+    let raw = Media::new(metadata!("FileType": "CR2")).unwrap();
+
+    assert_eq!(
+      pick_stem_sidecar_media([("jpg", &jpg), ("raw", &raw)]),
+      Some("raw")
+    );
+  }
+
+  #[test]
+  fn prefers_image_over_video() {
+    let video = Media::new(metadata!("FileType": "MP4")).unwrap();
+    let image = Media::new(metadata!("FileType": "HEIC")).unwrap();
+
+    assert_eq!(
+      pick_stem_sidecar_media([("video", &video), ("image", &image)]),
+      Some("image")
+    );
+  }
+
+  #[test]
+  fn picks_none_without_candidates() {
+    assert_eq!(pick_stem_sidecar_media::<&str>([]), None);
+  }
+}
@@ -0,0 +1,208 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Tag-level differences between metadata, with EXIF, XMP, IPTC and
+//! `QuickTime` group names normalized away.
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::{self, Display, Formatter},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Metadata;
+
+/// Groups (`ExifTool`'s family 1) describing the file rather than its
+/// metadata, or derived from other tags.
+const IGNORED_GROUPS: [&str; 4] = ["ExifTool", "System", "File", "Composite"];
+
+/// Tags describing the file rather than its metadata, which differ between
+/// any two files.
+const IGNORED_TAGS: [&str; 12] = [
+  "SourceFile",
+  "ExifToolVersion",
+  "FileName",
+  "Directory",
+  "FileSize",
+  "FileModifyDate",
+  "FileAccessDate",
+  "FileInodeChangeDate",
+  "FilePermissions",
+  "FileType",
+  "FileTypeExtension",
+  "MIMEType",
+];
+
+/// Tags holding the same information under different names, mapped to the
+/// name compared by. See <https://exiftool.org/TagNames/MWG.html>.
+const TAG_ALIASES: [(&str, &str); 7] = [
+  ("Artist", "Creator"),
+  ("By-line", "Creator"),
+  ("Rights", "Copyright"),
+  ("CopyrightNotice", "Copyright"),
+  ("ImageDescription", "Description"),
+  ("Caption-Abstract", "Description"),
+  ("Keywords", "Subject"),
+];
+
+/// A tag whose value differs between two sets of metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TagDiff {
+  pub tag:    String,
+  pub before: Option<String>,
+  pub after:  Option<String>,
+}
+
+impl Display for TagDiff {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{}: {} -> {}",
+      self.tag,
+      self.before.as_deref().unwrap_or("(none)"),
+      self.after.as_deref().unwrap_or("(none)")
+    )
+  }
+}
+
+/// Gets the name `tag` (optionally prefixed by its group, e.g.
+/// `XMP-dc:Creator`) is compared by, or `None` if it describes the file rather
+/// than its metadata.
+pub fn normalize_tag(tag: &str) -> Option<&str> {
+  let (group, name) = tag.split_once(':').unwrap_or(("", tag));
+
+  if IGNORED_GROUPS.contains(&group) || IGNORED_TAGS.contains(&name) {
+    return None;
+  }
+
+  Some(
+    TAG_ALIASES
+      .iter()
+      .find_map(|(alias, to)| (*alias == name).then_some(*to))
+      .unwrap_or(name),
+  )
+}
+
+/// Normalizes `tags` (see `normalize_tag`). Where several tags normalize to the
+/// same name, differing values are all kept, joined by ` | `.
+pub fn normalize_tags<'a>(
+  tags: impl IntoIterator<Item = (&'a str, String)>,
+) -> BTreeMap<String, String> {
+  let mut values = BTreeMap::<_, BTreeSet<_>>::new();
+
+  for (tag, value) in tags {
+    if let Some(tag) = normalize_tag(tag) {
+      values
+        .entry(tag.to_string())
+        .or_default()
+        .insert(value.trim().to_string());
+    }
+  }
+
+  values
+    .into_iter()
+    .map(|(tag, values)| (tag, values.into_iter().collect::<Vec<_>>().join(" | ")))
+    .collect()
+}
+
+/// Gets the tags differing between `before` and `after`, both normalized (see
+/// `normalize_tags`), ordered by tag.
+pub fn diff_tags(
+  before: &BTreeMap<String, String>,
+  after: &BTreeMap<String, String>,
+) -> Vec<TagDiff> {
+  before
+    .keys()
+    .chain(after.keys())
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .filter(|tag| before.get(*tag) != after.get(*tag))
+    .map(|tag| TagDiff {
+      tag:    tag.clone(),
+      before: before.get(tag).cloned(),
+      after:  after.get(tag).cloned(),
+    })
+    .collect()
+}
+
+/// Gets the tags differing between `before` and `after` (see `diff_tags`).
+pub fn diff_metadata(before: &Metadata, after: &Metadata) -> Vec<TagDiff> {
+  diff_tags(
+    &normalize_tags(before.iter_tags()),
+    &normalize_tags(after.iter_tags()),
+  )
+}
+
+#[cfg(test)]
+mod test_normalize_tags {
+  use super::*;
+
+  #[test]
+  fn merges_groups_and_aliases() {
+    let tags = normalize_tags([
+      ("IFD0:Artist", "Creator".to_string()),
+      ("XMP-dc:Creator", "Creator".to_string()),
+      (
+        "ExifIFD:DateTimeOriginal",
+        "2000-01-01T00:00:00".to_string(),
+      ),
+      (
+        "XMP-exif:DateTimeOriginal",
+        "2000-01-01T00:00:01".to_string(),
+      ),
+      ("System:FileName", "image.jpg".to_string()),
+      ("FileModifyDate", "2000-01-01T00:00:00".to_string()),
+    ]);
+
+    assert_eq!(
+      tags,
+      BTreeMap::from([
+        ("Creator".to_string(), "Creator".to_string()),
+        (
+          "DateTimeOriginal".to_string(),
+          "2000-01-01T00:00:00 | 2000-01-01T00:00:01".to_string()
+        ),
+      ])
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_diff_metadata {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn finds_changed_added_and_removed_tags() {
+    let before = metadata!(
+      "SourceFile": "a.jpg",
+      "Creator": "Creator",
+      "Copyright": "Copyright Creator",
+      "Rating": 1,
+    );
+    let after = metadata!(
+      "SourceFile": "b.jpg",
+      "Creator": "Other",
+      "Rating": 1,
+      "Label": "Red",
+    );
+
+    assert_eq!(diff_metadata(&before, &after), [
+      TagDiff {
+        tag:    "Copyright".to_string(),
+        before: Some("Copyright Creator".to_string()),
+        after:  None,
+      },
+      TagDiff {
+        tag:    "Creator".to_string(),
+        before: Some("Creator".to_string()),
+        after:  Some("Other".to_string()),
+      },
+      TagDiff {
+        tag:    "Label".to_string(),
+        before: None,
+        after:  Some("Red".to_string()),
+      },
+    ]);
+  }
+}
@@ -0,0 +1,274 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Google Takeout JSON sidecars, which hold the metadata Google Photos strips
+//! from its exports.
+
+use std::{path::Path, sync::LazyLock};
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::prim::get_time_zone_name;
+
+/// Suffix newer exports add between the media file name and `.json`. This may
+/// be truncated along with the rest of the name.
+const SUPPLEMENTAL_SUFFIX: &str = ".supplemental-metadata";
+
+/// Matches the copy number Google appends to the whole JSON file name (e.g.
+/// `image.jpg(1).json` for `image(1).jpg`).
+static COPY_NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.*)(\(\d+\))$").unwrap());
+
+/// Contents of a Google Takeout JSON sidecar, as far as needed.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Takeout {
+  pub title:            Option<String>,
+  pub description:      Option<String>,
+  pub photo_taken_time: Option<TakeoutTime>,
+  pub geo_data:         Option<TakeoutGeoData>,
+  pub geo_data_exif:    Option<TakeoutGeoData>,
+}
+
+/// A Takeout timestamp, in seconds since the Unix epoch (as a string).
+#[derive(Deserialize)]
+pub struct TakeoutTime {
+  pub timestamp: String,
+}
+
+/// A Takeout location. Google writes all zeroes when unknown.
+#[derive(Deserialize)]
+pub struct TakeoutGeoData {
+  pub latitude:  f64,
+  pub longitude: f64,
+}
+
+impl Takeout {
+  /// Gets when the media was captured, in the time zone at its location, or
+  /// the computer's time zone if it has no location (or that time zone is
+  /// unknown).
+  pub fn get_date_time(&self) -> Option<DateTime<FixedOffset>> {
+    let timestamp = self.photo_taken_time.as_ref()?.timestamp.parse().ok()?;
+    let date_time = DateTime::<Utc>::from_timestamp(timestamp, 0)?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let time_zone = self.get_lat_lon().and_then(|(lat, lon)| {
+      let name = get_time_zone_name(lat as f32, lon as f32);
+      name
+        .parse::<Tz>()
+        .inspect_err(|_| log::warn!("Unknown time zone `{name}`, using the computer's instead."))
+        .ok()
+    });
+
+    Some(match time_zone {
+      Some(time_zone) => date_time.with_timezone(&time_zone).fixed_offset(),
+      None => date_time.with_timezone(&Local).fixed_offset(),
+    })
+  }
+
+  /// Gets the non-empty description, if any.
+  pub fn get_description(&self) -> Option<&str> {
+    self.description.as_deref().filter(|d| !d.trim().is_empty())
+  }
+
+  /// Gets the latitude and longitude, preferring those edited in Google Photos
+  /// over those from the original file's EXIF.
+  pub fn get_lat_lon(&self) -> Option<(f64, f64)> {
+    [&self.geo_data, &self.geo_data_exif]
+      .into_iter()
+      .flatten()
+      .find(|g| g.latitude != 0.0 || g.longitude != 0.0)
+      .map(|g| (g.latitude, g.longitude))
+  }
+}
+
+/// Finds which of `media` (files in the same directory) the Takeout JSON at
+/// `json` describes. This handles Google's quirks of truncating long file
+/// names, appending copy numbers after the media extension, and the
+/// `.supplemental-metadata` suffix, before falling back to `title`.
+pub fn find_takeout_media<'a>(
+  json: impl AsRef<Path>,
+  title: Option<&str>,
+  media: impl IntoIterator<Item = &'a Path>,
+) -> Option<&'a Path> {
+  let name = json.as_ref().file_name()?.to_str()?.strip_suffix(".json")?;
+
+  let (name, copy) = match COPY_NUMBER.captures(name) {
+    Some(caps) => (caps.get(1)?.as_str(), Some(caps.get(2)?.as_str())),
+    None => (name, None),
+  };
+  let name = strip_supplemental_suffix(name);
+
+  let media = media
+    .into_iter()
+    .filter_map(|p| Some((p, p.file_name()?.to_str()?)))
+    .collect::<Vec<_>>();
+
+  // Exact name, with any copy number moved before the extension.
+  let expected = match (copy, name.rsplit_once('.')) {
+    (Some(copy), Some((stem, ext))) => format!("{stem}{copy}.{ext}"),
+    (Some(copy), None) => format!("{name}{copy}"),
+    (None, _) => name.to_string(),
+  };
+  if let Some((path, _)) = media.iter().find(|(_, m)| *m == expected) {
+    return Some(path);
+  }
+
+  // Truncated name (or missing extension), which must be unambiguous.
+  let truncated = media
+    .iter()
+    .filter(|(_, m)| m.starts_with(name) && get_copy_number(m) == copy)
+    .collect::<Vec<_>>();
+  if let [(path, _)] = truncated[..] {
+    return Some(path);
+  }
+
+  // Original file name, unless a copy (which shares its title).
+  if copy.is_none()
+    && let Some(title) = title
+    && let Some((path, _)) = media.iter().find(|(_, m)| *m == title)
+  {
+    return Some(path);
+  }
+
+  None
+}
+
+/// Gets the copy number (e.g. `(1)`) at the end of a media file name's stem.
+fn get_copy_number(name: &str) -> Option<&str> {
+  let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+  Some(COPY_NUMBER.captures(stem)?.get(2)?.as_str())
+}
+
+/// Removes `SUPPLEMENTAL_SUFFIX`, or any truncated part of it, from `name`.
+fn strip_supplemental_suffix(name: &str) -> &str {
+  name
+    .rmatch_indices('.')
+    .map(|(i, _)| i)
+    .find(|&i| name.len() - i > 1 && SUPPLEMENTAL_SUFFIX.starts_with(&name[i..]))
+    .map_or(name, |i| &name[..i])
+}
+
+#[cfg(test)]
+mod test_find_takeout_media {
+  use super::*;
+
+  fn find<'a>(json: &str, title: Option<&str>, media: &'a [&'a str]) -> Option<&'a Path> {
+    find_takeout_media(json, title, media.iter().map(Path::new))
+  }
+
+  #[test]
+  fn matches_exact_name() {
+    assert_eq!(
+      find("dir/image.jpg.json", None, &[
+        "dir/image.jpg",
+        "dir/other.jpg"
+      ]),
+      Some(Path::new("dir/image.jpg"))
+    );
+  }
+
+  #[test]
+  fn matches_supplemental_suffix() {
+    let media = ["image.jpg"];
+
+    assert_eq!(
+      find("image.jpg.supplemental-metadata.json", None, &media),
+      Some(Path::new("image.jpg"))
+    );
+    assert_eq!(
+      find("image.jpg.suppl.json", None, &media),
+      Some(Path::new("image.jpg"))
+    );
+  }
+
+  #[test]
+  fn matches_copy_number() {
+    let media = ["image.jpg", "image(1).jpg"];
+
+    assert_eq!(
+      find("image.jpg(1).json", Some("image.jpg"), &media),
+      Some(Path::new("image(1).jpg"))
+    );
+    assert_eq!(
+      find(
+        "image.jpg.supplemental-metadata(1).json",
+        Some("image.jpg"),
+        &media
+      ),
+      Some(Path::new("image(1).jpg"))
+    );
+  }
+
+  #[test]
+  fn matches_truncated_name() {
+    let media = [
+      "Screenshot_20190101-120000_Long_Application_Name.jpg",
+      "image.jpg",
+    ];
+
+    assert_eq!(
+      find(
+        "Screenshot_20190101-120000_Long_Applicati.json",
+        None,
+        &media
+      ),
+      Some(Path::new(media[0]))
+    );
+  }
+
+  #[test]
+  fn matches_missing_extension() {
+    assert_eq!(
+      find("image.json", None, &["image.jpg", "image(1).jpg"]),
+      Some(Path::new("image.jpg"))
+    );
+  }
+
+  #[test]
+  fn skips_ambiguous_truncated_name() {
+    assert_eq!(
+      find("image_long_na.json", None, &[
+        "image_long_name_1.jpg",
+        "image_long_name_2.jpg"
+      ]),
+      None
+    );
+  }
+
+  #[test]
+  fn falls_back_to_title() {
+    assert_eq!(
+      find("renamed.json", Some("image.jpg"), &["image.jpg"]),
+      Some(Path::new("image.jpg"))
+    );
+    assert_eq!(find("metadata.json", Some("Album"), &["image.jpg"]), None);
+  }
+}
+
+#[cfg(test)]
+mod test_takeout {
+  use super::*;
+
+  #[test]
+  fn parses_fields() {
+    let takeout = serde_json::from_str::<Takeout>(
+      r#"{
+        "title": "image.jpg",
+        "description": "",
+        "photoTakenTime": { "timestamp": "946684800", "formatted": "Jan 1, 2000" },
+        "geoData": { "latitude": 0.0, "longitude": 0.0, "altitude": 0.0 },
+        "geoDataExif": { "latitude": 47.6, "longitude": -122.3, "altitude": 0.0 }
+      }"#,
+    )
+    .unwrap();
+
+    assert_eq!(takeout.get_description(), None);
+    assert_eq!(takeout.get_lat_lon(), Some((47.6, -122.3)));
+    assert_eq!(
+      takeout.get_date_time().unwrap().to_rfc3339(),
+      "1999-12-31T16:00:00-08:00"
+    );
+  }
+}
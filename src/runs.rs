@@ -0,0 +1,178 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Record of the tags each run changed, stored in `catalog/.c1a/runs/` by run
+//! (e.g. `20250101T120000000.json`), for auditing with `c1a diff --run`, and of
+//! the old values of tags edited by `c1a set` (e.g.
+//! `20250101T120000000.edits.json`), for undoing it.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  io,
+  org::{Report, TagEdit},
+  prim::TagDiff,
+};
+
+/// Location of run records, relative to the catalog root.
+const RUNS_PATH: &str = ".c1a/runs";
+
+/// Tags changed in a single file during a run.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RunFile {
+  /// Path at the start of the run, relative to the scanned directory.
+  pub path:       PathBuf,
+  /// Path at the end of the run, if moved.
+  pub final_path: Option<PathBuf>,
+  pub tag_diffs:  Vec<TagDiff>,
+}
+
+/// Writes the tags changed during the run recorded by `report` to `catalog`,
+/// returning the path written. Runs without tag changes are not recorded.
+pub fn save(catalog: impl AsRef<Path>, report: &Report) -> Result<Option<PathBuf>, String> {
+  let files = report
+    .iter_files()
+    .filter(|f| !f.tag_diffs.is_empty())
+    .map(|f| RunFile {
+      path:       f.path.clone(),
+      final_path: f.final_path.clone(),
+      tag_diffs:  f.tag_diffs.clone(),
+    })
+    .collect::<Vec<_>>();
+
+  if files.is_empty() {
+    return Ok(None);
+  }
+
+  let path = get_path(catalog, report.get_run());
+
+  if path.exists() {
+    return Err(format!("{}: Run record already exists.", path.display()));
+  }
+
+  fs::create_dir_all(path.parent().unwrap())
+    .map_err(|e| format!("{}: Failed to create directory ({e}).", path.display()))?;
+
+  io::write_json(&path, &files)?;
+
+  Ok(Some(path))
+}
+
+/// Loads the tags changed during `run` (e.g. `20250101T120000000`) in
+/// `catalog`.
+pub fn load(catalog: impl AsRef<Path>, run: &str) -> Result<Vec<RunFile>, String> {
+  let path = get_path(catalog, run);
+
+  if !path.exists() {
+    return Err(format!("{run}: No record of tag changes for this run."));
+  }
+
+  io::read_json(path)
+}
+
+/// Writes the old values of tags edited during `run` to `catalog`.
+pub fn save_edits(catalog: impl AsRef<Path>, run: &str, edits: &[TagEdit]) -> Result<(), String> {
+  let path = get_path(catalog, &format!("{run}.edits"));
+
+  fs::create_dir_all(path.parent().unwrap())
+    .map_err(|e| format!("{}: Failed to create directory ({e}).", path.display()))?;
+
+  io::write_json(path, &edits)
+}
+
+/// Loads the old values of tags edited during `run` in `catalog`.
+pub fn load_edits(catalog: impl AsRef<Path>, run: &str) -> Result<Vec<TagEdit>, String> {
+  let path = get_path(catalog, &format!("{run}.edits"));
+
+  if !path.exists() {
+    return Err(format!("{run}: No record of tag edits for this run."));
+  }
+
+  io::read_json(path)
+}
+
+/// Gets the path to the record of `run` in `catalog`.
+fn get_path(catalog: impl AsRef<Path>, run: &str) -> PathBuf {
+  catalog.as_ref().join(RUNS_PATH).join(format!("{run}.json"))
+}
+
+#[cfg(test)]
+mod test_save_edits {
+  use std::collections::BTreeMap;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn saves_and_loads_edits() {
+    let d = test_dir!();
+    let edits = [TagEdit {
+      path: PathBuf::from("image.jpg.xmp"),
+      old:  BTreeMap::from([("Creator".to_string(), None)]),
+    }];
+
+    save_edits(d.root(), "20000101T000000", &edits).unwrap();
+
+    assert_eq!(load_edits(d.root(), "20000101T000000").unwrap(), edits);
+    assert_err!(load_edits(d.root(), "20000101T000001"), "No record");
+  }
+}
+
+#[cfg(test)]
+mod test_save {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn saves_and_loads_tag_changes() {
+    let d = test_dir!();
+
+    let mut report = Report::new(d.root());
+    report.metadata_diff(
+      "image.jpg.xmp",
+      &metadata!("Creator": "Creator"),
+      &metadata!("Creator": "Other"),
+    );
+
+    let path = save(d.root(), &report).unwrap().unwrap();
+    assert!(path.starts_with(d.get_path(RUNS_PATH)));
+
+    let files = load(d.root(), report.get_run()).unwrap();
+    assert_eq!(files, [RunFile {
+      path:       PathBuf::from("image.jpg.xmp"),
+      final_path: None,
+      tag_diffs:  vec![TagDiff {
+        tag:    "Creator".to_string(),
+        before: Some("Creator".to_string()),
+        after:  Some("Other".to_string()),
+      }],
+    }]);
+  }
+
+  #[test]
+  fn rejects_existing_run() {
+    let d = test_dir!();
+
+    let mut report = Report::new(d.root());
+    report.metadata_diff(
+      "image.jpg.xmp",
+      &metadata!("Creator": "Creator"),
+      &metadata!("Creator": "Other"),
+    );
+
+    save(d.root(), &report).unwrap();
+    assert_err!(save(d.root(), &report), "already exists");
+  }
+
+  #[test]
+  fn skips_runs_without_tag_changes() {
+    let d = test_dir!();
+
+    assert_eq!(save(d.root(), &Report::new(d.root())).unwrap(), None);
+    assert_err!(load(d.root(), "20000101T000000"), "No record");
+  }
+}
@@ -0,0 +1,281 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Staging area within the catalog (`catalog/.c1a/staging/`), so imports can
+//! run on copies and leave the source untouched.
+
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{
+  manifest,
+  org::{Action, Report},
+};
+
+/// Location of staging directories, relative to the catalog root. This is
+/// within a hidden directory so `ExifTool` does not scan it during `org`.
+const STAGING_PATH: &str = ".c1a/staging";
+
+/// Copies of files to import, alongside where they came from.
+pub struct Staging {
+  dir:    PathBuf,
+  source: PathBuf,
+  files:  Vec<StagedFile>,
+}
+
+/// A single file copied into staging.
+struct StagedFile {
+  /// Path relative to both the source and staging directories.
+  path:   PathBuf,
+  sha256: String,
+}
+
+impl Staging {
+  /// Copies `files` (relative to `source`) into a new staging directory in
+  /// `catalog`, hardlinking instead where possible. Each copy is verified
+  /// against its source by checksum.
+  ///
+  /// Hardlinks are safe as `ExifTool` writes changes to a new file, which then
+  /// replaces the original link.
+  pub fn create(
+    catalog: impl AsRef<Path>,
+    source: impl AsRef<Path>,
+    files: impl IntoIterator<Item = PathBuf>,
+  ) -> Result<Self, String> {
+    let source = source.as_ref();
+    let dir = catalog
+      .as_ref()
+      .join(STAGING_PATH)
+      .join(Local::now().format("%Y%m%dT%H%M%S%3f").to_string());
+
+    if dir.exists() {
+      return Err(format!(
+        "{}: Staging directory already exists.",
+        dir.display()
+      ));
+    }
+
+    log::info!("{}: Staging files.", dir.display());
+
+    let mut staged = Vec::new();
+
+    for path in files {
+      let src = source.join(&path);
+      let dst = dir.join(&path);
+
+      fs::create_dir_all(dst.parent().unwrap())
+        .map_err(|e| format!("{}: Failed to create directory ({e}).", dst.display()))?;
+
+      if let Err(e) = fs::hard_link(&src, &dst) {
+        log::debug!("{}: Copying, as hardlink failed ({e}).", src.display());
+        fs::copy(&src, &dst)
+          .map_err(|e| format!("{}: Failed to copy into staging ({e}).", src.display()))?;
+      }
+
+      let sha256 = manifest::hash_file(&src)?;
+      if manifest::hash_file(&dst)? != sha256 {
+        return Err(format!(
+          "{}: Staged copy does not match source.",
+          src.display()
+        ));
+      }
+
+      staged.push(StagedFile { path, sha256 });
+    }
+
+    Ok(Self {
+      dir,
+      source: source.to_path_buf(),
+      files: staged,
+    })
+  }
+
+  /// Gets the staging directory, which should be imported from.
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// Removes the staging directory, returning the paths of all files that
+  /// `report` shows were moved into the catalog. If `delete_source`, these are
+  /// also deleted from the source, after verifying the imported files. Files
+  /// changed during the import (e.g. by metadata updates) are verified only to
+  /// exist, while all others must still match their source's checksum.
+  pub fn finish(self, report: &Report, delete_source: bool) -> Result<HashSet<PathBuf>, String> {
+    let imported = self.get_imported(report, delete_source)?;
+
+    if delete_source {
+      for file in &self.files {
+        if !imported.contains(&file.path) {
+          continue;
+        }
+
+        let src = self.source.join(&file.path);
+        log::debug!("{}: Deleting imported source file.", src.display());
+        fs::remove_file(&src)
+          .map_err(|e| format!("{}: Failed to delete source file ({e}).", src.display()))?;
+      }
+
+      log::info!(
+        "{}: Deleted {} imported file(s), leaving {}.",
+        self.source.display(),
+        imported.len(),
+        self.files.len() - imported.len()
+      );
+    }
+
+    fs::remove_dir_all(&self.dir).map_err(|e| {
+      format!(
+        "{}: Failed to remove staging directory ({e}).",
+        self.dir.display()
+      )
+    })?;
+
+    Ok(imported)
+  }
+
+  /// Gets the paths of all staged files that were moved into the catalog,
+  /// optionally verifying that their imported copies are intact.
+  fn get_imported(&self, report: &Report, verify: bool) -> Result<HashSet<PathBuf>, String> {
+    let mut imported = HashSet::new();
+
+    for file in report.iter_files() {
+      let Some(final_path) = &file.final_path else {
+        continue;
+      };
+      let Some(staged) = self.files.iter().find(|f| f.path == file.path) else {
+        continue;
+      };
+
+      imported.insert(staged.path.clone());
+
+      if !verify {
+        continue;
+      }
+
+      let final_path = report.get_source().join(final_path);
+      let changed = !file.tag_changes.is_empty()
+        || file.actions.iter().any(|a| {
+          matches!(
+            a,
+            Action::MetadataUpdated { .. } | Action::MetadataSynced { .. }
+          )
+        });
+
+      if !final_path.exists() {
+        return Err(format!(
+          "{}: Imported file is missing, not deleting source.",
+          final_path.display()
+        ));
+      }
+
+      if !changed && manifest::hash_file(&final_path)? != staged.sha256 {
+        return Err(format!(
+          "{}: Imported file does not match source, not deleting source.",
+          final_path.display()
+        ));
+      }
+    }
+
+    Ok(imported)
+  }
+}
+
+#[cfg(test)]
+mod test_create {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn stages_copies_of_all_files() {
+    let d = test_dir!();
+    fs::create_dir_all(d.get_path("import/DCIM")).unwrap();
+    fs::write(d.get_path("import/DCIM/image.jpg"), "image").unwrap();
+    fs::create_dir(d.get_path("catalog")).unwrap();
+
+    let staging = Staging::create(
+      d.get_path("catalog"),
+      d.get_path("import"),
+      manifest::list_files(d.get_path("import")).unwrap(),
+    )
+    .unwrap();
+
+    assert!(
+      staging
+        .dir()
+        .starts_with(d.get_path("catalog/.c1a/staging"))
+    );
+    assert_eq!(
+      fs::read(staging.dir().join("DCIM/image.jpg")).unwrap(),
+      b"image"
+    );
+  }
+}
+
+#[cfg(test)]
+mod test_finish {
+  use super::*;
+  use crate::testing::*;
+
+  /// Stages `import/` and moves `image.jpg` from staging into `catalog/`,
+  /// returning the staging and a report of the move.
+  fn import(d: &TestDir) -> (Staging, Report) {
+    fs::create_dir(d.get_path("import")).unwrap();
+    fs::write(d.get_path("import/image.jpg"), "image").unwrap();
+    fs::write(d.get_path("import/invalid.jpg"), "invalid").unwrap();
+    fs::create_dir(d.get_path("catalog")).unwrap();
+
+    let staging = Staging::create(
+      d.get_path("catalog"),
+      d.get_path("import"),
+      manifest::list_files(d.get_path("import")).unwrap(),
+    )
+    .unwrap();
+
+    let to = d.get_path("catalog/000101_000000000.jpg");
+    fs::rename(staging.dir().join("image.jpg"), &to).unwrap();
+    let mut report = Report::new(staging.dir());
+    report.action("image.jpg", Action::Moved { to });
+
+    (staging, report)
+  }
+
+  #[test]
+  fn keeps_source_by_default() {
+    let d = test_dir!();
+    let (staging, report) = import(&d);
+    let dir = staging.dir().to_path_buf();
+
+    staging.finish(&report, false).unwrap();
+
+    assert!(d.get_path("import/image.jpg").exists());
+    assert!(!dir.exists());
+  }
+
+  #[test]
+  fn deletes_only_imported_source_files() {
+    let d = test_dir!();
+    let (staging, report) = import(&d);
+
+    let imported = staging.finish(&report, true).unwrap();
+
+    assert_eq!(imported, HashSet::from([PathBuf::from("image.jpg")]));
+    assert!(!d.get_path("import/image.jpg").exists());
+    assert!(d.get_path("import/invalid.jpg").exists());
+  }
+
+  #[test]
+  fn keeps_source_if_import_does_not_match() {
+    let d = test_dir!();
+    let (staging, report) = import(&d);
+    // Replace rather than write through the hardlink to the source.
+    fs::remove_file(d.get_path("catalog/000101_000000000.jpg")).unwrap();
+    fs::write(d.get_path("catalog/000101_000000000.jpg"), "imagf").unwrap();
+
+    assert_err!(staging.finish(&report, true), "does not match source");
+    assert!(d.get_path("import/image.jpg").exists());
+  }
+}
@@ -11,7 +11,7 @@ use std::{
   sync::LazyLock,
 };
 
-use crate::io;
+use crate::{io, trash};
 
 static ASSET_ROOT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::from("assets"));
 static TEST_ROOT: LazyLock<PathBuf> =
@@ -53,8 +53,11 @@ impl TestDir {
     traverse_dir(&self.root, Some(&self.trash))
   }
 
+  /// Gets all files in the trash, excluding its journal.
   pub fn files_trash(&self) -> HashSet<PathBuf> {
-    traverse_dir(&self.trash, None::<&Path>)
+    let mut files = traverse_dir(&self.trash, None::<&Path>);
+    files.remove(&self.trash.join(trash::JOURNAL_NAME));
+    files
   }
 
   pub fn get_path(&self, file: impl AsRef<Path>) -> PathBuf {
@@ -0,0 +1,279 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Journal of files moved to the trash directory, so they can later be listed,
+//! restored or purged.
+
+use std::{
+  fs::{self, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Location of the trash directory, relative to the catalog root.
+pub const TRASH_PATH: &str = ".trash";
+
+/// Name of the journal within the trash directory. This is JSON Lines, so
+/// entries can be appended as files are trashed.
+pub const JOURNAL_NAME: &str = ".journal.jsonl";
+
+/// Why and when a single file was trashed.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TrashEntry {
+  /// Path relative to both the catalog and trash directories.
+  pub path:    PathBuf,
+  pub reason:  String,
+  /// When the `Organizer` run that trashed this started.
+  pub run:     String,
+  pub trashed: DateTime<Utc>,
+}
+
+/// Appends `entry` to the journal in `dir_trash`.
+pub fn record(dir_trash: impl AsRef<Path>, entry: &TrashEntry) -> Result<(), String> {
+  let path = dir_trash.as_ref().join(JOURNAL_NAME);
+
+  let mut line = serde_json::to_string(entry)
+    .map_err(|e| format!("{}: Failed to serialize JSON ({e}).", path.display()))?;
+  line.push('\n');
+
+  OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)
+    .and_then(|mut f| f.write_all(line.as_bytes()))
+    .map_err(|e| format!("{}: Failed to write file ({e}).", path.display()))
+}
+
+/// Reads all entries from the journal in `dir_trash`.
+pub fn read_journal(dir_trash: impl AsRef<Path>) -> Result<Vec<TrashEntry>, String> {
+  let path = dir_trash.as_ref().join(JOURNAL_NAME);
+
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+
+  fs::read_to_string(&path)
+    .map_err(|e| format!("{}: Failed to read file ({e}).", path.display()))?
+    .lines()
+    .filter(|l| !l.trim().is_empty())
+    .map(|l| {
+      serde_json::from_str(l)
+        .map_err(|e| format!("{}: Failed to parse JSON ({e}).", path.display()))
+    })
+    .collect()
+}
+
+/// Replaces the journal in `dir_trash` with `entries`.
+fn write_journal(dir_trash: impl AsRef<Path>, entries: &[TrashEntry]) -> Result<(), String> {
+  let path = dir_trash.as_ref().join(JOURNAL_NAME);
+
+  let mut lines = String::new();
+  for entry in entries {
+    lines.push_str(
+      &serde_json::to_string(entry)
+        .map_err(|e| format!("{}: Failed to serialize JSON ({e}).", path.display()))?,
+    );
+    lines.push('\n');
+  }
+
+  fs::write(&path, lines).map_err(|e| format!("{}: Failed to write file ({e}).", path.display()))
+}
+
+/// Moves trashed files back to their original paths under `catalog`. `target`
+/// is either a run (e.g. `20250101T120000000`), or a path relative to the trash
+/// directory, in which case all trashed files under it are restored. Returns
+/// the paths restored.
+pub fn restore(
+  catalog: impl AsRef<Path>,
+  dir_trash: impl AsRef<Path>,
+  target: &str,
+) -> Result<Vec<PathBuf>, String> {
+  let catalog = catalog.as_ref();
+  let dir_trash = dir_trash.as_ref();
+  let entries = read_journal(dir_trash)?;
+
+  let by_run = entries.iter().any(|e| e.run == target);
+  let (selected, kept): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| {
+    if by_run {
+      e.run == target
+    } else {
+      e.path.starts_with(target)
+    }
+  });
+
+  if selected.is_empty() {
+    return Err(format!("{target}: No trashed files match."));
+  }
+
+  for entry in &selected {
+    let dst = catalog.join(&entry.path);
+    if dst.exists() {
+      return Err(format!(
+        "{}: Cannot restore, as file already exists.",
+        dst.display()
+      ));
+    }
+  }
+
+  for entry in &selected {
+    let src = dir_trash.join(&entry.path);
+    let dst = catalog.join(&entry.path);
+
+    fs::create_dir_all(dst.parent().unwrap())
+      .map_err(|e| format!("{}: Failed to create directory ({e}).", dst.display()))?;
+    fs::rename(&src, &dst)
+      .map_err(|e| format!("{}: Failed to restore file ({e}).", src.display()))?;
+  }
+
+  write_journal(dir_trash, &kept)?;
+
+  Ok(selected.into_iter().map(|e| e.path).collect())
+}
+
+/// Permanently deletes all trashed files trashed before `cutoff`. Returns the
+/// paths deleted.
+pub fn purge(dir_trash: impl AsRef<Path>, cutoff: DateTime<Utc>) -> Result<Vec<PathBuf>, String> {
+  let dir_trash = dir_trash.as_ref();
+
+  let (selected, kept): (Vec<_>, Vec<_>) = read_journal(dir_trash)?
+    .into_iter()
+    .partition(|e| e.trashed < cutoff);
+
+  for entry in &selected {
+    let path = dir_trash.join(&entry.path);
+
+    if path.exists() {
+      fs::remove_file(&path)
+        .map_err(|e| format!("{}: Failed to delete file ({e}).", path.display()))?;
+    }
+  }
+
+  write_journal(dir_trash, &kept)?;
+
+  Ok(selected.into_iter().map(|e| e.path).collect())
+}
+
+/// Parses an age such as `30d`, with units of weeks (`w`), days (`d`) or hours
+/// (`h`).
+pub fn parse_age(age: &str) -> Result<TimeDelta, String> {
+  let invalid = || format!("{age}: Invalid age (expected e.g. `30d`, `2w` or `12h`).");
+
+  let unit = age.chars().last().ok_or_else(invalid)?;
+  let count = age[..age.len() - unit.len_utf8()]
+    .parse::<i64>()
+    .map_err(|_| invalid())?;
+
+  match unit {
+    'w' => TimeDelta::try_weeks(count),
+    'd' => TimeDelta::try_days(count),
+    'h' => TimeDelta::try_hours(count),
+    _ => None,
+  }
+  .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod test_restore {
+  use super::*;
+  use crate::testing::*;
+
+  fn trash(d: &TestDir, path: &str, run: &str) {
+    fs::create_dir_all(d.get_trash(path).parent().unwrap()).unwrap();
+    fs::write(d.get_trash(path), path).unwrap();
+    record(d.trash(), &TrashEntry {
+      path:    PathBuf::from(path),
+      reason:  "test".to_string(),
+      run:     run.to_string(),
+      trashed: Utc::now(),
+    })
+    .unwrap();
+  }
+
+  #[test]
+  fn restores_run() {
+    let d = test_dir!();
+    trash(&d, "dir/a.jpg", "1");
+    trash(&d, "b.jpg", "1");
+    trash(&d, "c.jpg", "2");
+
+    restore(d.root(), d.trash(), "1").unwrap();
+
+    assert!(d.get_path("dir/a.jpg").exists());
+    assert!(d.get_path("b.jpg").exists());
+    assert!(d.get_trash("c.jpg").exists());
+    assert_eq!(read_journal(d.trash()).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn restores_path() {
+    let d = test_dir!();
+    trash(&d, "dir/a.jpg", "1");
+    trash(&d, "b.jpg", "1");
+
+    assert_eq!(restore(d.root(), d.trash(), "dir").unwrap(), vec![
+      PathBuf::from("dir/a.jpg")
+    ]);
+    assert!(d.get_trash("b.jpg").exists());
+  }
+
+  #[test]
+  fn errors_if_destination_exists() {
+    let d = test_dir!();
+    trash(&d, "a.jpg", "1");
+    fs::write(d.get_path("a.jpg"), "new").unwrap();
+
+    assert_err!(
+      restore(d.root(), d.trash(), "a.jpg"),
+      "Cannot restore, as file already exists."
+    );
+    assert!(d.get_trash("a.jpg").exists());
+  }
+}
+
+#[cfg(test)]
+mod test_purge {
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn deletes_only_old_files() {
+    let d = test_dir!();
+    for (path, age) in [("old.jpg", 40), ("new.jpg", 1)] {
+      fs::write(d.get_trash(path), path).unwrap();
+      record(d.trash(), &TrashEntry {
+        path:    PathBuf::from(path),
+        reason:  "test".to_string(),
+        run:     "1".to_string(),
+        trashed: Utc::now() - TimeDelta::days(age),
+      })
+      .unwrap();
+    }
+
+    let purged = purge(d.trash(), Utc::now() - TimeDelta::days(30)).unwrap();
+
+    assert_eq!(purged, vec![PathBuf::from("old.jpg")]);
+    assert!(!d.get_trash("old.jpg").exists());
+    assert!(d.get_trash("new.jpg").exists());
+  }
+}
+
+#[cfg(test)]
+mod test_parse_age {
+  use super::*;
+
+  #[test]
+  fn parses_units() {
+    assert_eq!(parse_age("30d"), Ok(TimeDelta::days(30)));
+    assert_eq!(parse_age("2w"), Ok(TimeDelta::weeks(2)));
+    assert_eq!(parse_age("12h"), Ok(TimeDelta::hours(12)));
+  }
+
+  #[test]
+  fn errors_on_invalid_age() {
+    assert!(parse_age("30").is_err());
+    assert!(parse_age("d").is_err());
+    assert!(parse_age("").is_err());
+  }
+}
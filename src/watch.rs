@@ -0,0 +1,141 @@
+// Copyright 2023-5 Seth Pendergrass. See LICENSE.
+
+//! Monitoring of an inbox directory (e.g. where phones sync to) for new files.
+
+use std::{
+  path::{Component, Path, PathBuf},
+  sync::mpsc::{self, Receiver, RecvTimeoutError},
+  time::Duration,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// An inbox directory being watched for changes.
+pub struct Inbox {
+  dir:      PathBuf,
+  events:   Receiver<notify::Result<Event>>,
+  // Stops watching when dropped.
+  _watcher: RecommendedWatcher,
+}
+
+impl Inbox {
+  /// Starts watching `dir`, recursively.
+  pub fn new(dir: impl AsRef<Path>) -> Result<Self, String> {
+    let dir = dir.as_ref();
+    let (tx, events) = mpsc::channel();
+
+    let mut watcher = notify::recommended_watcher(tx)
+      .map_err(|e| format!("{}: Failed to create watcher ({e}).", dir.display()))?;
+    watcher
+      .watch(dir, RecursiveMode::Recursive)
+      .map_err(|e| format!("{}: Failed to watch directory ({e}).", dir.display()))?;
+
+    Ok(Self {
+      dir: dir.to_path_buf(),
+      events,
+      _watcher: watcher,
+    })
+  }
+
+  /// Blocks until a file in the inbox is created or modified.
+  pub fn wait_for_change(&self) -> Result<(), String> {
+    loop {
+      let event = self
+        .events
+        .recv()
+        .map_err(|_| "Stopped watching inbox.".to_string())?;
+
+      if self.is_relevant(event) {
+        return Ok(());
+      }
+    }
+  }
+
+  /// Blocks until no files in the inbox have been created or modified for
+  /// `settle`, so that files still being written are not imported.
+  pub fn wait_until_quiet(&self, settle: Duration) -> Result<(), String> {
+    loop {
+      match self.events.recv_timeout(settle) {
+        Ok(event) => {
+          if self.is_relevant(event) {
+            log::trace!("{}: Inbox changed, waiting.", self.dir.display());
+          }
+        }
+        Err(RecvTimeoutError::Timeout) => return Ok(()),
+        Err(RecvTimeoutError::Disconnected) => return Err("Stopped watching inbox.".to_string()),
+      }
+    }
+  }
+
+  /// Discards all pending events, such as those caused by importing.
+  pub fn discard_events(&self) {
+    while self.events.try_recv().is_ok() {}
+  }
+
+  /// Whether `event` is a non-hidden file in the inbox being created or
+  /// modified. Hidden files include the inbox's own run reports.
+  fn is_relevant(&self, event: notify::Result<Event>) -> bool {
+    let event = match event {
+      Ok(event) => event,
+      Err(e) => {
+        log::warn!("{}: Watch error ({e}).", self.dir.display());
+        return false;
+      }
+    };
+
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+      && event.paths.iter().any(|p| !is_hidden(&self.dir, p))
+  }
+}
+
+/// Whether `path`, or any directory it is in below `dir`, is hidden.
+fn is_hidden(dir: impl AsRef<Path>, path: impl AsRef<Path>) -> bool {
+  path
+    .as_ref()
+    .strip_prefix(dir)
+    .unwrap_or(path.as_ref())
+    .components()
+    .any(|c| matches!(c, Component::Normal(n) if n.to_string_lossy().starts_with('.')))
+}
+
+#[cfg(test)]
+mod test_is_hidden {
+  use super::*;
+
+  #[test]
+  fn checks_components_below_dir() {
+    assert!(!is_hidden("/home/.inbox", "/home/.inbox/image.jpg"));
+    assert!(is_hidden("/inbox", "/inbox/.c1a_report.json"));
+    assert!(is_hidden("/inbox", "/inbox/.sync/image.jpg"));
+  }
+}
+
+#[cfg(test)]
+mod test_wait {
+  use std::fs;
+
+  use super::*;
+  use crate::testing::*;
+
+  #[test]
+  fn detects_new_files() {
+    let d = test_dir!();
+    let inbox = Inbox::new(d.root()).unwrap();
+
+    fs::write(d.get_path("image.jpg"), "image").unwrap();
+
+    inbox.wait_for_change().unwrap();
+    inbox.wait_until_quiet(Duration::from_millis(50)).unwrap();
+  }
+
+  #[test]
+  fn ignores_hidden_files() {
+    let d = test_dir!();
+    let inbox = Inbox::new(d.root()).unwrap();
+
+    fs::write(d.get_path(".c1a_report.json"), "{}").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    assert!(inbox.events.try_iter().all(|e| !inbox.is_relevant(e)));
+  }
+}